@@ -0,0 +1,50 @@
+//! Check the attachments and usages in the database
+//!
+//! usage: tbcheck [--repair] [user ...]
+//!
+//! Checks the given users or all users and the orphaned usages and prints the reports as JSON.
+//! With `--repair` the usages are recalculated and orphaned usages are deleted.
+
+#![warn(clippy::all)]
+
+use anyhow::bail;
+use tb_domain::{Store, Usage, UserId, UserStore};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+
+    let database_url =
+        std::env::var("DB_URL").unwrap_or("postgres://localhost/tendabike".to_string());
+
+    let mut repair = false;
+    let mut users = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--repair" => repair = true,
+            arg => match arg.parse::<i32>() {
+                Ok(id) => users.push(UserId::from(id)),
+                Err(_) => bail!("usage: tbcheck [--repair] [user ...]"),
+            },
+        }
+    }
+
+    let pool = tb_sqlx::DbPool::new(&database_url).await?;
+    let mut store = pool.begin().await?;
+    tb_domain::reload_types(&mut store).await?;
+
+    if users.is_empty() {
+        users = store.user_ids_get_all().await?;
+    }
+    let mut reports = Vec::new();
+    for user in users {
+        reports.push(user.check(repair, &mut store).await?);
+    }
+    reports.push(Usage::check_orphans(repair, &mut store).await?);
+
+    if repair {
+        store.commit().await?;
+    }
+    println!("{}", serde_json::to_string_pretty(&reports)?);
+    Ok(())
+}
@@ -4,11 +4,18 @@ use crate::appstate::AppState;
 
 mod activity;
 mod attachment;
+mod audit;
+mod batch;
+mod check;
+mod configuration;
+mod gearrule;
 mod part;
 mod service;
 mod serviceplan;
 mod shop;
+mod stock;
 mod types;
+mod usagerule;
 mod user;
 
 pub(super) fn router() -> Router<AppState> {
@@ -19,6 +26,13 @@ pub(super) fn router() -> Router<AppState> {
         .nest("/part", part::router())
         .nest("/part", attachment::router())
         .nest("/service", service::router())
+        .nest("/stock", stock::router())
+        .nest("/usagerule", usagerule::router())
         .nest("/plan", serviceplan::router())
         .nest("/activ", activity::router())
+        .nest("/gearrule", gearrule::router())
+        .nest("/configuration", configuration::router())
+        .nest("/batch", batch::router())
+        .nest("/audit", audit::router())
+        .nest("/check", check::router())
 }
@@ -7,15 +7,25 @@
 /// The module also provides endpoints for managing activity parts, such as
 /// setting a default part and rescanning all parts.
 ///
-/// Finally, the module provides an endpoint for using CSV data to update usage data for activities.
+/// Finally, the module provides an endpoint for using CSV data to update usage data for activities,
+/// one to upload GPX, TCX or FIT files and one to move many activities to another gear.
+///
+/// The activities of the user can be listed page by page, filtered and sorted by the query parameters.
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    body::Bytes,
+    extract::{DefaultBodyLimit, Path, Query, State},
     routing::{delete, get, post},
 };
 
 use crate::{AxumAdmin, DbPool, RequestSession, appstate::AppState, error::ApiResult};
-use tb_domain::{Activity, ActivityId, PartId, Store, Summary};
+use tb_domain::{
+    ActTypeId, Activity, ActivityFilter, ActivityId, ActivityPage, ActivityQuery, PartId, Store,
+    Summary,
+};
+
+/// Activity files of long rides can get big
+const UPLOAD_LIMIT: usize = 32 * 1024 * 1024;
 
 async fn def_part_api(
     user: RequestSession,
@@ -35,6 +45,16 @@ async fn rescan(_u: AxumAdmin, State(store): State<DbPool>) -> ApiResult<()> {
     Ok(Json(()))
 }
 
+/// web interface to list the activities of the user
+async fn list(
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(query): Query<ActivityQuery>,
+) -> ApiResult<ActivityPage> {
+    let mut store = store.begin().await?;
+    Ok(Activity::list(query, &user, &mut store).await.map(Json)?)
+}
+
 /// web interface to read an activity
 async fn act_get(
     user: RequestSession,
@@ -92,10 +112,56 @@ async fn descend(
     Ok(Json(res))
 }
 
+#[derive(serde::Deserialize)]
+struct UploadQuery {
+    gear: Option<PartId>,
+    what: Option<ActTypeId>,
+    name: Option<String>,
+}
+
+/// web interface to upload an activity file
+async fn upload(
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(UploadQuery { gear, what, name }): Query<UploadQuery>,
+    data: Bytes,
+) -> ApiResult<Summary> {
+    let mut store = store.begin().await?;
+    let res = Activity::upload(&data, gear, what, name, &user, &mut store).await?;
+    store.commit().await?;
+    Ok(Json(res))
+}
+
+#[derive(serde::Deserialize)]
+struct Reassign {
+    #[serde(flatten)]
+    filter: ActivityFilter,
+    /// the gear the activities are moved to
+    target: PartId,
+}
+
+/// web interface to move all matching activities to another gear
+async fn reassign(
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Json(Reassign { filter, target }): Json<Reassign>,
+) -> ApiResult<Summary> {
+    let mut store = store.begin().await?;
+    let res = Activity::reassign(filter, target, &user, &mut store).await?;
+    store.commit().await?;
+    Ok(Json(res))
+}
+
 pub(crate) fn router() -> Router<AppState> {
     Router::new()
+        .route("/", get(list))
         .route("/descend", post(descend))
+        .route(
+            "/upload",
+            post(upload).layer(DefaultBodyLimit::max(UPLOAD_LIMIT)),
+        )
         .route("/{id}", delete(act_delete).get(act_get).put(act_put))
         .route("/rescan", get(rescan))
         .route("/defaultgear", post(def_part_api))
+        .route("/reassign", post(reassign))
 }
@@ -5,14 +5,21 @@
 //!
 //! The module defines two async functions `attach_rt` and `detach_rt` that handle the requests to the API endpoints.
 //! The `router` function creates a new router and maps the API endpoints to their respective functions.
+//!
+//! The last attach, detach, dispose or recover request can be undone:
+//! `GET /undo` shows the operation, `POST /undo` undoes it.
 
-use axum::{Json, Router, extract::State, routing::post};
+use axum::{
+    Json, Router,
+    extract::State,
+    routing::{get, post},
+};
 use log::debug;
 use serde::Deserialize;
 use time::OffsetDateTime;
 
 use crate::{DbPool, RequestSession, appstate::AppState, error::ApiResult};
-use tb_domain::{PartId, PartTypeId, Store, Summary};
+use tb_domain::{PartId, PartTypeId, Store, Summary, Undo};
 
 /// Description of an Attach or Detach request
 
@@ -51,6 +58,7 @@ async fn attach_rt(
     let res = tb_domain::attach_assembly(&user, part_id, time, gear, hook, all, &mut store)
         .await
         .map(Json)?;
+    Undo::record("attach", &user, &mut store).await?;
     store.commit().await?;
     Ok(res)
 }
@@ -69,6 +77,7 @@ async fn detach_rt(
     let res = tb_domain::detach_assembly(&user, part_id, time, all, &mut store)
         .await
         .map(Json)?;
+    Undo::record("detach", &user, &mut store).await?;
     store.commit().await?;
     Ok(res)
 }
@@ -96,6 +105,7 @@ async fn dispose_rt(
     let res = tb_domain::dispose_assembly(&user, part, time, all, &mut store)
         .await
         .map(Json)?;
+    Undo::record("dispose", &user, &mut store).await?;
     store.commit().await?;
     Ok(res)
 }
@@ -113,6 +123,19 @@ async fn recover_rt(
     let res = tb_domain::recover_assembly(&user, part, all, &mut store)
         .await
         .map(Json)?;
+    Undo::record("recover", &user, &mut store).await?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn get_undo(user: RequestSession, State(store): State<DbPool>) -> ApiResult<Undo> {
+    let mut store = store.begin().await?;
+    Ok(Undo::get(&user, &mut store).await.map(Json)?)
+}
+
+async fn undo_rt(user: RequestSession, State(store): State<DbPool>) -> ApiResult<Summary> {
+    let mut store = store.begin().await?;
+    let res = Undo::undo(&user, &mut store).await.map(Json)?;
     store.commit().await?;
     Ok(res)
 }
@@ -123,4 +146,5 @@ pub(crate) fn router() -> Router<AppState> {
         .route("/detach", post(detach_rt))
         .route("/dispose", post(dispose_rt))
         .route("/recover", post(recover_rt))
+        .route("/undo", get(get_undo).post(undo_rt))
 }
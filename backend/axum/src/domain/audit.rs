@@ -0,0 +1,81 @@
+//! This file contains the implementation of the `audit` resource endpoints.
+//!
+//! The audit log records all changes to parts, attachments, services, activities, service plans,
+//! stock, usage rules, gear rules, configurations, private part types and notification
+//! preferences, see `tb_domain::AuditEntry`.
+//! The entries are returned newest first. The following endpoints are implemented:
+//!
+//! - `GET /`: retrieves the changes made as the user
+//! - `GET /user/{user}`: retrieves the changes made as another user, admins only
+//! - `GET /part/{part}`: retrieves the changes of a part
+//!
+//! All endpoints accept the query parameters `before` to page through older entries
+//! and `limit` for the number of entries.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    routing::get,
+};
+use serde::Deserialize;
+
+use crate::{DbPool, RequestSession, appstate::AppState, error::ApiResult};
+use tb_domain::{AuditEntry, AuditId, PartId, Session, UserId};
+
+const DEFAULT_LIMIT: i64 = 100;
+
+fn default_limit() -> i64 {
+    DEFAULT_LIMIT
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct AuditQuery {
+    /// only entries older than this one
+    #[serde(default)]
+    before: Option<AuditId>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(own_log))
+        .route("/user/{user}", get(user_log))
+        .route("/part/{part}", get(part_log))
+}
+
+async fn own_log(
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(AuditQuery { before, limit }): Query<AuditQuery>,
+) -> ApiResult<Vec<AuditEntry>> {
+    let mut store = store.begin().await?;
+    let id = user.user_id();
+    Ok(AuditEntry::for_user(id, before, limit, &user, &mut store)
+        .await
+        .map(Json)?)
+}
+
+async fn user_log(
+    Path(id): Path<UserId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(AuditQuery { before, limit }): Query<AuditQuery>,
+) -> ApiResult<Vec<AuditEntry>> {
+    let mut store = store.begin().await?;
+    Ok(AuditEntry::for_user(id, before, limit, &user, &mut store)
+        .await
+        .map(Json)?)
+}
+
+async fn part_log(
+    Path(part): Path<PartId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(AuditQuery { before, limit }): Query<AuditQuery>,
+) -> ApiResult<Vec<AuditEntry>> {
+    let mut store = store.begin().await?;
+    Ok(AuditEntry::for_part(part, before, limit, &user, &mut store)
+        .await
+        .map(Json)?)
+}
@@ -0,0 +1,45 @@
+//! This module contains the implementation of the batch API endpoint.
+//!
+//! - `POST /`: executes a list of operations in one transaction
+//!
+//! With `dry_run` set the operations are executed but the transaction is rolled back,
+//! so the returned summary shows the consequences without changing anything.
+//! A batch can only be undone if it consists of attachments, detachments and disposals.
+
+use axum::{Json, Router, extract::State, routing::post};
+use serde::Deserialize;
+
+use crate::{DbPool, RequestSession, appstate::AppState, error::ApiResult};
+use tb_domain::{Operation, Store, Summary, Undo};
+
+#[derive(Clone, Debug, Deserialize)]
+struct Batch {
+    operations: Vec<Operation>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new().route("/", post(batch))
+}
+
+async fn batch(
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Json(Batch {
+        operations,
+        dry_run,
+    }): Json<Batch>,
+) -> ApiResult<Summary> {
+    let mut store = store.begin().await?;
+    let undoable = operations.iter().all(Operation::undoable);
+    let res = Operation::batch(operations, &user, &mut store).await?;
+    // dropping the transaction rolls it back
+    if !dry_run {
+        if undoable {
+            Undo::record("batch", &user, &mut store).await?;
+        }
+        store.commit().await?;
+    }
+    Ok(Json(res))
+}
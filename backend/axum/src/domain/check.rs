@@ -0,0 +1,56 @@
+//! This file contains the implementation of the `check` resource endpoints.
+//!
+//! The checks find inconsistent attachments and usages. They are restricted to admins.
+//! The following endpoints are implemented:
+//!
+//! - `GET /user/{user}`: reports the inconsistencies of the parts of a user
+//! - `POST /user/{user}`: recalculates the usages of the parts of a user
+//! - `GET /usages`: reports the usages nothing refers to
+//! - `POST /usages`: deletes the usages nothing refers to
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::get,
+};
+
+use crate::{AxumAdmin, DbPool, appstate::AppState, error::ApiResult};
+use tb_domain::{CheckReport, Store, Usage, UserId};
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/user/{user}", get(check_user).post(repair_user))
+        .route("/usages", get(check_usages).post(repair_usages))
+}
+
+async fn check_user(
+    _a: AxumAdmin,
+    Path(user): Path<UserId>,
+    State(pool): State<DbPool>,
+) -> ApiResult<CheckReport> {
+    let mut store = pool.begin().await?;
+    Ok(user.check(false, &mut store).await.map(Json)?)
+}
+
+async fn repair_user(
+    _a: AxumAdmin,
+    Path(user): Path<UserId>,
+    State(pool): State<DbPool>,
+) -> ApiResult<CheckReport> {
+    let mut store = pool.begin().await?;
+    let res = user.check(true, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn check_usages(_a: AxumAdmin, State(pool): State<DbPool>) -> ApiResult<CheckReport> {
+    let mut store = pool.begin().await?;
+    Ok(Usage::check_orphans(false, &mut store).await.map(Json)?)
+}
+
+async fn repair_usages(_a: AxumAdmin, State(pool): State<DbPool>) -> ApiResult<CheckReport> {
+    let mut store = pool.begin().await?;
+    let res = Usage::check_orphans(true, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
@@ -0,0 +1,109 @@
+//! This file contains the implementation of the `configuration` resource endpoints.
+//!
+//! A configuration is a named set of parts for a gear. The following endpoints are implemented:
+//!
+//! - `GET /`: retrieves all configurations of the user
+//! - `POST /`: creates a new configuration
+//! - `PUT /{config}`: updates the name and the parts of a configuration
+//! - `DELETE /{config}`: deletes a configuration
+//! - `POST /{config}/apply`: attaches all parts of the configuration to its gear
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, post, put},
+};
+use http::StatusCode;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    DbPool, RequestSession,
+    appstate::AppState,
+    error::{ApiResult, AppError},
+};
+use tb_domain::{ConfigPart, Configuration, ConfigurationId, PartId, Store, Summary};
+
+#[derive(Clone, Debug, Deserialize)]
+struct NewConfiguration {
+    gear: PartId,
+    name: String,
+    parts: Vec<ConfigPart>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ChangeConfiguration {
+    name: String,
+    parts: Vec<ConfigPart>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct ApplyConfiguration {
+    /// when the parts are attached
+    #[serde(with = "time::serde::rfc3339")]
+    time: OffsetDateTime,
+}
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_configs).post(create_config))
+        .route("/{config}", put(update_config).delete(delete_config))
+        .route("/{config}/apply", post(apply_config))
+}
+
+async fn list_configs(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+) -> ApiResult<Vec<Configuration>> {
+    let mut store = pool.begin().await?;
+    Ok(Configuration::for_user(&user, &mut store).await.map(Json)?)
+}
+
+async fn create_config(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(NewConfiguration { gear, name, parts }): Json<NewConfiguration>,
+) -> Result<(StatusCode, Json<Configuration>), AppError> {
+    let mut store = pool.begin().await?;
+    let config = Configuration::create(gear, name, parts, &user, &mut store).await?;
+    store.commit().await?;
+    Ok((StatusCode::CREATED, Json(config)))
+}
+
+async fn update_config(
+    Path(config): Path<ConfigurationId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(ChangeConfiguration { name, parts }): Json<ChangeConfiguration>,
+) -> ApiResult<Configuration> {
+    let mut store = pool.begin().await?;
+    let res = config
+        .update(name, parts, &user, &mut store)
+        .await
+        .map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn delete_config(
+    Path(config): Path<ConfigurationId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+) -> ApiResult<ConfigurationId> {
+    let mut store = pool.begin().await?;
+    let res = config.delete(&user, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn apply_config(
+    Path(config): Path<ConfigurationId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(ApplyConfiguration { time }): Json<ApplyConfiguration>,
+) -> ApiResult<Summary> {
+    let mut store = pool.begin().await?;
+    let res = config.apply(time, &user, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
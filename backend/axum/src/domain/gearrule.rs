@@ -0,0 +1,100 @@
+//! This file contains the implementation of the `gearrule` resource endpoints.
+//!
+//! Gear rules assign gears to new activities automatically. The following endpoints are implemented:
+//!
+//! - `GET /`: retrieves all gear rules of the user in the order of evaluation
+//! - `POST /`: creates a new gear rule after all existing ones
+//! - `PUT /{rule}`: updates a gear rule
+//! - `DELETE /{rule}`: deletes a gear rule
+//! - `GET /dryrun`: lists the existing activities which would get a different gear by the rules
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, put},
+};
+use http::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    DbPool, RequestSession,
+    appstate::AppState,
+    error::{ApiResult, AppError},
+};
+use tb_domain::{GearMatch, GearReassignment, GearRule, GearRuleId, PartId, Store};
+
+#[derive(Clone, Debug, Deserialize)]
+struct NewGearRule {
+    gear: PartId,
+    #[serde(flatten)]
+    conditions: GearMatch,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ChangeGearRule {
+    position: i32,
+    gear: PartId,
+    #[serde(flatten)]
+    conditions: GearMatch,
+}
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_rules).post(create_rule))
+        .route("/{rule}", put(update_rule).delete(delete_rule))
+        .route("/dryrun", get(dry_run))
+}
+
+async fn list_rules(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<Vec<GearRule>> {
+    let mut store = pool.begin().await?;
+    Ok(GearRule::for_user(&user, &mut store).await.map(Json)?)
+}
+
+async fn create_rule(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(NewGearRule { gear, conditions }): Json<NewGearRule>,
+) -> Result<(StatusCode, Json<GearRule>), AppError> {
+    let mut store = pool.begin().await?;
+    let rule = GearRule::create(gear, conditions, &user, &mut store).await?;
+    store.commit().await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+async fn update_rule(
+    Path(rule): Path<GearRuleId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(ChangeGearRule {
+        position,
+        gear,
+        conditions,
+    }): Json<ChangeGearRule>,
+) -> ApiResult<GearRule> {
+    let mut store = pool.begin().await?;
+    let res = rule
+        .update(position, gear, conditions, &user, &mut store)
+        .await
+        .map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn delete_rule(
+    Path(rule): Path<GearRuleId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+) -> ApiResult<GearRuleId> {
+    let mut store = pool.begin().await?;
+    let res = rule.delete(&user, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn dry_run(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+) -> ApiResult<Vec<GearReassignment>> {
+    let mut store = pool.begin().await?;
+    Ok(GearRule::dry_run(&user, &mut store).await.map(Json)?)
+}
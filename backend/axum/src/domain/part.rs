@@ -23,7 +23,7 @@ use std::collections::HashSet;
 
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::{get, post},
 };
 use http::StatusCode;
@@ -35,7 +35,10 @@ use crate::{
     error::{ApiResult, AppError},
 };
 use serde_with::serde_as;
-use tb_domain::{Part, PartId, PartTypeId, Store};
+use tb_domain::{
+    AssemblyChange, AssemblyNode, Bucket, Money, Part, PartCost, PartId, PartTypeId, Store,
+    Summary, TimelineEntry, Usage, UsagePoint,
+};
 use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 
 #[serde_as]
@@ -52,6 +55,9 @@ pub struct NewPart {
     pub purchase: OffsetDateTime,
     /// Notes about the part
     pub notes: String,
+    /// The purchase price
+    #[serde(default)]
+    pub price: Option<Money>,
 }
 
 #[serde_as]
@@ -66,6 +72,49 @@ pub struct ChangePart {
     pub purchase: OffsetDateTime,
     /// Notes about the part
     pub notes: String,
+    /// The purchase price, null removes it, a missing price keeps it
+    #[serde(default, with = "::serde_with::rust::double_option")]
+    pub price: Option<Option<Money>>,
+}
+
+#[derive(serde::Deserialize)]
+struct UsageQuery {
+    /// defaults to now
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    at: Option<OffsetDateTime>,
+}
+
+#[derive(serde::Deserialize)]
+struct SeriesQuery {
+    #[serde(default)]
+    bucket: Bucket,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    from: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    to: Option<OffsetDateTime>,
+}
+
+#[derive(serde::Deserialize)]
+struct TimelineQuery {
+    #[serde(default)]
+    bucket: Bucket,
+}
+
+#[derive(serde::Deserialize)]
+struct Duplicate {
+    /// the name of the new gear
+    name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    time: OffsetDateTime,
+}
+
+#[derive(serde::Deserialize)]
+struct DiffQuery {
+    #[serde(with = "time::serde::rfc3339")]
+    from: OffsetDateTime,
+    /// defaults to now
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    to: Option<OffsetDateTime>,
 }
 
 pub(super) fn router() -> Router<AppState> {
@@ -73,6 +122,13 @@ pub(super) fn router() -> Router<AppState> {
         .route("/", post(post_part))
         .route("/{part}", get(get_part).put(put_part).delete(delete_part))
         .route("/categories", get(mycats))
+        .route("/cost", get(cost_report))
+        .route("/{part}/usage", get(usage_at))
+        .route("/{part}/usage/series", get(usage_series))
+        .route("/{part}/assembly", get(assembly))
+        .route("/{part}/assembly/diff", get(assembly_diff))
+        .route("/{part}/timeline", get(timeline))
+        .route("/{part}/duplicate", post(duplicate))
 }
 
 async fn get_part(
@@ -94,11 +150,12 @@ async fn post_part(
         model,
         purchase,
         notes,
+        price,
     }): Json<NewPart>,
 ) -> Result<(StatusCode, Json<Part>), AppError> {
     let mut store = store.begin().await?;
     let part = Part::create(
-        name, vendor, model, what, None, purchase, notes, &user, &mut store,
+        name, vendor, model, what, None, purchase, notes, price, &user, &mut store,
     )
     .await?;
     store.commit().await?;
@@ -126,12 +183,15 @@ async fn put_part(
         model,
         purchase,
         notes,
+        price,
     }): Json<ChangePart>,
 ) -> ApiResult<Part> {
     let mut store = store.begin().await?;
 
     let res = part
-        .change(name, vendor, model, purchase, notes, &user, &mut store)
+        .change(
+            name, vendor, model, purchase, notes, price, &user, &mut store,
+        )
         .await
         .map(Json)?;
     store.commit().await?;
@@ -145,3 +205,82 @@ async fn mycats(
     let mut store = store.begin().await?;
     Ok(Part::categories(&user, &mut store).await.map(Json)?)
 }
+
+async fn cost_report(
+    user: RequestSession,
+    State(store): State<DbPool>,
+) -> ApiResult<Vec<PartCost>> {
+    let mut store = store.begin().await?;
+    Ok(Part::cost_report(&user, &mut store).await.map(Json)?)
+}
+
+async fn usage_at(
+    Path(part): Path<PartId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(UsageQuery { at }): Query<UsageQuery>,
+) -> ApiResult<Usage> {
+    let mut store = store.begin().await?;
+    let at = at.unwrap_or_else(OffsetDateTime::now_utc);
+    Ok(part.usage_at(at, &user, &mut store).await.map(Json)?)
+}
+
+async fn usage_series(
+    Path(part): Path<PartId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(SeriesQuery { bucket, from, to }): Query<SeriesQuery>,
+) -> ApiResult<Vec<UsagePoint>> {
+    let mut store = store.begin().await?;
+    Ok(part
+        .usage_series(bucket, from, to, &user, &mut store)
+        .await
+        .map(Json)?)
+}
+
+async fn assembly(
+    Path(gear): Path<PartId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(UsageQuery { at }): Query<UsageQuery>,
+) -> ApiResult<AssemblyNode> {
+    let mut store = store.begin().await?;
+    let at = at.unwrap_or_else(OffsetDateTime::now_utc);
+    Ok(gear.assembly(at, &user, &mut store).await.map(Json)?)
+}
+
+async fn assembly_diff(
+    Path(gear): Path<PartId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(DiffQuery { from, to }): Query<DiffQuery>,
+) -> ApiResult<Vec<AssemblyChange>> {
+    let mut store = store.begin().await?;
+    let to = to.unwrap_or_else(OffsetDateTime::now_utc);
+    Ok(gear
+        .assembly_diff(from, to, &user, &mut store)
+        .await
+        .map(Json)?)
+}
+
+async fn timeline(
+    Path(part): Path<PartId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Query(TimelineQuery { bucket }): Query<TimelineQuery>,
+) -> ApiResult<Vec<TimelineEntry>> {
+    let mut store = store.begin().await?;
+    Ok(part.timeline(bucket, &user, &mut store).await.map(Json)?)
+}
+
+async fn duplicate(
+    Path(part): Path<PartId>,
+    user: RequestSession,
+    State(store): State<DbPool>,
+    Json(Duplicate { name, time }): Json<Duplicate>,
+) -> Result<(StatusCode, Json<Summary>), AppError> {
+    let mut store = store.begin().await?;
+    let res = part.duplicate(name, time, &user, &mut store).await?;
+    store.commit().await?;
+    Ok((StatusCode::CREATED, Json(res)))
+}
@@ -30,7 +30,7 @@ use serde_derive::Deserialize;
 use time::OffsetDateTime;
 
 use crate::{ApiResult, DbPool, RequestSession, appstate::AppState, error::AppError};
-use tb_domain::{PartId, Service, ServiceId, ServicePlanId, Store, Summary};
+use tb_domain::{Money, PartId, Service, ServiceId, ServicePlanId, Store, Summary};
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
@@ -47,6 +47,8 @@ struct NewService {
     name: String,
     notes: String,
     plans: Vec<ServicePlanId>,
+    #[serde(default)]
+    cost: Option<Money>,
 }
 async fn create(
     user: RequestSession,
@@ -57,11 +59,15 @@ async fn create(
         name,
         notes,
         plans,
+        cost,
     }): Json<NewService>,
 ) -> Result<(StatusCode, Json<Summary>), AppError> {
     let mut store = store.begin().await?;
     part_id.checkuser(&user, &mut store).await?;
-    let summary = Service::create(part_id, time, name, notes, None, plans, &mut store).await?;
+    let summary = Service::create(
+        part_id, time, name, notes, None, plans, cost, &user, &mut store,
+    )
+    .await?;
     store.commit().await?;
     Ok((StatusCode::CREATED, Json(summary)))
 }
@@ -22,19 +22,23 @@
 
 use axum::{
     Json, Router,
-    extract::{Path, State},
-    routing::{delete, post},
+    extract::{Path, Query, State},
+    routing::{delete, get},
 };
 use http::StatusCode;
 use log::trace;
 
 use crate::{ApiResult, DbPool, RequestSession, appstate::AppState, error::AppError};
-use tb_domain::{Service, ServicePlan, ServicePlanId, Store};
+use tb_domain::{
+    FORECAST_WEEKS, PlanForecast, PlanStatus, Service, ServicePlan, ServicePlanId, Store,
+};
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
-        .route("/", post(create).put(update))
+        .route("/", get(status).post(create).put(update))
         .route("/{id}", delete(delete_plan))
+        .route("/{id}/status", get(plan_status))
+        .route("/{id}/forecast", get(forecast))
 }
 
 async fn create(
@@ -70,3 +74,40 @@ async fn delete_plan(
     store.commit().await?;
     Ok(res)
 }
+
+/// the status of all service plans of the user
+async fn status(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<Vec<PlanStatus>> {
+    let mut store = pool.begin().await?;
+    Ok(PlanStatus::for_user(&user, &mut store).await.map(Json)?)
+}
+
+/// the status of the plan for every part it applies to
+async fn plan_status(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Path(id): Path<ServicePlanId>,
+) -> ApiResult<Vec<PlanStatus>> {
+    let mut store = pool.begin().await?;
+    Ok(id.status(&user, &mut store).await.map(Json)?)
+}
+
+#[derive(serde::Deserialize)]
+struct ForecastQuery {
+    weeks: Option<u32>,
+}
+
+/// forecast when the plan will be due based on the usage of the last weeks
+async fn forecast(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Path(id): Path<ServicePlanId>,
+    Query(ForecastQuery { weeks }): Query<ForecastQuery>,
+) -> ApiResult<Vec<PlanForecast>> {
+    let mut store = pool.begin().await?;
+    let res = id
+        .forecast(weeks.unwrap_or(FORECAST_WEEKS), &user, &mut store)
+        .await
+        .map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
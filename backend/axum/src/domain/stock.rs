@@ -0,0 +1,186 @@
+//! This file contains the implementation of the `stock` resource endpoints.
+//!
+//! The `stock` resource holds the spare parts of a user. The following endpoints are implemented:
+//!
+//! - `GET /`: retrieves all stock of the user
+//! - `POST /`: creates a new stock
+//! - `PUT /{stock}`: updates a stock
+//! - `DELETE /{stock}`: deletes a stock
+//! - `POST /{stock}/attach`: creates a part from the stock and attaches it to a gear
+//! - `GET /warnings`: retrieves all stock which is running low
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, post, put},
+};
+use http::StatusCode;
+use serde::Deserialize;
+use time::OffsetDateTime;
+
+use crate::{
+    DbPool, RequestSession,
+    appstate::AppState,
+    error::{ApiResult, AppError},
+};
+use tb_domain::{Money, PartId, PartTypeId, Stock, StockId, StockWarning, Store, Summary};
+
+#[derive(Clone, Debug, Deserialize)]
+struct NewStock {
+    what: PartTypeId,
+    #[serde(default)]
+    hook: Option<PartTypeId>,
+    name: String,
+    #[serde(default)]
+    vendor: String,
+    #[serde(default)]
+    model: String,
+    quantity: i32,
+    #[serde(default)]
+    min_quantity: i32,
+    #[serde(default)]
+    price: Option<Money>,
+    #[serde(default)]
+    notes: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ChangeStock {
+    #[serde(default)]
+    hook: Option<PartTypeId>,
+    name: String,
+    vendor: String,
+    model: String,
+    quantity: i32,
+    min_quantity: i32,
+    #[serde(default)]
+    price: Option<Money>,
+    notes: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct AttachStock {
+    /// when the part is attached
+    #[serde(with = "time::serde::rfc3339")]
+    time: OffsetDateTime,
+    /// the gear the part will be attached to
+    gear: PartId,
+    /// the hook on that gear
+    hook: PartTypeId,
+}
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_stock).post(create_stock))
+        .route("/{stock}", put(update_stock).delete(delete_stock))
+        .route("/{stock}/attach", post(attach_stock))
+        .route("/warnings", get(warnings))
+}
+
+async fn list_stock(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<Vec<Stock>> {
+    let mut store = pool.begin().await?;
+    Ok(Stock::for_user(&user, &mut store).await.map(Json)?)
+}
+
+async fn create_stock(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(NewStock {
+        what,
+        hook,
+        name,
+        vendor,
+        model,
+        quantity,
+        min_quantity,
+        price,
+        notes,
+    }): Json<NewStock>,
+) -> Result<(StatusCode, Json<Stock>), AppError> {
+    let mut store = pool.begin().await?;
+    let stock = Stock::create(
+        what,
+        hook,
+        name,
+        vendor,
+        model,
+        quantity,
+        min_quantity,
+        price,
+        notes,
+        &user,
+        &mut store,
+    )
+    .await?;
+    store.commit().await?;
+    Ok((StatusCode::CREATED, Json(stock)))
+}
+
+async fn update_stock(
+    Path(stock): Path<StockId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(ChangeStock {
+        hook,
+        name,
+        vendor,
+        model,
+        quantity,
+        min_quantity,
+        price,
+        notes,
+    }): Json<ChangeStock>,
+) -> ApiResult<Stock> {
+    let mut store = pool.begin().await?;
+    let res = stock
+        .update(
+            hook,
+            name,
+            vendor,
+            model,
+            quantity,
+            min_quantity,
+            price,
+            notes,
+            &user,
+            &mut store,
+        )
+        .await
+        .map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn delete_stock(
+    Path(stock): Path<StockId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+) -> ApiResult<StockId> {
+    let mut store = pool.begin().await?;
+    let res = stock.delete(&user, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn attach_stock(
+    Path(stock): Path<StockId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(AttachStock { time, gear, hook }): Json<AttachStock>,
+) -> ApiResult<Summary> {
+    let mut store = pool.begin().await?;
+    let res = stock
+        .attach(time, gear, hook, &user, &mut store)
+        .await
+        .map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn warnings(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+) -> ApiResult<Vec<StockWarning>> {
+    let mut store = pool.begin().await?;
+    Ok(Stock::warnings(&user, &mut store).await.map(Json)?)
+}
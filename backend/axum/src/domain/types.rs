@@ -1,12 +1,23 @@
 //! This module contains types and functions related to handling activity and part types in the server.
 //!
-//! The `activity` and `part` functions are used to retrieve all activity and part types from the database.
+//! The `activity` and `part` functions are used to retrieve all activity and part types from the cached catalog.
+//! Admins can create, change and delete types of the global catalog, users can maintain their private part types.
+//! After every change the cached catalog gets reloaded.
 //! The `router` function is used to create a router that handles requests related to activity and part types.
 
-use axum::{Json, Router, routing::get};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, post, put},
+};
+use http::StatusCode;
 
-use crate::{appstate::AppState, error::ApiResult};
-use tb_domain::{ActivityType, PartType};
+use crate::{
+    AxumAdmin, DbPool, RequestSession,
+    appstate::AppState,
+    error::{ApiResult, AppError},
+};
+use tb_domain::{ActTypeId, ActivityType, PartType, PartTypeId, Session, Store};
 
 // get all activity types
 async fn activity() -> ApiResult<Vec<ActivityType>> {
@@ -14,12 +25,163 @@ async fn activity() -> ApiResult<Vec<ActivityType>> {
 }
 
 /// get all part types
-async fn part() -> ApiResult<Vec<PartType>> {
-    Ok(Json(PartType::all_ordered()))
+///
+/// includes the private types if the user is logged in
+async fn part(user: Option<RequestSession>) -> ApiResult<Vec<PartType>> {
+    Ok(Json(match user {
+        Some(user) => PartType::for_user(user.user_id()),
+        None => PartType::all_ordered(),
+    }))
+}
+
+/// reload the cached catalog after a change was committed
+async fn reload(pool: &DbPool) -> Result<(), AppError> {
+    let mut store = pool.begin().await?;
+    tb_domain::reload_types(&mut store).await?;
+    Ok(())
+}
+
+async fn create_part(
+    user: Option<&dyn Session>,
+    pool: DbPool,
+    parttype: PartType,
+) -> Result<(StatusCode, Json<PartType>), AppError> {
+    let mut store = pool.begin().await?;
+    let res = parttype.create(user, &mut store).await?;
+    store.commit().await?;
+    reload(&pool).await?;
+    Ok((StatusCode::CREATED, Json(res)))
+}
+
+async fn update_part(
+    user: Option<&dyn Session>,
+    pool: DbPool,
+    id: PartTypeId,
+    parttype: PartType,
+) -> ApiResult<PartType> {
+    if id != parttype.id {
+        Err(tb_domain::Error::BadRequest(
+            "PartTypeId does not match part type".to_string(),
+        ))?
+    }
+    let mut store = pool.begin().await?;
+    let res = parttype.update(user, &mut store).await?;
+    store.commit().await?;
+    reload(&pool).await?;
+    Ok(Json(res))
+}
+
+async fn delete_part(
+    user: Option<&dyn Session>,
+    pool: DbPool,
+    id: PartTypeId,
+) -> ApiResult<PartTypeId> {
+    let mut store = pool.begin().await?;
+    let res = id.delete(user, &mut store).await?;
+    store.commit().await?;
+    reload(&pool).await?;
+    Ok(Json(res))
+}
+
+async fn part_post(
+    _a: AxumAdmin,
+    State(pool): State<DbPool>,
+    Json(parttype): Json<PartType>,
+) -> Result<(StatusCode, Json<PartType>), AppError> {
+    create_part(None, pool, parttype).await
+}
+
+async fn part_put(
+    _a: AxumAdmin,
+    State(pool): State<DbPool>,
+    Path(id): Path<PartTypeId>,
+    Json(parttype): Json<PartType>,
+) -> ApiResult<PartType> {
+    update_part(None, pool, id, parttype).await
+}
+
+async fn part_delete(
+    _a: AxumAdmin,
+    State(pool): State<DbPool>,
+    Path(id): Path<PartTypeId>,
+) -> ApiResult<PartTypeId> {
+    delete_part(None, pool, id).await
+}
+
+/// create a private part type for the user
+async fn mine_post(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(parttype): Json<PartType>,
+) -> Result<(StatusCode, Json<PartType>), AppError> {
+    create_part(Some(&user), pool, parttype).await
+}
+
+async fn mine_put(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Path(id): Path<PartTypeId>,
+    Json(parttype): Json<PartType>,
+) -> ApiResult<PartType> {
+    update_part(Some(&user), pool, id, parttype).await
+}
+
+async fn mine_delete(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Path(id): Path<PartTypeId>,
+) -> ApiResult<PartTypeId> {
+    delete_part(Some(&user), pool, id).await
+}
+
+async fn activity_post(
+    _a: AxumAdmin,
+    State(pool): State<DbPool>,
+    Json(acttype): Json<ActivityType>,
+) -> Result<(StatusCode, Json<ActivityType>), AppError> {
+    let mut store = pool.begin().await?;
+    let res = acttype.create(&mut store).await?;
+    store.commit().await?;
+    reload(&pool).await?;
+    Ok((StatusCode::CREATED, Json(res)))
+}
+
+async fn activity_put(
+    _a: AxumAdmin,
+    State(pool): State<DbPool>,
+    Path(id): Path<ActTypeId>,
+    Json(acttype): Json<ActivityType>,
+) -> ApiResult<ActivityType> {
+    if id != acttype.id {
+        Err(tb_domain::Error::BadRequest(
+            "ActTypeId does not match activity type".to_string(),
+        ))?
+    }
+    let mut store = pool.begin().await?;
+    let res = acttype.update(&mut store).await?;
+    store.commit().await?;
+    reload(&pool).await?;
+    Ok(Json(res))
+}
+
+async fn activity_delete(
+    _a: AxumAdmin,
+    State(pool): State<DbPool>,
+    Path(id): Path<ActTypeId>,
+) -> ApiResult<ActTypeId> {
+    let mut store = pool.begin().await?;
+    let res = id.delete(&mut store).await?;
+    store.commit().await?;
+    reload(&pool).await?;
+    Ok(Json(res))
 }
 
 pub(super) fn router() -> Router<AppState> {
     Router::new()
-        .route("/part", get(part))
-        .route("/activity", get(activity))
+        .route("/part", get(part).post(part_post))
+        .route("/part/{id}", put(part_put).delete(part_delete))
+        .route("/mine", post(mine_post))
+        .route("/mine/{id}", put(mine_put).delete(mine_delete))
+        .route("/activity", get(activity).post(activity_post))
+        .route("/activity/{id}", put(activity_put).delete(activity_delete))
 }
@@ -0,0 +1,80 @@
+//! This file contains the implementation of the `usagerule` resource endpoints.
+//!
+//! Usage rules define how activities count for the parts on a hook. The following endpoints are implemented:
+//!
+//! - `GET /`: retrieves all usage rules of the user
+//! - `POST /`: creates a new usage rule
+//! - `PUT /{rule}`: changes the factor of a usage rule
+//! - `DELETE /{rule}`: deletes a usage rule
+
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    routing::{get, put},
+};
+use http::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    DbPool, RequestSession,
+    appstate::AppState,
+    error::{ApiResult, AppError},
+};
+use tb_domain::{ActTypeId, PartTypeId, Store, UsageRule, UsageRuleId};
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct NewUsageRule {
+    what: ActTypeId,
+    hook: PartTypeId,
+    factor: f64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+struct ChangeUsageRule {
+    factor: f64,
+}
+
+pub(super) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_rules).post(create_rule))
+        .route("/{rule}", put(update_rule).delete(delete_rule))
+}
+
+async fn list_rules(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<Vec<UsageRule>> {
+    let mut store = pool.begin().await?;
+    Ok(UsageRule::for_user(&user, &mut store).await.map(Json)?)
+}
+
+async fn create_rule(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(NewUsageRule { what, hook, factor }): Json<NewUsageRule>,
+) -> Result<(StatusCode, Json<UsageRule>), AppError> {
+    let mut store = pool.begin().await?;
+    let rule = UsageRule::create(what, hook, factor, &user, &mut store).await?;
+    store.commit().await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+async fn update_rule(
+    Path(rule): Path<UsageRuleId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(ChangeUsageRule { factor }): Json<ChangeUsageRule>,
+) -> ApiResult<UsageRule> {
+    let mut store = pool.begin().await?;
+    let res = rule.update(factor, &user, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
+async fn delete_rule(
+    Path(rule): Path<UsageRuleId>,
+    user: RequestSession,
+    State(pool): State<DbPool>,
+) -> ApiResult<UsageRuleId> {
+    let mut store = pool.begin().await?;
+    let res = rule.delete(&user, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
@@ -1,6 +1,7 @@
 //! This module contains the implementation of user-related routes and handlers for the Axum web framework.
 //!
 //! The routes in this module are used to retrieve user information, summaries, and lists of users.
+//! The summary accepts the cursor of an earlier summary as `since` and then only returns the changes.
 //! The handlers in this module interact with the database and Strava API to retrieve and process user data.
 //!
 //! This module also defines the `RUser` struct, which represents a user in the system and is used throughout the module.
@@ -8,21 +9,28 @@
 
 use axum::{
     Json, Router,
-    extract::{Query, State},
-    routing::get,
+    extract::{DefaultBodyLimit, Query, State},
+    routing::{get, post},
 };
-use serde::Serialize;
 
 use crate::{ApiResult, AxumAdmin, DbPool, RequestSession, appstate::AppState};
-use tb_domain::{Session, ShopId, Store, Summary};
+use tb_domain::{Export, NotifyPrefs, Session, ShopId, Store, Summary, SyncCursor, SyncSummary};
 use tb_strava::StravaUser;
 
+/// Exports of long time users get big
+const IMPORT_LIMIT: usize = 64 * 1024 * 1024;
+
 pub(super) fn router() -> Router<AppState> {
     Router::new()
         .route("/", get(getuser))
         .route("/summary", get(summary))
         .route("/all", get(userlist))
         .route("/export", get(export))
+        .route(
+            "/import",
+            post(import).layer(DefaultBodyLimit::max(IMPORT_LIMIT)),
+        )
+        .route("/notify", get(get_notify).put(put_notify))
 }
 
 async fn getuser(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<tb_domain::User> {
@@ -30,63 +38,78 @@ async fn getuser(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<
     Ok(user.user_id().read(&mut store).await.map(Json)?)
 }
 
+/// get the notification preferences of the user
+async fn get_notify(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<NotifyPrefs> {
+    let mut store = pool.begin().await?;
+    Ok(NotifyPrefs::get(&user, &mut store).await.map(Json)?)
+}
+
+/// store the notification preferences of the user
+async fn put_notify(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(prefs): Json<NotifyPrefs>,
+) -> ApiResult<NotifyPrefs> {
+    let mut store = pool.begin().await?;
+    let res = prefs.set(&user, &mut store).await.map(Json)?;
+    store.commit().await?;
+    Ok(res)
+}
+
 #[derive(serde::Deserialize)]
-struct ShopQuery {
+struct SummaryQuery {
     shop: Option<ShopId>,
+    /// only the changes since the cursor of an earlier summary
+    since: Option<SyncCursor>,
 }
 async fn summary(
     mut session: RequestSession,
     State(pool): State<DbPool>,
-    Query(ShopQuery { shop }): Query<ShopQuery>,
-) -> ApiResult<Summary> {
+    Query(SummaryQuery { shop, since }): Query<SummaryQuery>,
+) -> ApiResult<SyncSummary> {
     let mut store = pool.begin().await?;
     session.set_shop(shop)?;
     StravaUser::update_gear(&mut session, &mut store).await?;
     let res = session
         .user_id()
-        .get_summary(session.shop(), &mut store)
+        .sync_summary(since, session.shop(), &mut store)
         .await
         .map(Json)?;
     store.commit().await?;
     Ok(res)
 }
 
-#[derive(Clone, Serialize, Debug)]
-pub struct Export {
-    pub user: tb_domain::User,
-    pub parts: Vec<tb_domain::Part>,
-    pub attachments: Vec<tb_domain::AttachmentDetail>,
-    pub services: Vec<tb_domain::Service>,
-    pub plans: Vec<tb_domain::ServicePlan>,
-    pub usages: Vec<tb_domain::Usage>,
-    pub activities: Vec<tb_domain::Activity>,
-    pub shops: Vec<tb_domain::Shop>,
+async fn export(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<Export> {
+    let mut store = pool.begin().await?;
+    Ok(Export::new(&user, &mut store).await.map(Json)?)
 }
 
-async fn export(user: RequestSession, State(pool): State<DbPool>) -> ApiResult<Export> {
+/// import an export into the account of the user
+async fn import(
+    user: RequestSession,
+    State(pool): State<DbPool>,
+    Json(mut export): Json<Export>,
+) -> ApiResult<Summary> {
+    // the rest of the import needs the private types in the cached catalog
+    let mut store = pool.begin().await?;
+    let types = export.import_types(&user, &mut store).await?;
+    let committed = store.commit().await;
+    tb_domain::reload_types(&mut pool.begin().await?).await?;
+    committed?;
+
     let mut store = pool.begin().await?;
-    let user_id = user.user_id();
-    let Summary {
-        activities,
-        parts,
-        attachments,
-        usages,
-        services,
-        plans,
-        shops,
-        ..
-    } = user_id.get_summary(None, &mut store).await?;
-    let user = user_id.read(&mut store).await?;
-    Ok(Json(Export {
-        user,
-        activities,
-        parts,
-        attachments,
-        usages,
-        services,
-        plans,
-        shops,
-    }))
+    let res = match export.import(&types, &user, &mut store).await {
+        Ok(summary) => store.commit().await.map(|_| summary),
+        Err(err) => Err(err),
+    };
+    if res.is_err() && !types.is_empty() {
+        let mut store = pool.begin().await?;
+        Export::discard_types(&types, &user, &mut store).await?;
+        let committed = store.commit().await;
+        tb_domain::reload_types(&mut pool.begin().await?).await?;
+        committed?;
+    }
+    Ok(res.map(Json)?)
 }
 
 async fn userlist(
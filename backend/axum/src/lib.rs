@@ -32,6 +32,8 @@ use appstate::*;
 mod error;
 use error::*;
 
+mod notify;
+
 pub async fn start(database_url: &str, path: std::path::PathBuf, addr: SocketAddr) -> TbResult<()> {
     tracing_subscriber::registry()
         .with(
@@ -42,6 +44,7 @@ pub async fn start(database_url: &str, path: std::path::PathBuf, addr: SocketAdd
         .init();
 
     let pool = tb_sqlx::DbPool::new(database_url).await?;
+    tb_domain::reload_types(&mut pool.begin().await?).await?;
 
     let session_store = PostgresStore::new(pool.raw());
     session_store
@@ -61,6 +64,8 @@ pub async fn start(database_url: &str, path: std::path::PathBuf, addr: SocketAdd
         )))
         .with_secure(false);
 
+    let notify_task = notify::spawn(pool.clone())?;
+
     let app_state = AppState::new(pool);
 
     let app = Router::new()
@@ -79,15 +84,19 @@ pub async fn start(database_url: &str, path: std::path::PathBuf, addr: SocketAdd
         .context("Binding address")?;
 
     axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal(deletion_task.abort_handle()))
+        .with_graceful_shutdown(shutdown_signal(vec![
+            deletion_task.abort_handle(),
+            notify_task.abort_handle(),
+        ]))
         .await
         .context("Main server")?;
 
     deletion_task.await.ok();
+    notify_task.await.ok();
     Ok(())
 }
 
-async fn shutdown_signal(deletion_task_abort_handle: tokio::task::AbortHandle) {
+async fn shutdown_signal(abort_handles: Vec<tokio::task::AbortHandle>) {
     use tokio::signal;
     let ctrl_c = async {
         signal::ctrl_c()
@@ -107,7 +116,10 @@ async fn shutdown_signal(deletion_task_abort_handle: tokio::task::AbortHandle) {
     let terminate = std::future::pending::<()>();
 
     tokio::select! {
-        _ = ctrl_c => { deletion_task_abort_handle.abort() },
-        _ = terminate => { deletion_task_abort_handle.abort() },
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    for handle in abort_handles {
+        handle.abort();
     }
 }
@@ -1,6 +1,6 @@
 use anyhow::Context;
 use axum::{
-    extract::FromRequestParts,
+    extract::{FromRequestParts, OptionalFromRequestParts},
     response::{IntoResponse, Response},
 };
 use http::{StatusCode, request::Parts};
@@ -25,6 +25,9 @@ pub(crate) struct RequestSession {
     expires_at: Option<SystemTime>,
     refresh_token: Option<RefreshToken>,
     shop: Option<ShopId>,
+    /// the admin acting for the user
+    #[serde(default)]
+    admin: Option<UserId>,
     #[serde(skip)]
     session: Option<TowerSession>,
 }
@@ -73,6 +76,7 @@ impl RequestSession {
             refresh_token: refresh_token.cloned(),
             session: Some(session),
             shop: None,
+            admin: None,
         }
         .update()
         .await
@@ -80,7 +84,7 @@ impl RequestSession {
 
     /// User for admin actions to impersonate user
     pub(crate) async fn create_from_id(
-        _admin: AxumAdmin,
+        admin: AxumAdmin,
         user: UserId,
         store: &mut impl StravaStore,
     ) -> TbResult<RequestSession> {
@@ -100,6 +104,7 @@ impl RequestSession {
             refresh_token,
             session: None,
             shop: None,
+            admin: Some(admin.0),
         })
     }
 
@@ -225,6 +230,10 @@ impl TbSession for RequestSession {
         self.is_admin
     }
 
+    fn actor(&self) -> UserId {
+        self.admin.unwrap_or(self.id)
+    }
+
     fn shop(&self) -> Option<ShopId> {
         self.shop
     }
@@ -242,6 +251,10 @@ impl StravaSession for RequestSession {
         self.strava_id
     }
 
+    async fn authorize(&mut self, store: &mut impl StravaStore) -> TbResult<()> {
+        self.check_token(store).await
+    }
+
     async fn request_json<T: DeserializeOwned>(
         &mut self,
         uri: &str,
@@ -298,7 +311,25 @@ where
     }
 }
 
-pub struct AxumAdmin;
+impl<S> OptionalFromRequestParts<S> for RequestSession
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        match <RequestSession as FromRequestParts<S>>::from_request_parts(parts, state).await {
+            Ok(session) => Ok(Some(session)),
+            Err((StatusCode::UNAUTHORIZED, _)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+pub struct AxumAdmin(UserId);
 
 impl<S> FromRequestParts<S> for AxumAdmin
 where
@@ -307,13 +338,13 @@ where
     type Rejection = Response;
 
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
-        let user = RequestSession::from_request_parts(parts, state)
+        let user = <RequestSession as FromRequestParts<S>>::from_request_parts(parts, state)
             .await
             .map_err(IntoResponse::into_response)?;
         if !user.is_admin() {
             Err((http::StatusCode::NOT_FOUND, "Page not found").into_response())
         } else {
-            Ok(AxumAdmin)
+            Ok(AxumAdmin(user.user_id()))
         }
     }
 }
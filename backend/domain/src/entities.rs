@@ -27,3 +27,31 @@ pub use summary::*;
 
 mod types;
 pub use types::*;
+
+mod notification;
+pub use notification::*;
+
+mod stock;
+pub use stock::*;
+
+mod usagerule;
+pub use usagerule::*;
+
+mod gearrule;
+pub use gearrule::*;
+
+mod configuration;
+pub use configuration::*;
+
+mod export;
+pub use export::*;
+mod batch;
+pub use batch::*;
+mod audit;
+pub use audit::*;
+mod undo;
+pub use undo::*;
+mod check;
+pub use check::*;
+mod sync;
+pub use sync::*;
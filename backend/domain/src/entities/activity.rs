@@ -24,6 +24,8 @@
 //!
 //! By assigning a gear to the activity it gets accounted with that gear and all it's parts attached
 //! at the start time of the activity
+//! Besides the primary gear further gears can be assigned, e.g. a trailer or a heart rate strap.
+//! The activity is accounted with each of them
 //! Most operations are done on the ActivityId though
 //!
 
@@ -36,6 +38,12 @@ use time::{OffsetDateTime, PrimitiveDateTime, macros::format_description};
 
 use crate::*;
 
+mod bulk;
+pub use bulk::*;
+mod query;
+pub use query::*;
+mod upload;
+
 /// The Id of an Activity
 ///
 /// Most operations for activities are done on the Id alone
@@ -71,6 +79,9 @@ pub struct Activity {
     pub energy: Option<i32>,
     /// Which gear did she use?
     pub gear: Option<PartId>,
+    /// Further gears used besides the primary gear
+    #[serde(default)]
+    pub gears: Vec<PartId>,
     /// The name of the recording device
     pub device_name: Option<String>,
     /// opaque identifier for the source file
@@ -129,13 +140,12 @@ impl ActivityId {
     /// checks authorization  
     pub async fn delete(self, session: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
         info!("Deleting {self:?}");
-        let mut res = self
-            .read(session, store)
-            .await?
-            .register(Factor::Sub, store)
-            .await?;
+        let act = self.read(session, store).await?;
+        act.audit(Some(&act), None, session, store).await?;
+        let mut res = act.register(Factor::Sub, store).await?;
         store.activity_delete(self).await?;
         res.activities[0].gear = None;
+        res.activities[0].gears = Vec::new();
         res.activities[0].duration = 0;
         res.activities[0].time = None;
         res.activities[0].distance = None;
@@ -151,9 +161,10 @@ impl Activity {
     ///
     /// returns the activity and all affected parts  
     /// checks authorization  
-    pub async fn upsert(self, user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
+    pub async fn upsert(mut self, user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
+        self.check_gears(user, store).await?;
         if let Some(old_activity) = self.id.read_optional(user, store).await? {
-            old_activity.replace(self, store).await
+            old_activity.replace(self, user, store).await
         } else {
             user.check_owner(
                 self.user_id,
@@ -166,6 +177,7 @@ impl Activity {
 
             info!("Creating {:?}", &self);
             let new = store.activity_create(self).await?;
+            new.audit(None, Some(&new), user, store).await?;
             // let res = new.check_geartype(res, store)?;
             new.register(Factor::Add, store).await
         }
@@ -176,20 +188,77 @@ impl Activity {
     ///
     /// returns all affected parts  
     /// checks authorization  
-    pub async fn update(self, user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
-        self.id.read(user, store).await?.replace(self, store).await
+    pub async fn update(mut self, user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
+        self.check_gears(user, store).await?;
+        self.id
+            .read(user, store)
+            .await?
+            .replace(self, user, store)
+            .await
+    }
+
+    /// All gears of the activity, the primary gear first
+    pub fn all_gears(&self) -> Vec<PartId> {
+        let mut res: Vec<PartId> = self.gear.into_iter().collect();
+        for gear in &self.gears {
+            if !res.contains(gear) {
+                res.push(*gear);
+            }
+        }
+        res
     }
 
-    async fn replace(self, new: Activity, store: &mut impl Store) -> TbResult<Summary> {
+    /// check that the further gears are main gears of the user
+    ///
+    /// Duplicates, the primary gear and deleted gears are removed from the further gears
+    async fn check_gears(&mut self, user: &dyn Session, store: &mut impl Store) -> TbResult<()> {
+        let mut gears = Vec::new();
+        for gear in &self.gears {
+            if self.gear == Some(*gear) || gears.contains(gear) {
+                continue;
+            }
+            let part = match gear.part(user, store).await {
+                Ok(part) => part,
+                // the gear was deleted since the activity was stored
+                Err(Error::NotFound(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            if !part.what.is_main()? {
+                return Err(Error::BadRequest(format!("part {gear} is not a gear")));
+            }
+            gears.push(*gear);
+        }
+        self.gears = gears;
+        Ok(())
+    }
+
+    async fn replace(
+        self,
+        new: Activity,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
         info!("Updating {self:?}");
-        let mut res = self.register(Factor::Sub, store).await?;
+        let mut res = self.clone().register(Factor::Sub, store).await?;
 
         let act = store.activity_update(new).await?;
+        act.audit(Some(&self), Some(&act), user, store).await?;
 
         res = res + act.register(Factor::Add, store).await?;
         Ok(res)
     }
 
+    /// record the change of the activity
+    pub(crate) async fn audit(
+        &self,
+        before: Option<&Activity>,
+        after: Option<&Activity>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        AuditEntry::record(user, "activity", self.id, self.gear, before, after, store).await
+    }
+
     /// Extract the usage out of an activity
     ///
     /// If the descend value is missing, assume descend = climb
@@ -242,7 +311,18 @@ impl Activity {
             Factor::Sub => -self.usage(),
         };
 
-        let res = Attachment::register_activity(self.gear, self.start, usage, store).await?;
+        let mut res = Summary::default();
+        for gear in self.all_gears() {
+            res = res
+                + Attachment::register_activity(
+                    Some(gear),
+                    self.what,
+                    self.start,
+                    usage.clone(),
+                    store,
+                )
+                .await?;
+        }
         let activities = vec![self];
         Ok(Summary { activities, ..res })
     }
@@ -352,6 +432,11 @@ impl Activity {
             .await?;
         let mut hash = SumHash::default();
         for act in acts {
+            let before = Activity {
+                gear: None,
+                ..act.clone()
+            };
+            act.audit(Some(&before), Some(&act), user, store).await?;
             hash += act.register(Factor::Add, store).await?;
         }
         Ok(hash.into())
@@ -367,6 +452,28 @@ impl Activity {
         warn!("Done rescanning");
         Ok(())
     }
+
+    /// recalculate the usages of all parts, attachments and services of the user
+    ///
+    /// Needed when the weighting of the activities changes, e.g. by a usage rule
+    pub(crate) async fn rescan_user(user: UserId, store: &mut impl Store) -> TbResult<()> {
+        info!("rescanning activities of user {user}");
+        let mut usages = Vec::new();
+        for part in store.part_get_all_for_userid(&user).await? {
+            usages.push(Usage::new(part.usage()));
+            for att in store.attachments_all_by_part(part.id).await? {
+                usages.push(Usage::new(att.usage));
+            }
+            for service in store.services_by_part(part.id).await? {
+                usages.push(Usage::new(service.usage));
+            }
+        }
+        Usage::update_vec(&usages, store).await?;
+        for a in store.get_all(&user).await? {
+            a.register(Factor::Add, store).await?;
+        }
+        Ok(())
+    }
 }
 
 async fn match_and_update(
@@ -383,3 +490,43 @@ async fn match_and_update(
     act.descend = Some(rdescend);
     act.update(user, store).await
 }
+
+#[cfg(test)]
+impl Activity {
+    /// A ride of user 1 without any usage
+    pub(crate) fn test_ride(start: OffsetDateTime, gear: Option<PartId>) -> Self {
+        Activity {
+            id: ActivityId::new(1),
+            user_id: 1.into(),
+            what: 1.into(),
+            name: "ride".into(),
+            start,
+            duration: 0,
+            time: None,
+            distance: None,
+            climb: None,
+            descend: None,
+            energy: None,
+            gear,
+            gears: Vec::new(),
+            device_name: None,
+            external_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_gears() {
+        let act = Activity {
+            gears: vec![3.into(), 2.into(), 3.into(), 4.into()],
+            ..Activity::test_ride(OffsetDateTime::UNIX_EPOCH, Some(2.into()))
+        };
+        assert_eq!(act.all_gears(), vec![2.into(), 3.into(), 4.into()]);
+        let act = Activity { gear: None, ..act };
+        assert_eq!(act.all_gears(), vec![3.into(), 2.into(), 4.into()]);
+    }
+}
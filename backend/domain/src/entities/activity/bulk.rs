@@ -0,0 +1,178 @@
+//! Operations on many activities at once
+
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// Selects activities of a user
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityFilter {
+    /// activities starting at or after this time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    /// activities starting before this time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    /// activities with this primary gear
+    #[serde(default)]
+    pub gear: Option<PartId>,
+    /// activities of this type
+    #[serde(default)]
+    pub what: Option<ActTypeId>,
+}
+
+impl ActivityFilter {
+    pub(crate) fn matches(&self, act: &Activity) -> bool {
+        self.from.is_none_or(|from| act.start >= from)
+            && self.to.is_none_or(|to| act.start < to)
+            && self.gear.is_none_or(|gear| act.gear == Some(gear))
+            && self.what.is_none_or(|what| act.what == what)
+    }
+
+    /// all activities of the user matching the filter ordered by start
+    pub(crate) async fn activities(
+        &self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<Activity>> {
+        let mut acts: Vec<_> = store
+            .get_all(&user.user_id())
+            .await?
+            .into_iter()
+            .filter(|a| self.matches(a))
+            .collect();
+        acts.sort_by_key(|a| a.start);
+        Ok(acts)
+    }
+}
+
+impl Activity {
+    /// Move all activities matching the filter to the gear `target`
+    ///
+    /// The usages are accumulated over all activities and stored once.
+    /// Activities which are already on the target gear are not touched.
+    ///
+    /// returns the changed activities and all affected parts and usages
+    pub async fn reassign(
+        filter: ActivityFilter,
+        target: PartId,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        let gear = target.part(user, store).await?;
+        if !gear.what.is_main()? {
+            return Err(Error::BadRequest(format!("part {target} is not a gear")));
+        }
+        let types = gear.what.act_types();
+
+        let acts: Vec<_> = filter
+            .activities(user, store)
+            .await?
+            .into_iter()
+            .filter(|a| a.gear != Some(target))
+            .collect();
+        if let Some(act) = acts.iter().find(|a| !types.contains(&a.what)) {
+            return Err(Error::BadRequest(format!(
+                "{} cannot be used for activity {} of type {}",
+                gear.name, act.id, act.what
+            )));
+        }
+        info!(
+            "moving {} activities of user {} to gear {target}",
+            acts.len(),
+            user.user_id()
+        );
+
+        let mut hash = SumHash::default();
+        let mut activities = Vec::new();
+        let mut deltas: HashMap<UsageId, Usage> = HashMap::new();
+        for act in acts {
+            let usage = act.usage();
+            let mut changes = Vec::new();
+            if let Some(old) = act.gear {
+                changes.push((old, -usage.clone()));
+            }
+            // the target must not be accounted twice
+            if !act.gears.contains(&target) {
+                changes.push((target, usage));
+            }
+            for (gear, usage) in changes {
+                let (usages, parts) =
+                    Attachment::activity_usages(gear, act.what, act.start, &usage, store).await?;
+                for (id, usage) in usages {
+                    let delta = deltas.entry(id).or_insert_with(|| Usage::new(id));
+                    *delta = &*delta + usage;
+                }
+                for part in parts {
+                    hash += part;
+                }
+            }
+            let gears = act.gears.iter().copied().filter(|g| *g != target).collect();
+            let new = store
+                .activity_update(Activity {
+                    gear: Some(target),
+                    gears,
+                    ..act.clone()
+                })
+                .await?;
+            act.audit(Some(&act), Some(&new), user, store).await?;
+            activities.push(new);
+        }
+
+        let mut usages = Vec::new();
+        for (id, delta) in deltas {
+            usages.push(id.read(store).await? + delta);
+        }
+        Usage::update_vec(&usages, store).await?;
+        hash += Summary {
+            activities,
+            usages,
+            ..Default::default()
+        };
+        Ok(hash.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn filter_activities() {
+        let act = Activity::test_ride(datetime!(2024-03-10 10:00 UTC), Some(2.into()));
+        let march = ActivityFilter {
+            from: Some(datetime!(2024-03-01 0:00 UTC)),
+            to: Some(datetime!(2024-04-01 0:00 UTC)),
+            ..Default::default()
+        };
+        assert!(ActivityFilter::default().matches(&act));
+        assert!(march.matches(&act));
+        assert!(
+            !ActivityFilter {
+                to: Some(act.start),
+                ..march.clone()
+            }
+            .matches(&act)
+        );
+        assert!(
+            ActivityFilter {
+                gear: Some(2.into()),
+                what: Some(1.into()),
+                ..march.clone()
+            }
+            .matches(&act)
+        );
+        assert!(
+            !ActivityFilter {
+                gear: Some(3.into()),
+                ..march
+            }
+            .matches(&act)
+        );
+    }
+}
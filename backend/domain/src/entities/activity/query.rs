@@ -0,0 +1,261 @@
+//! Listing the activities of a user page by page
+//!
+//! The activities are sorted by start or distance, ties are broken by the id.
+//! A page ends with the cursor for the next one. It holds the sort key and the id of
+//! the last activity of the page, so paging does not depend on that activity being unchanged.
+
+use serde_derive::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// The number of activities per page if not given
+const DEFAULT_LIMIT: i64 = 100;
+/// Do not return more activities at once
+const MAX_LIMIT: i64 = 1000;
+
+/// What the activities are sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivitySort {
+    #[default]
+    Start,
+    /// activities without distance count as 0
+    Distance,
+}
+
+/// The direction of the sort
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// The position after the last activity of a page
+///
+/// It is passed to the client as an opaque token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, SerializeDisplay, DeserializeFromStr)]
+pub enum ActivityCursor {
+    Start(OffsetDateTime, ActivityId),
+    Distance(i32, ActivityId),
+}
+
+impl ActivityCursor {
+    /// the cursor after `act` for the sort order
+    fn new(sort: ActivitySort, act: &Activity) -> Self {
+        match sort {
+            ActivitySort::Start => ActivityCursor::Start(act.start, act.id),
+            ActivitySort::Distance => ActivityCursor::Distance(act.distance.unwrap_or(0), act.id),
+        }
+    }
+
+    fn sort(&self) -> ActivitySort {
+        match self {
+            ActivityCursor::Start(..) => ActivitySort::Start,
+            ActivityCursor::Distance(..) => ActivitySort::Distance,
+        }
+    }
+}
+
+impl std::fmt::Display for ActivityCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActivityCursor::Start(start, id) => write!(f, "s{}_{id}", start.unix_timestamp_nanos()),
+            ActivityCursor::Distance(distance, id) => write!(f, "d{distance}_{id}"),
+        }
+    }
+}
+
+impl std::str::FromStr for ActivityCursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::BadRequest(format!("invalid cursor {s}"));
+        let (key, id) = s
+            .get(1..)
+            .and_then(|s| s.split_once('_'))
+            .ok_or_else(invalid)?;
+        let id = ActivityId::new(id.parse().map_err(|_| invalid())?);
+        match &s[..1] {
+            "s" => {
+                let nanos = key.parse().map_err(|_| invalid())?;
+                let start =
+                    OffsetDateTime::from_unix_timestamp_nanos(nanos).map_err(|_| invalid())?;
+                Ok(ActivityCursor::Start(start, id))
+            }
+            "d" => Ok(ActivityCursor::Distance(
+                key.parse().map_err(|_| invalid())?,
+                id,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Selects, sorts and pages the activities of a user
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActivityQuery {
+    /// activities starting at or after this time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    /// activities starting before this time
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    /// activities of this type
+    #[serde(default)]
+    pub what: Option<ActTypeId>,
+    /// activities using this gear
+    #[serde(default)]
+    pub gear: Option<PartId>,
+    /// activities using a gear the part was attached to at their start
+    #[serde(default)]
+    pub part: Option<PartId>,
+    /// The minimal distance
+    #[serde(default)]
+    pub min_distance: Option<i32>,
+    /// The maximal distance
+    #[serde(default)]
+    pub max_distance: Option<i32>,
+    /// The name of the recording device, ignoring case
+    #[serde(default)]
+    pub device_name: Option<String>,
+    #[serde(default)]
+    pub sort: ActivitySort,
+    #[serde(default)]
+    pub order: SortOrder,
+    /// the cursor of the previous page
+    #[serde(default)]
+    pub after: Option<ActivityCursor>,
+    /// the number of activities per page
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+/// A page of activities
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActivityPage {
+    pub activities: Vec<Activity>,
+    /// the cursor for the next page, none if this is the last one
+    pub next: Option<ActivityCursor>,
+}
+
+impl ActivityQuery {
+    /// check and normalize the query
+    fn check(self) -> TbResult<Self> {
+        if let (Some(min), Some(max)) = (self.min_distance, self.max_distance)
+            && min > max
+        {
+            return Err(Error::BadRequest(format!(
+                "minimal distance {min} is bigger than maximal distance {max}"
+            )));
+        }
+        if let Some(after) = self.after
+            && after.sort() != self.sort
+        {
+            return Err(Error::BadRequest(format!(
+                "cursor {after} does not fit the sort order"
+            )));
+        }
+        let device_name = self
+            .device_name
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        Ok(ActivityQuery {
+            device_name,
+            limit: Some(self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)),
+            ..self
+        })
+    }
+}
+
+impl Activity {
+    /// One page of the activities of the user matching the query
+    ///
+    /// # Returns
+    ///
+    /// The activities in the requested order and the cursor for the next page
+    pub async fn list(
+        query: ActivityQuery,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<ActivityPage> {
+        let query = query.check()?;
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+        // one more to know if there is another page
+        let mut activities = store
+            .activities_query(user.user_id(), &query, limit + 1)
+            .await?;
+        let next = if activities.len() as i64 > limit {
+            activities.truncate(limit as usize);
+            activities
+                .last()
+                .map(|a| ActivityCursor::new(query.sort, a))
+        } else {
+            None
+        };
+        Ok(ActivityPage { activities, next })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_query() {
+        let query = ActivityQuery {
+            device_name: Some(" ".into()),
+            limit: Some(5000),
+            ..Default::default()
+        }
+        .check()
+        .unwrap();
+        assert_eq!(query.device_name, None);
+        assert_eq!(query.limit, Some(MAX_LIMIT));
+        assert_eq!(
+            ActivityQuery::default().check().unwrap().limit,
+            Some(DEFAULT_LIMIT)
+        );
+        assert!(
+            ActivityQuery {
+                min_distance: Some(2),
+                max_distance: Some(1),
+                ..Default::default()
+            }
+            .check()
+            .is_err()
+        );
+        assert!(
+            ActivityQuery {
+                after: Some(ActivityCursor::Distance(0, ActivityId::new(1))),
+                ..Default::default()
+            }
+            .check()
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn cursor_token() {
+        let start = OffsetDateTime::from_unix_timestamp_nanos(1_700_000_000_123_456_000).unwrap();
+        for cursor in [
+            ActivityCursor::Start(start, ActivityId::new(42)),
+            ActivityCursor::Distance(-3, ActivityId::new(7)),
+        ] {
+            assert_eq!(
+                cursor.to_string().parse::<ActivityCursor>().unwrap(),
+                cursor
+            );
+        }
+        assert_eq!(
+            ActivityCursor::Distance(1200, ActivityId::new(7)).to_string(),
+            "d1200_7"
+        );
+        for bad in ["", "s", "x1_2", "d1", "d_2", "s1_x"] {
+            assert!(bad.parse::<ActivityCursor>().is_err(), "{bad}");
+        }
+    }
+}
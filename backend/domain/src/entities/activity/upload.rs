@@ -0,0 +1,682 @@
+//! Import of activity files
+//!
+//! Users without a Strava account can upload the files written by their bike computer or watch.
+//! GPX, TCX and FIT files are parsed into an `Activity`, which is then registered like any other activity.
+//!
+//! Uploaded activities get negative ids, so they never collide with the ids Strava hands out.
+
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+use crate::*;
+
+/// Elevation changes below this threshold are considered GPS noise
+const ELEVATION_THRESHOLD: f64 = 2.0;
+/// Below this speed in m/s we consider the athlete to be standing still
+const MIN_MOVING_SPEED: f64 = 0.5;
+/// Mean earth radius in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+/// Start of the FIT epoch (1989-12-31 00:00:00 UTC) as unix timestamp
+const FIT_EPOCH: i64 = 631_065_600;
+
+/// The values extracted from an activity file
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct FileActivity {
+    pub name: Option<String>,
+    pub what: Option<ActTypeId>,
+    pub start: Option<OffsetDateTime>,
+    pub duration: i32,
+    pub time: Option<i32>,
+    pub distance: Option<i32>,
+    pub climb: Option<i32>,
+    pub descend: Option<i32>,
+    pub energy: Option<i32>,
+    pub device_name: Option<String>,
+}
+
+impl Activity {
+    /// Create or update an activity from an uploaded GPX, TCX or FIT file
+    ///
+    /// An activity of the user with the same start time gets replaced,
+    /// keeping its gear, type and name if they are not given explicitly.
+    /// A new activity without an explicit gear gets its gear from the gear rules.
+    ///
+    /// returns the activity and all affected parts
+    /// checks authorization
+    pub async fn upload(
+        data: &[u8],
+        gear: Option<PartId>,
+        what: Option<ActTypeId>,
+        name: Option<String>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        let file = FileActivity::parse(data)?;
+        let start = file
+            .start
+            .ok_or(Error::BadRequest("activity file has no start time".into()))?;
+        if let Some(gear) = gear {
+            gear.part(user, store).await?;
+        }
+
+        let old = store
+            .activity_get_by_user_and_start(user.user_id(), start)
+            .await?;
+        let id = match &old {
+            Some(old) => old.id,
+            None => store.activity_new_id().await?,
+        };
+        let use_rules = gear.is_none() && old.is_none();
+        let gear = gear.or(old.as_ref().and_then(|a| a.gear));
+        let what = match (what.or(file.what), &old) {
+            (Some(what), _) => what,
+            (None, Some(old)) => old.what,
+            (None, None) => match gear {
+                Some(gear) => gear
+                    .part(user, store)
+                    .await?
+                    .what
+                    .act_types()
+                    .first()
+                    .copied()
+                    .unwrap_or(ActTypeId::from(0)),
+                None => ActTypeId::from(0),
+            },
+        };
+        let name = name
+            .or(old.as_ref().map(|a| a.name.clone()))
+            .or(file.name)
+            .unwrap_or_else(|| format!("Activity {}", start.date()));
+
+        let mut activity = Activity {
+            id,
+            user_id: user.user_id(),
+            what,
+            name,
+            start,
+            duration: file.duration.max(file.time.unwrap_or(0)),
+            time: file.time,
+            distance: file.distance,
+            climb: file.climb,
+            descend: file.descend,
+            energy: file.energy,
+            gear,
+            gears: old.as_ref().map(|a| a.gears.clone()).unwrap_or_default(),
+            device_name: file.device_name,
+            external_id: old.and_then(|a| a.external_id),
+        };
+        if use_rules {
+            GearRule::assign(&mut activity, store).await?;
+        }
+        activity.upsert(user, store).await
+    }
+}
+
+impl FileActivity {
+    /// Parse an activity file, detecting the format from its content
+    pub(crate) fn parse(data: &[u8]) -> TbResult<Self> {
+        if data.len() >= 12 && &data[8..12] == b".FIT" {
+            return parse_fit(data);
+        }
+        let text = std::str::from_utf8(data)
+            .map_err(|_| Error::BadRequest("unknown activity file format".into()))?;
+        let doc = roxmltree::Document::parse(text)
+            .map_err(|e| Error::BadRequest(format!("could not parse activity file: {e}")))?;
+        match doc.root_element().tag_name().name() {
+            "gpx" => Ok(parse_gpx(&doc)),
+            "TrainingCenterDatabase" => Ok(parse_tcx(&doc)),
+            name => Err(Error::BadRequest(format!(
+                "unknown activity file format '{name}'"
+            ))),
+        }
+    }
+}
+
+/// Map the sport names used in activity files to our activity types
+fn sport2type(sport: &str) -> Option<ActTypeId> {
+    let id = match sport.to_lowercase().as_str() {
+        "biking" | "cycling" | "ride" | "road_biking" | "mountain_biking" | "gravel_cycling" => 1,
+        "snowboarding" | "snowboard" => 2,
+        "running" | "run" | "trail_running" => 3,
+        "hiking" | "hike" => 4,
+        "virtual_ride" | "virtualride" | "indoor_cycling" => 5,
+        "alpine_skiing" | "skiing" | "alpineski" => 6,
+        "walking" | "walk" => 8,
+        "e_biking" | "ebikeride" | "e_bike_ride" => 9,
+        "backcountry_skiing" | "backcountryski" => 10,
+        _ => return None,
+    };
+    Some(id.into())
+}
+
+/// A single sample of a recorded track
+#[derive(Debug, Default, Clone, Copy)]
+struct TrackPoint {
+    time: Option<OffsetDateTime>,
+    position: Option<(f64, f64)>,
+    elevation: Option<f64>,
+    /// cumulated distance as recorded by the device
+    distance: Option<f64>,
+}
+
+/// Great-circle distance between two positions in meters
+fn haversine((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2 - lon1).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// Compute the activity values from the track
+fn track_stats(points: &[TrackPoint], res: &mut FileActivity) {
+    let times = points.iter().filter_map(|p| p.time).collect::<Vec<_>>();
+    if let (Some(first), Some(last)) = (times.first(), times.last()) {
+        res.start = res.start.or(Some(*first));
+        if res.duration == 0 {
+            res.duration = (*last - *first).whole_seconds() as i32;
+        }
+    }
+
+    let mut distance = 0.0;
+    let mut moving = 0;
+    let mut last: Option<TrackPoint> = None;
+    for p in points {
+        if let Some(l) = last {
+            let step = match (l.distance, p.distance, l.position, p.position) {
+                (Some(d1), Some(d2), _, _) => d2 - d1,
+                (_, _, Some(p1), Some(p2)) => haversine(p1, p2),
+                _ => 0.0,
+            };
+            distance += step;
+            if let (Some(t1), Some(t2)) = (l.time, p.time) {
+                let secs = (t2 - t1).whole_seconds();
+                if secs > 0 && step / secs as f64 >= MIN_MOVING_SPEED {
+                    moving += secs;
+                }
+            }
+        }
+        last = Some(*p);
+    }
+    if distance > 0.0 {
+        res.distance = res.distance.or(Some(distance.round() as i32));
+        res.time = res.time.or(Some(moving as i32));
+    }
+
+    let mut elevations = points.iter().filter_map(|p| p.elevation);
+    if let Some(mut reference) = elevations.next() {
+        let (mut climb, mut descend) = (0.0, 0.0);
+        for e in elevations {
+            if e - reference >= ELEVATION_THRESHOLD {
+                climb += e - reference;
+                reference = e;
+            } else if reference - e >= ELEVATION_THRESHOLD {
+                descend += reference - e;
+                reference = e;
+            }
+        }
+        res.climb = res.climb.or(Some(climb.round() as i32));
+        res.descend = res.descend.or(Some(descend.round() as i32));
+    }
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.tag_name().name() == name)
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text()).map(str::trim)
+}
+
+fn child_value(node: roxmltree::Node, name: &str) -> Option<f64> {
+    child_text(node, name).and_then(|t| t.parse().ok())
+}
+
+fn parse_time(text: Option<&str>) -> Option<OffsetDateTime> {
+    text.and_then(|t| OffsetDateTime::parse(t, &Rfc3339).ok())
+}
+
+fn descendants<'a, 'input: 'a>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> + 'a {
+    node.descendants()
+        .filter(move |n| n.tag_name().name() == name)
+}
+
+fn parse_gpx(doc: &roxmltree::Document) -> FileActivity {
+    let root = doc.root_element();
+    let mut res = FileActivity {
+        device_name: root.attribute("creator").map(String::from),
+        ..Default::default()
+    };
+    if let Some(trk) = child(root, "trk") {
+        res.name = child_text(trk, "name").map(String::from);
+        res.what = child_text(trk, "type").and_then(sport2type);
+    }
+    let points = descendants(root, "trkpt")
+        .map(|p| TrackPoint {
+            time: parse_time(child_text(p, "time")),
+            position: p
+                .attribute("lat")
+                .and_then(|lat| lat.parse().ok())
+                .zip(p.attribute("lon").and_then(|lon| lon.parse().ok())),
+            elevation: child_value(p, "ele"),
+            distance: None,
+        })
+        .collect::<Vec<_>>();
+    track_stats(&points, &mut res);
+    res
+}
+
+fn parse_tcx(doc: &roxmltree::Document) -> FileActivity {
+    let mut res = FileActivity::default();
+    let Some(activity) = descendants(doc.root_element(), "Activity").next() else {
+        return res;
+    };
+    res.what = activity.attribute("Sport").and_then(sport2type);
+    res.start = parse_time(child_text(activity, "Id"));
+    res.device_name = child(activity, "Creator")
+        .and_then(|c| child_text(c, "Name"))
+        .map(String::from);
+
+    let laps = descendants(activity, "Lap").collect::<Vec<_>>();
+    if !laps.is_empty() {
+        let sum = |name| {
+            laps.iter()
+                .filter_map(|l| child_value(*l, name))
+                .sum::<f64>()
+        };
+        res.start = parse_time(laps[0].attribute("StartTime")).or(res.start);
+        res.time = Some(sum("TotalTimeSeconds").round() as i32);
+        res.distance = Some(sum("DistanceMeters").round() as i32);
+        // TCX only knows the kcal burned, which are close to the kJ of work done for cycling
+        let calories = sum("Calories").round() as i32;
+        res.energy = (calories > 0).then_some(calories);
+    }
+
+    let points = descendants(activity, "Trackpoint")
+        .map(|p| TrackPoint {
+            time: parse_time(child_text(p, "Time")),
+            position: child(p, "Position").and_then(|pos| {
+                child_value(pos, "LatitudeDegrees").zip(child_value(pos, "LongitudeDegrees"))
+            }),
+            elevation: child_value(p, "AltitudeMeters"),
+            distance: child_value(p, "DistanceMeters"),
+        })
+        .collect::<Vec<_>>();
+    track_stats(&points, &mut res);
+    res
+}
+
+/// A minimal reader for the binary FIT protocol
+///
+/// Only the session and file_id messages are decoded, everything else is skipped.
+struct FitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+/// Definition of a FIT message type
+#[derive(Clone, Default)]
+struct FitDefinition {
+    big_endian: bool,
+    global: u16,
+    /// field number and size
+    fields: Vec<(u8, usize)>,
+    /// size of developer fields, which we skip
+    dev_size: usize,
+}
+
+const FIT_FILE_ID: u16 = 0;
+const FIT_SESSION: u16 = 18;
+
+impl<'a> FitReader<'a> {
+    fn bytes(&mut self, len: usize) -> TbResult<&'a [u8]> {
+        let res = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or(Error::BadRequest("truncated FIT file".into()))?;
+        self.pos += len;
+        Ok(res)
+    }
+
+    fn byte(&mut self) -> TbResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn definition(&mut self, developer: bool) -> TbResult<FitDefinition> {
+        let header = self.bytes(5)?;
+        let big_endian = header[1] == 1;
+        let global = if big_endian {
+            u16::from_be_bytes([header[2], header[3]])
+        } else {
+            u16::from_le_bytes([header[2], header[3]])
+        };
+        let mut fields = Vec::new();
+        for _ in 0..header[4] {
+            let field = self.bytes(3)?;
+            fields.push((field[0], field[1] as usize));
+        }
+        let mut dev_size = 0;
+        if developer {
+            for _ in 0..self.byte()? {
+                dev_size += self.bytes(3)?[1] as usize;
+            }
+        }
+        Ok(FitDefinition {
+            big_endian,
+            global,
+            fields,
+            dev_size,
+        })
+    }
+}
+
+/// Decode an unsigned FIT value, returning None for the invalid marker
+fn fit_uint(bytes: &[u8], big_endian: bool) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..bytes.len().min(8) {
+        let b = if big_endian {
+            bytes[i]
+        } else {
+            bytes[bytes.len() - 1 - i]
+        };
+        value = (value << 8) | b as u64;
+    }
+    let invalid = match bytes.len() {
+        1 | 2 | 4 | 8 => u64::MAX >> (64 - 8 * bytes.len()),
+        _ => return None,
+    };
+    (value != invalid).then_some(value)
+}
+
+fn fit_string(bytes: &[u8]) -> Option<String> {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    let res = String::from_utf8_lossy(&bytes[..end]).trim().to_string();
+    (!res.is_empty()).then_some(res)
+}
+
+fn fit_sport(sport: u64) -> Option<ActTypeId> {
+    let id = match sport {
+        1 => 3,
+        2 => 1,
+        11 => 8,
+        13 => 6,
+        14 => 2,
+        17 => 4,
+        21 => 9,
+        _ => return None,
+    };
+    Some(id.into())
+}
+
+fn fit_manufacturer(manufacturer: u64) -> Option<&'static str> {
+    Some(match manufacturer {
+        1 => "Garmin",
+        32 => "Wahoo",
+        123 => "Polar",
+        260 => "Zwift",
+        267 => "Bryton",
+        289 => "Hammerhead",
+        294 => "Coros",
+        _ => return None,
+    })
+}
+
+fn parse_fit(data: &[u8]) -> TbResult<FileActivity> {
+    let header_size = data[0] as usize;
+    let data_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let end = (header_size + data_size).min(data.len());
+    let mut reader = FitReader {
+        data: &data[..end],
+        pos: header_size,
+    };
+    let mut definitions = vec![FitDefinition::default(); 16];
+    let mut res = FileActivity::default();
+    let mut sessions = 0;
+    let mut calories = None;
+
+    while reader.pos < end {
+        let header = reader.byte()?;
+        let local = if header & 0x80 != 0 {
+            // compressed timestamp header
+            (header >> 5) & 0x03
+        } else if header & 0x40 != 0 {
+            definitions[(header & 0x0f) as usize] = reader.definition(header & 0x20 != 0)?;
+            continue;
+        } else {
+            header & 0x0f
+        };
+        let def = definitions[local as usize].clone();
+        for &(field, size) in &def.fields {
+            let bytes = reader.bytes(size)?;
+            let value = || fit_uint(bytes, def.big_endian);
+            match (def.global, field) {
+                (FIT_FILE_ID, 1) if res.device_name.is_none() => {
+                    res.device_name = value().and_then(fit_manufacturer).map(String::from)
+                }
+                (FIT_FILE_ID, 8) => {
+                    if let Some(name) = fit_string(bytes) {
+                        res.device_name = Some(name)
+                    }
+                }
+                (FIT_SESSION, 2) if sessions == 0 => {
+                    res.start = value()
+                        .and_then(|t| i64::try_from(t).ok()?.checked_add(FIT_EPOCH))
+                        .and_then(|t| OffsetDateTime::from_unix_timestamp(t).ok())
+                }
+                (FIT_SESSION, 5) if sessions == 0 => res.what = value().and_then(fit_sport),
+                (FIT_SESSION, 7) => {
+                    let mut duration = Some(res.duration);
+                    add(&mut duration, value().map(|v| v / 1000))?;
+                    res.duration = duration.unwrap_or_default();
+                }
+                (FIT_SESSION, 8) => add(&mut res.time, value().map(|v| v / 1000))?,
+                (FIT_SESSION, 11) => add(&mut calories, value())?,
+                (FIT_SESSION, 9) => add(&mut res.distance, value().map(|v| v / 100))?,
+                (FIT_SESSION, 22) => add(&mut res.climb, value())?,
+                (FIT_SESSION, 23) => add(&mut res.descend, value())?,
+                (FIT_SESSION, 48) => add(&mut res.energy, value().map(|v| v / 1000))?,
+                _ => (),
+            }
+        }
+        reader.bytes(def.dev_size)?;
+        if def.global == FIT_SESSION {
+            sessions += 1;
+        }
+    }
+
+    if sessions == 0 {
+        return Err(Error::BadRequest("FIT file contains no session".into()));
+    }
+    // not all devices record the work, the kcal burned are a close approximation for cycling
+    res.energy = res.energy.or(calories);
+    Ok(res)
+}
+
+/// add a value of the file to the total of all sessions
+fn add(total: &mut Option<i32>, value: Option<u64>) -> TbResult<()> {
+    if let Some(value) = value {
+        let sum = i32::try_from(value)
+            .ok()
+            .and_then(|v| v.checked_add(total.unwrap_or(0)))
+            .ok_or(Error::BadRequest(
+                "FIT file contains a value out of range".into(),
+            ))?;
+        *total = Some(sum);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_haversine() {
+        // one degree of latitude
+        let d = haversine((48.0, 11.0), (49.0, 11.0));
+        assert!((d - 111_195.0).abs() < 1.0);
+        assert_eq!(haversine((48.0, 11.0), (48.0, 11.0)), 0.0);
+    }
+
+    #[test]
+    fn test_parse_gpx() {
+        let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx creator="Garmin Edge 530" version="1.1" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <name>Morning Ride</name>
+    <type>cycling</type>
+    <trkseg>
+      <trkpt lat="48.0000" lon="11.0000"><ele>500.0</ele><time>2024-05-04T08:00:00Z</time></trkpt>
+      <trkpt lat="48.0090" lon="11.0000"><ele>510.0</ele><time>2024-05-04T08:02:00Z</time></trkpt>
+      <trkpt lat="48.0090" lon="11.0000"><ele>511.0</ele><time>2024-05-04T08:10:00Z</time></trkpt>
+      <trkpt lat="48.0000" lon="11.0000"><ele>495.0</ele><time>2024-05-04T08:12:00Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+        let act = FileActivity::parse(gpx.as_bytes()).unwrap();
+        assert_eq!(act.name.as_deref(), Some("Morning Ride"));
+        assert_eq!(act.what, Some(ActTypeId::from(1)));
+        assert_eq!(act.device_name.as_deref(), Some("Garmin Edge 530"));
+        assert_eq!(act.start, Some(datetime!(2024-05-04 8:00 UTC)));
+        assert_eq!(act.duration, 12 * 60);
+        assert_eq!(act.time, Some(4 * 60));
+        assert_eq!(act.distance, Some(2002));
+        assert_eq!(act.climb, Some(10));
+        assert_eq!(act.descend, Some(15));
+    }
+
+    #[test]
+    fn test_parse_tcx() {
+        let tcx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">
+  <Activities>
+    <Activity Sport="Biking">
+      <Id>2024-05-04T07:59:00Z</Id>
+      <Lap StartTime="2024-05-04T08:00:00Z">
+        <TotalTimeSeconds>400.4</TotalTimeSeconds>
+        <DistanceMeters>1500.0</DistanceMeters>
+        <Calories>50</Calories>
+        <Track>
+          <Trackpoint><Time>2024-05-04T08:00:00Z</Time><AltitudeMeters>500.0</AltitudeMeters><DistanceMeters>0.0</DistanceMeters></Trackpoint>
+          <Trackpoint><Time>2024-05-04T08:05:00Z</Time><AltitudeMeters>520.0</AltitudeMeters><DistanceMeters>1500.0</DistanceMeters></Trackpoint>
+        </Track>
+      </Lap>
+      <Lap StartTime="2024-05-04T08:10:00Z">
+        <TotalTimeSeconds>300.0</TotalTimeSeconds>
+        <DistanceMeters>1000.0</DistanceMeters>
+        <Calories>30</Calories>
+        <Track>
+          <Trackpoint><Time>2024-05-04T08:10:00Z</Time><AltitudeMeters>520.0</AltitudeMeters><DistanceMeters>1500.0</DistanceMeters></Trackpoint>
+          <Trackpoint><Time>2024-05-04T08:15:00Z</Time><AltitudeMeters>508.0</AltitudeMeters><DistanceMeters>2500.0</DistanceMeters></Trackpoint>
+        </Track>
+      </Lap>
+      <Creator><Name>Edge 830</Name></Creator>
+    </Activity>
+  </Activities>
+</TrainingCenterDatabase>"#;
+        let act = FileActivity::parse(tcx.as_bytes()).unwrap();
+        assert_eq!(act.what, Some(ActTypeId::from(1)));
+        assert_eq!(act.device_name.as_deref(), Some("Edge 830"));
+        assert_eq!(act.start, Some(datetime!(2024-05-04 8:00 UTC)));
+        assert_eq!(act.duration, 15 * 60);
+        // the laps take precedence over the track
+        assert_eq!(act.time, Some(700));
+        assert_eq!(act.distance, Some(2500));
+        assert_eq!(act.climb, Some(20));
+        assert_eq!(act.descend, Some(12));
+        assert_eq!(act.energy, Some(80));
+    }
+
+    /// a FIT file with the header and the given records
+    fn fit_file(records: &[&[u8]]) -> Vec<u8> {
+        let records = records.concat();
+        let mut res = vec![14, 0x10, 0x08, 0x08];
+        res.extend((records.len() as u32).to_le_bytes());
+        res.extend(b".FIT");
+        // the crc is not checked
+        res.extend([0, 0]);
+        res.extend(records);
+        res
+    }
+
+    #[test]
+    fn test_parse_fit() {
+        let start = datetime!(2024-05-04 8:00 UTC).unix_timestamp() - FIT_EPOCH;
+        let data = fit_file(&[
+            // definition of file_id as local 0 with a developer field
+            &[0x60, 0, 0, 0, 0, 2, 1, 2, 0x84, 8, 8, 7, 1, 0, 2, 0],
+            // manufacturer Garmin, product name and 2 bytes of developer data
+            &[0x00, 1, 0, b'E', b'd', b'g', b'e', 0, 0, 0, 0, 0xaa, 0xbb],
+            // definition of an unknown message as local 2
+            &[0x42, 0, 0, 20, 0, 1, 3, 2, 0x84],
+            &[0x02, 0x12, 0x34],
+            // big-endian definition of session as local 1
+            &[
+                0x41, 0, 1, 0, 18, 9, 2, 4, 0x86, 5, 1, 0, 7, 4, 0x86, 8, 4, 0x86, 9, 4, 0x86, 11,
+                2, 0x84, 22, 2, 0x84, 23, 2, 0x84, 48, 4, 0x86,
+            ],
+            // session with a compressed timestamp header for local 1
+            &[
+                [0x80 | 1 << 5 | 3].as_slice(),
+                &(start as u32).to_be_bytes(),
+                &[2],
+                &3_600_000u32.to_be_bytes(),
+                &3_000_000u32.to_be_bytes(),
+                &2_500_000u32.to_be_bytes(),
+                &600u16.to_be_bytes(),
+                &300u16.to_be_bytes(),
+                &290u16.to_be_bytes(),
+                &550_000u32.to_be_bytes(),
+            ]
+            .concat(),
+        ]);
+        let act = FileActivity::parse(&data).unwrap();
+        assert_eq!(act.what, Some(ActTypeId::from(1)));
+        assert_eq!(act.device_name.as_deref(), Some("Edge"));
+        assert_eq!(act.start, Some(datetime!(2024-05-04 8:00 UTC)));
+        assert_eq!(act.duration, 3600);
+        assert_eq!(act.time, Some(3000));
+        assert_eq!(act.distance, Some(25_000));
+        assert_eq!(act.climb, Some(300));
+        assert_eq!(act.descend, Some(290));
+        // the work wins over the calories
+        assert_eq!(act.energy, Some(550));
+
+        // without a session there is no activity
+        assert!(FileActivity::parse(&fit_file(&[&[0x40, 0, 0, 0, 0, 0]])).is_err());
+        // truncated record
+        assert!(FileActivity::parse(&fit_file(&[&[0x40, 0, 0, 0, 0, 1, 1]])).is_err());
+        // a climb which does not fit, and two sessions which only fit on their own
+        let session =
+            |climb: u32| -> Vec<u8> { [[0x00].as_slice(), &climb.to_le_bytes()].concat() };
+        let climb = |sessions: &[&[u8]]| {
+            let mut records: Vec<&[u8]> = vec![&[0x40, 0, 0, 18, 0, 1, 22, 4, 0x86]];
+            records.extend(sessions);
+            FileActivity::parse(&fit_file(&records))
+        };
+        assert_eq!(
+            climb(&[&session(2_000_000_000)]).unwrap().climb,
+            Some(2_000_000_000)
+        );
+        assert!(matches!(
+            climb(&[&session(3_000_000_000)]),
+            Err(Error::BadRequest(_))
+        ));
+        assert!(matches!(
+            climb(&[&session(2_000_000_000), &session(2_000_000_000)]),
+            Err(Error::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_unknown() {
+        assert!(FileActivity::parse(b"<html></html>").is_err());
+        assert!(FileActivity::parse(b"not an activity").is_err());
+    }
+}
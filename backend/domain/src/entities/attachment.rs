@@ -29,7 +29,10 @@ use serde_derive::{Deserialize, Serialize};
 use crate::traits::{AttachmentStore, Store};
 
 use crate::*;
-use time::OffsetDateTime;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+mod assembly;
+pub use assembly::*;
 
 /// Timeline of attachments
 ///
@@ -93,13 +96,12 @@ impl Attachment {
         }
     }
     /// return the calculated usage for the attachment
-    async fn calculate_usage(&self, store: &mut impl ActivityStore) -> TbResult<Usage> {
-        Ok(
-            Activity::find(self.gear, self.attached, self.detached, store)
-                .await?
-                .into_iter()
-                .fold(Usage::new(self.usage), |usage, act| usage + &act.usage()),
-        )
+    pub(crate) async fn calculate_usage(&self, store: &mut impl Store) -> TbResult<Usage> {
+        let rules = UsageRule::for_part(self.gear, store).await?;
+        let acts = Activity::find(self.gear, self.attached, self.detached, store).await?;
+        Ok(UsageRule::weigh(&rules, self.hook, acts)
+            .into_iter()
+            .fold(Usage::new(self.usage), |usage, (_, act)| usage + act))
     }
 
     pub(crate) async fn usage(&self, store: &mut impl UsageStore) -> TbResult<Usage> {
@@ -115,22 +117,28 @@ impl Attachment {
         time: OffsetDateTime,
         gear: PartId,
         hash: &mut SumHash,
+        user: &dyn Session,
         store: &mut impl Store,
     ) -> TbResult<OffsetDateTime> {
         debug!("-- moving {} to {}", self.part_id, gear);
-        *hash += self.detach(time, store).await?;
-        attach_one(self.part_id, time, gear, self.hook, hash, store).await
+        *hash += self.detach(time, user, store).await?;
+        attach_one(self.part_id, time, gear, self.hook, hash, user, store).await
     }
 
     /// change detached time for attachment
     ///
     /// * deletes the attachment for detached < attached
     /// * Does not check for collisions
-    async fn detach(mut self, time: OffsetDateTime, store: &mut impl Store) -> TbResult<Summary> {
+    async fn detach(
+        mut self,
+        time: OffsetDateTime,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
         trace!("detaching {} at {}", self.part_id, time);
 
         // delete the old attachment
-        let res = self.delete(store).await?;
+        let res = self.delete(user, store).await?;
         if self.attached >= time {
             // if it was detached at or before the attach time, we do not need to create a new attachment
             return Ok(res);
@@ -138,14 +146,18 @@ impl Attachment {
 
         // create a new attachment with the new detached time
         self.detached = time;
-        Ok(res + self.create(store).await?)
+        Ok(res + self.create(user, store).await?)
     }
 
     /// register and store a new attachment
     //
     /// - recalculates the usage counters in the attached assembly
     /// - returns all affected parts
-    async fn create(mut self, store: &mut impl Store) -> TbResult<Summary> {
+    pub(crate) async fn create(
+        mut self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
         trace!("create {self:?}");
 
         // create the Usage for the attachement
@@ -156,10 +168,11 @@ impl Attachment {
         let part = self.part_id.update_timestamps(self.attached, store).await?;
         let mut usages = vec![part.usage().read(store).await? + &usage, usage];
         // store the attachment in the database
-        let attachment = store
-            .attachment_create(self)
-            .await?
-            .add_details(&part.name, part.what);
+        let attachment = store.attachment_create(self).await?;
+        attachment
+            .audit(None, Some(&attachment), user, store)
+            .await?;
+        let attachment = attachment.add_details(&part.name, part.what);
 
         // recalculate the service usages and append to usages
         usages.append(&mut Service::recalculate(part.id, self.attached, store).await?);
@@ -180,11 +193,16 @@ impl Attachment {
     ///
     /// - recalculates the usage counters in the attached assembly
     /// - returns all affected parts
-    async fn delete(self, store: &mut impl Store) -> TbResult<Summary> {
+    pub(crate) async fn delete(
+        self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
         trace!("delete {self:?}");
 
         // delete the attachment on the db
         let att = AttachmentStore::delete(store, self).await?;
+        att.audit(Some(&att), None, user, store).await?;
         let usage = -att.usage.delete(store).await?;
 
         // recalc service usages
@@ -207,6 +225,31 @@ impl Attachment {
         })
     }
 
+    /// record the change of the attachment
+    ///
+    /// The change drops the undo of the user, operations which can be undone record it anew
+    async fn audit(
+        &self,
+        before: Option<&Attachment>,
+        after: Option<&Attachment>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        store.undo_delete(user.user_id()).await?;
+        let attached = self.attached.format(&Rfc3339).unwrap_or_default();
+        let id = format!("{}@{attached}", self.part_id);
+        AuditEntry::record(
+            user,
+            "attachment",
+            id,
+            Some(self.part_id),
+            before,
+            after,
+            store,
+        )
+        .await
+    }
+
     /// add redundant details for client simplicity
     fn add_details(self, name: &str, what: PartTypeId) -> AttachmentDetail {
         AttachmentDetail {
@@ -217,26 +260,31 @@ impl Attachment {
     }
 
     /// add redundant details from database for client simplicity
-    async fn read_details(self, store: &mut impl PartStore) -> TbResult<AttachmentDetail> {
+    pub(crate) async fn read_details(
+        self,
+        store: &mut impl PartStore,
+    ) -> TbResult<AttachmentDetail> {
         let part = self.part_id.read(store).await?;
         Ok(self.add_details(&part.name, part.what))
     }
 
-    pub(crate) async fn activities_by_part(
+    pub(crate) async fn usages_by_part(
         part: PartId,
+        rules: &[UsageRule],
         begin: OffsetDateTime,
         end: OffsetDateTime,
         store: &mut (impl AttachmentStore + ActivityStore),
-    ) -> TbResult<Vec<Activity>> {
+    ) -> TbResult<Vec<(OffsetDateTime, Usage)>> {
         use std::cmp::{max, min};
         let attachments = store.attachments_all_by_part(part).await?;
-        let mut activities = Vec::new();
+        let mut usages = Vec::new();
         for att in attachments {
             let begin = max(att.attached, begin);
             let end = min(att.detached, end);
-            activities.append(&mut Activity::find(att.gear, begin, end, store).await?);
+            let acts = Activity::find(att.gear, begin, end, store).await?;
+            usages.append(&mut UsageRule::weigh(rules, att.hook, acts));
         }
-        Ok(activities)
+        Ok(usages)
     }
 
     /// return all attachments with details for the parts in 'partlist'
@@ -255,8 +303,12 @@ impl Attachment {
         Ok((attachments, usages))
     }
 
+    /// account the usage of an activity of type `what` to the gear and all parts attached at `start`
+    ///
+    /// The usage is weighted per hook according to the usage rules of the owner of the gear
     pub(crate) async fn register_activity(
         gear: Option<PartId>,
+        what: ActTypeId,
         start: OffsetDateTime,
         usage: Usage,
         store: &mut impl Store,
@@ -265,27 +317,12 @@ impl Attachment {
             None => return Ok(Summary::default()),
             Some(x) => x,
         };
+        let (deltas, parts) = Self::activity_usages(gear, what, start, &usage, store).await?;
 
-        // get all attachment usages and add usage to it
         let mut usages = Vec::new();
-        let mut parts = Vec::new();
-
-        let attachments = store.attachment_get_by_gear_and_time(gear, start).await?;
-        for att in attachments.iter() {
-            usages.push(att.usage);
+        for (id, usage) in deltas {
+            usages.push(id.read(store).await? + usage);
         }
-
-        // get all parts from attachments, add usage and modify last_used
-        let partlist = attachments.iter().map(|a| a.part_id);
-        // we need to add gear since it is not attached
-        for part in partlist.chain([gear]) {
-            let part = part.update_timestamps(start, store).await?;
-            usages.push(part.usage());
-            usages.append(&mut Service::get_usageids(part.id, start, store).await?);
-            parts.push(part);
-        }
-
-        let usages = Usage::get_vec(&usages, store).await? + &usage;
         // store all updated usages
         Usage::update_vec(&usages, store).await?;
         Ok(Summary {
@@ -295,20 +332,62 @@ impl Attachment {
         })
     }
 
+    /// the usages affected by an activity of type `what` on the gear at `start`
+    ///
+    /// Updates the timestamps of the parts, but does not touch the usages.
+    ///
+    /// returns the usage ids with the weighted usage to add and the affected parts
+    pub(crate) async fn activity_usages(
+        gear: PartId,
+        what: ActTypeId,
+        start: OffsetDateTime,
+        usage: &Usage,
+        store: &mut impl Store,
+    ) -> TbResult<(Vec<(UsageId, Usage)>, Vec<Part>)> {
+        let gear_type = gear.read(store).await?.what;
+        let rules = UsageRule::for_part(gear, store).await?;
+
+        let mut deltas = Vec::new();
+        let mut parts = Vec::new();
+
+        let attachments = store.attachment_get_by_gear_and_time(gear, start).await?;
+        let hooks = attachments
+            .iter()
+            .map(|a| (a.part_id, a.hook, Some(a.usage)))
+            // we need to add gear since it is not attached
+            .chain([(gear, gear_type, None)]);
+
+        // get all attachment and part usages and modify last_used
+        for (part, hook, attachment) in hooks {
+            if !UsageRule::counts(&rules, what, hook) {
+                continue;
+            }
+            let part = part.update_timestamps(start, store).await?;
+            let mut ids: Vec<_> = attachment.into_iter().collect();
+            ids.push(part.usage());
+            ids.append(&mut Service::get_usageids(part.id, start, store).await?);
+            parts.push(part);
+            let usage = UsageRule::apply(&rules, what, hook, usage);
+            deltas.extend(ids.into_iter().map(|id| (id, usage.clone())));
+        }
+        Ok((deltas, parts))
+    }
+
     async fn detach_assembly(
         self,
         time: OffsetDateTime,
         all: bool,
+        user: &dyn Session,
         store: &mut impl Store,
     ) -> TbResult<Summary> {
         debug!("-- detaching {} at {}", self.part_id, time);
 
         let mut hash = SumHash::default();
         if all {
-            shift_subparts(self.gear, self.part_id, time, &mut hash, store).await?;
+            shift_subparts(self.gear, self.part_id, time, &mut hash, user, store).await?;
         }
         // detach the part
-        hash += self.detach(time, store).await?;
+        hash += self.detach(time, user, store).await?;
         Ok(hash.into())
     }
 }
@@ -323,23 +402,25 @@ async fn shift_subparts(
     to: PartId,
     time: OffsetDateTime,
     hash: &mut SumHash,
+    user: &dyn Session,
     store: &mut impl Store,
 ) -> TbResult<()> {
     let sub_attachments = subattachments(to, from, time, store).await?;
     for attachment in sub_attachments {
-        attachment.shift(time, to, hash, store).await?;
+        attachment.shift(time, to, hash, user, store).await?;
     }
     Ok(())
 }
 
 /// find all subparts which are attached to target at self.time
-async fn subattachments(
+pub(crate) async fn subattachments(
     part: PartId,
     gear: PartId,
     time: OffsetDateTime,
     store: &mut impl Store,
 ) -> TbResult<Vec<Attachment>> {
-    let types = part.read(store).await?.what.subtypes();
+    let part = part.read(store).await?;
+    let types = part.what.subtypes(part.owner);
     store
         .assembly_get_by_types_time_and_gear(types, gear, time)
         .await
@@ -372,6 +453,7 @@ async fn attach_one(
     gear: PartId,
     hook: PartTypeId,
     hash: &mut SumHash,
+    user: &dyn Session,
     store: &mut impl Store,
 ) -> TbResult<OffsetDateTime> {
     // when does the current attachment end
@@ -380,7 +462,7 @@ async fn attach_one(
     // we need this to reattach subparts
     let mut det = MAX_TIME;
 
-    let what = part_id.set_owner_and_shop(gear, store).await?.what;
+    let what = part_id.set_owner_and_shop(gear, user, store).await?.what;
 
     if let Some(next) = store
         .attachment_find_successor(part_id, gear, hook, time, what)
@@ -404,7 +486,7 @@ async fn attach_one(
             // the previous one is the real next so we keep 'det'!
             // 'next' will be replaced by 'self' but 'end' is taken from 'next'
             end = next.detached;
-            *hash += next.delete(store).await?;
+            *hash += next.delete(user, store).await?;
         } else {
             trace!(
                 "changing gear/hook from {}/{} to {}/{}",
@@ -424,12 +506,12 @@ async fn attach_one(
     {
         Some(prev) => {
             trace!("adjacent starting {}", prev.attached);
-            *hash += prev.detach(end, store).await?
+            *hash += prev.detach(end, user, store).await?
         }
         _ => {
             // trace!("create {:?}\n", self);
             *hash += Attachment::new(part_id, time, gear, hook, end)
-                .create(store)
+                .create(user, store)
                 .await?;
         }
     }
@@ -449,7 +531,7 @@ pub async fn attach_assembly(
     let time = round_time(time);
     // check user
     let part = part.part(user, store).await?;
-    let parttype = part.what.get()?;
+    let parttype = part.what.visible(part.owner)?;
 
     let geartypeid = gear.part(user, store).await?.what;
 
@@ -474,7 +556,7 @@ pub async fn attach_assembly(
     // detach part if it is attached already
     if let Some(attachment) = store.attachment_get_by_part_and_time(part.id, time).await? {
         debug!("detaching self assembly");
-        hash += attachment.detach_assembly(time, all, store).await?;
+        hash += attachment.detach_assembly(time, all, user, store).await?;
     }
 
     // if there is a part attached to the gear at the hook, detach it
@@ -483,16 +565,16 @@ pub async fn attach_assembly(
         .await?;
     if let Some(attachment) = attachment {
         debug!("detaching predecessor assembly {}", attachment.part_id);
-        hash += attachment.detach_assembly(time, all, store).await?;
+        hash += attachment.detach_assembly(time, all, user, store).await?;
     }
 
     // reattach the assembly
     debug!("- attaching assembly {} to {}", part.id, gear);
-    let end = attach_one(part.id, time, gear, hook, &mut hash, store).await?;
+    let end = attach_one(part.id, time, gear, hook, &mut hash, user, store).await?;
     if all {
         let subparts = subattachments(part.id, part.id, time, store).await?;
         for attachment in subparts {
-            let detached = attachment.shift(time, gear, &mut hash, store).await?;
+            let detached = attachment.shift(time, gear, &mut hash, user, store).await?;
             if detached == end && end < attachment.detached {
                 trace!(
                     "reattaching {} to {} at {}",
@@ -505,6 +587,7 @@ pub async fn attach_assembly(
                     part.id,
                     attachment.hook,
                     &mut hash,
+                    user,
                     store,
                 )
                 .await?;
@@ -528,7 +611,7 @@ pub async fn detach_assembly(
         .attachment_get_by_part_and_time(part_id, time)
         .await?
         .ok_or(Error::NotFound("part not attached".into()))?;
-    attachment.detach_assembly(time, all, store).await
+    attachment.detach_assembly(time, all, user, store).await
 }
 
 pub async fn dispose_assembly(
@@ -555,8 +638,8 @@ pub async fn dispose_assembly(
     }
 
     let mut res = SumHash::default();
-    res += part_id.dispose(time, store).await?;
-    res += dispose_subparts(part_id, time, all, store).await?;
+    res += part_id.dispose(time, user, store).await?;
+    res += dispose_subparts(part_id, time, all, user, store).await?;
 
     Ok(res.into())
 }
@@ -565,6 +648,7 @@ async fn dispose_subparts(
     part: PartId,
     time: OffsetDateTime,
     all: bool,
+    user: &dyn Session,
     store: &mut impl Store,
 ) -> TbResult<Summary> {
     let sub_attachments = subattachments(part, part, time, store).await?;
@@ -573,9 +657,9 @@ async fn dispose_subparts(
         let attachments = store.attachments_all_by_part(attachment.part_id).await?;
         if !all || attachments.iter().any(|a| a.attached > time) {
             debug!("-- detaching {}", attachment.part_id);
-            res += attachment.detach(time, store).await?
+            res += attachment.detach(time, user, store).await?
         } else {
-            res += attachment.part_id.dispose(time, store).await?
+            res += attachment.part_id.dispose(time, user, store).await?
         }
     }
     Ok(res.into())
@@ -589,10 +673,10 @@ pub async fn recover_assembly(
 ) -> Result<Summary, Error> {
     let mut res = SumHash::default();
     if let Some(time) = part.part(user, store).await?.disposed_at {
-        res += part.restore(store).await?;
+        res += part.restore(user, store).await?;
         if all {
             for attachment in subattachments(part, part, time, store).await? {
-                res += attachment.part_id.restore(store).await?;
+                res += attachment.part_id.restore(user, store).await?;
             }
         }
         Ok(res.into())
@@ -611,3 +695,16 @@ pub async fn is_attached(
         .await?
         .is_some())
 }
+
+/// The part attached to hook 1 of gear 1
+#[cfg(test)]
+pub(crate) fn att(part: i32, attached: OffsetDateTime, detached: OffsetDateTime) -> Attachment {
+    Attachment {
+        part_id: part.into(),
+        attached,
+        gear: 1.into(),
+        hook: 1.into(),
+        detached,
+        usage: UsageId::default(),
+    }
+}
@@ -0,0 +1,175 @@
+//! The assembly of a gear at a point in time
+//!
+//! All parts of an assembly are attached to the gear itself. The hook of an attachment
+//! is the type of the part it hangs on, e.g. a tire hooks into the front or rear wheel.
+//! This allows to rebuild the tree of the assembly from the attachments at any time.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::subattachments;
+use crate::*;
+
+/// A part in the assembly tree
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssemblyNode {
+    /// the hook the part is attached to, None for the gear itself
+    pub hook: Option<PartTypeId>,
+    /// when it was attached, None for the gear itself
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub attached: Option<OffsetDateTime>,
+    pub part: Part,
+    /// the usage of the part at that time
+    pub usage: Usage,
+    /// the parts attached to this part
+    pub children: Vec<AssemblyNode>,
+}
+
+/// A position in the assembly which changed between two points in time
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssemblyChange {
+    pub hook: PartTypeId,
+    pub what: PartTypeId,
+    /// the part at the position before, None if it was empty
+    pub before: Option<Part>,
+    /// the part at the position afterwards, None if it was removed
+    pub after: Option<Part>,
+}
+
+/// move all nodes hanging on `what` from `pool` into a tree
+fn build(what: PartTypeId, pool: &mut Vec<AssemblyNode>) -> Vec<AssemblyNode> {
+    let (mut children, rest): (Vec<_>, Vec<_>) = std::mem::take(pool)
+        .into_iter()
+        .partition(|n| n.hook == Some(what));
+    *pool = rest;
+    children.sort_by_key(|c| (c.part.what, c.hook));
+    for child in &mut children {
+        child.children = build(child.part.what, pool);
+    }
+    children
+}
+
+/// the parts attached at `time` by position
+async fn positions(
+    gear: PartId,
+    time: OffsetDateTime,
+    store: &mut impl Store,
+) -> TbResult<BTreeMap<(PartTypeId, PartTypeId), Part>> {
+    let mut res = BTreeMap::new();
+    for att in subattachments(gear, gear, time, store).await? {
+        let part = att.part_id.read(store).await?;
+        res.insert((att.hook, part.what), part);
+    }
+    Ok(res)
+}
+
+impl PartId {
+    /// The assembly tree of the gear at time `at`
+    ///
+    /// Every part comes with its usage at that time
+    pub async fn assembly(
+        self,
+        at: OffsetDateTime,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<AssemblyNode> {
+        let gear = self.part(user, store).await?;
+
+        let mut pool = Vec::new();
+        for att in subattachments(self, self, at, store).await? {
+            pool.push(AssemblyNode {
+                hook: Some(att.hook),
+                attached: Some(att.attached),
+                part: att.part_id.read(store).await?,
+                usage: att.part_id.usage_until(at, store).await?,
+                children: Vec::new(),
+            });
+        }
+
+        let mut children = build(gear.what, &mut pool);
+        // parts which lost their parent are still part of the assembly
+        children.append(&mut pool);
+        Ok(AssemblyNode {
+            hook: None,
+            attached: None,
+            usage: self.usage_until(at, store).await?,
+            part: gear,
+            children,
+        })
+    }
+
+    /// All positions of the assembly of the gear which differ between `from` and `to`
+    pub async fn assembly_diff(
+        self,
+        from: OffsetDateTime,
+        to: OffsetDateTime,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<AssemblyChange>> {
+        self.checkuser(user, store).await?;
+        let mut before = positions(self, from, store).await?;
+        let mut after = positions(self, to, store).await?;
+
+        let keys: BTreeSet<_> = before.keys().chain(after.keys()).copied().collect();
+        Ok(keys
+            .into_iter()
+            .filter_map(|(hook, what)| {
+                let before = before.remove(&(hook, what));
+                let after = after.remove(&(hook, what));
+                let unchanged = before.as_ref().map(|p| p.id) == after.as_ref().map(|p| p.id);
+                (!unchanged).then_some(AssemblyChange {
+                    hook,
+                    what,
+                    before,
+                    after,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: i32, what: i32, hook: i32) -> AssemblyNode {
+        AssemblyNode {
+            hook: Some(hook.into()),
+            attached: None,
+            part: Part {
+                id: id.into(),
+                owner: 1.into(),
+                what: what.into(),
+                name: format!("part {id}"),
+                vendor: String::new(),
+                model: String::new(),
+                purchase: OffsetDateTime::UNIX_EPOCH,
+                last_used: OffsetDateTime::UNIX_EPOCH,
+                disposed_at: None,
+                usage: UsageId::default(),
+                source: None,
+                notes: String::new(),
+                shop: None,
+                price: None,
+            },
+            usage: Usage::default(),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn build_tree() {
+        // bike with a rear wheel carrying a tire and a cassette, and a chain
+        let mut pool = vec![node(3, 3, 5), node(2, 5, 1), node(4, 9, 5), node(5, 4, 1)];
+        let tree = build(1.into(), &mut pool);
+        assert!(pool.is_empty());
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].part.id, 5.into());
+        assert!(tree[0].children.is_empty());
+        assert_eq!(tree[1].part.id, 2.into());
+        let children: Vec<_> = tree[1].children.iter().map(|c| c.part.id).collect();
+        assert_eq!(children, vec![3.into(), 4.into()]);
+    }
+}
@@ -0,0 +1,151 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! An append-only log of changes
+//!
+//! Every change to parts, attachments, services, activities, service plans, stock, usage rules,
+//! gear rules, configurations, private part types and notification preferences is recorded
+//! with the user who made it and the state of the entity before and after the change.
+//! A created entity has no state before, a deleted one none after. Attachments are never
+//! changed in place, so detaching a part shows up as the deletion of the old attachment
+//! and the creation of a shorter one.
+//!
+//! The usages are derived from these entities and are not recorded. Neither is the global
+//! type catalog, which is maintained by the admins.
+
+use std::fmt::Display;
+
+use derive_more::{Display, From, Into};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// Do not return more entries at once
+const MAX_ENTRIES: i64 = 1000;
+
+#[derive(Clone, Copy, Debug, Display, From, Into, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditId(i64);
+
+/// A recorded change
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// The primary key, ascending with time
+    pub id: AuditId,
+    /// when the change was made
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    /// The user who made the change
+    pub actor: UserId,
+    /// The user the actor was acting as
+    ///
+    /// differs from the actor if an admin acts for a user
+    pub user: UserId,
+    /// The kind of the entity, e.g. "part" or "attachment"
+    pub entity: String,
+    /// The id of the entity
+    pub entity_id: String,
+    /// The part the entity belongs to
+    pub part: Option<PartId>,
+    /// The entity before the change
+    pub before: Option<Value>,
+    /// The entity after the change
+    pub after: Option<Value>,
+}
+
+fn to_json<T: Serialize>(value: Option<&T>) -> TbResult<Option<Value>> {
+    value
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| Error::AnyFailure(e.into()))
+}
+
+impl AuditEntry {
+    /// record a change of an entity
+    ///
+    /// `before` is `None` if the entity was created, `after` is `None` if it was deleted
+    pub(crate) async fn record<T: Serialize>(
+        user: &dyn Session,
+        entity: &str,
+        id: impl Display,
+        part: Option<PartId>,
+        before: Option<&T>,
+        after: Option<&T>,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        store
+            .audit_append(
+                user.actor(),
+                user.user_id(),
+                entity,
+                id.to_string(),
+                part,
+                to_json(before)?,
+                to_json(after)?,
+            )
+            .await
+    }
+
+    /// The changes to the part and the entities belonging to it, newest first
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - only return entries older than this one
+    /// * `limit` - return at most that many entries
+    pub async fn for_part(
+        part: PartId,
+        before: Option<AuditId>,
+        limit: i64,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<AuditEntry>> {
+        part.checkuser(user, store).await?;
+        store
+            .audit_for_part(part, before, limit.clamp(1, MAX_ENTRIES))
+            .await
+    }
+
+    /// The changes made as user `id`, newest first
+    ///
+    /// Users can only see their own changes, admins can see all.
+    ///
+    /// # Arguments
+    ///
+    /// * `before` - only return entries older than this one
+    /// * `limit` - return at most that many entries
+    pub async fn for_user(
+        id: UserId,
+        before: Option<AuditId>,
+        limit: i64,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<AuditEntry>> {
+        if !user.is_admin() {
+            user.check_owner(
+                id,
+                format!("user {} cannot access the log of user {id}", user.user_id()),
+            )?;
+        }
+        store
+            .audit_for_user(id, before, limit.clamp(1, MAX_ENTRIES))
+            .await
+    }
+}
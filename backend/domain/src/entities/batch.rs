@@ -0,0 +1,247 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Several operations in one transaction
+//!
+//! A batch executes a list of operations in order. Every operation sees the result of the
+//! operations before it. If one of them fails the whole batch fails and the caller must not
+//! commit. Since the operations only touch the store, a caller can preview the consequences
+//! of a batch by running it and rolling back.
+
+use serde_derive::Deserialize;
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// Do not allow batches bigger than that
+const MAX_OPERATIONS: usize = 100;
+
+/// An operation of a batch
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    /// attach the part to the hook of the gear
+    Attach {
+        part_id: PartId,
+        #[serde(with = "time::serde::rfc3339")]
+        time: OffsetDateTime,
+        gear: PartId,
+        hook: PartTypeId,
+        /// attach the part with its subparts
+        #[serde(default)]
+        all: bool,
+    },
+    /// detach the part
+    Detach {
+        part_id: PartId,
+        #[serde(with = "time::serde::rfc3339")]
+        time: OffsetDateTime,
+        /// detach the part with its subparts
+        #[serde(default)]
+        all: bool,
+    },
+    /// dispose the part
+    Dispose {
+        part_id: PartId,
+        #[serde(with = "time::serde::rfc3339")]
+        time: OffsetDateTime,
+        /// dispose the part with its subparts
+        #[serde(default)]
+        all: bool,
+    },
+    /// log a service for the part
+    Service {
+        part_id: PartId,
+        #[serde(with = "time::serde::rfc3339")]
+        time: OffsetDateTime,
+        name: String,
+        #[serde(default)]
+        notes: String,
+        #[serde(default)]
+        plans: Vec<ServicePlanId>,
+        #[serde(default)]
+        cost: Option<Money>,
+    },
+    /// change the data of the part
+    ChangePart {
+        part_id: PartId,
+        name: String,
+        vendor: String,
+        model: String,
+        #[serde(with = "time::serde::rfc3339")]
+        purchase: OffsetDateTime,
+        notes: String,
+        /// null removes the price, a missing price keeps it
+        #[serde(default, with = "::serde_with::rust::double_option")]
+        price: Option<Option<Money>>,
+    },
+}
+
+/// prefix the message of the error with the failing operation
+fn in_operation(err: Error, index: usize) -> Error {
+    let msg = |msg| format!("operation {index}: {msg}");
+    match err {
+        Error::Forbidden(m) => Error::Forbidden(msg(m)),
+        Error::NotFound(m) => Error::NotFound(msg(m)),
+        Error::BadRequest(m) => Error::BadRequest(msg(m)),
+        Error::Conflict(m) => Error::Conflict(msg(m)),
+        err => err,
+    }
+}
+
+impl Operation {
+    /// can `Undo` revert the operation completely
+    ///
+    /// Services and changes of the part data are not part of an undo.
+    pub fn undoable(&self) -> bool {
+        matches!(
+            self,
+            Operation::Attach { .. } | Operation::Detach { .. } | Operation::Dispose { .. }
+        )
+    }
+
+    async fn execute(self, user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
+        match self {
+            Operation::Attach {
+                part_id,
+                time,
+                gear,
+                hook,
+                all,
+            } => attach_assembly(user, part_id, time, gear, hook, all, store).await,
+            Operation::Detach { part_id, time, all } => {
+                detach_assembly(user, part_id, time, all, store).await
+            }
+            Operation::Dispose { part_id, time, all } => {
+                dispose_assembly(user, part_id, time, all, store).await
+            }
+            Operation::Service {
+                part_id,
+                time,
+                name,
+                notes,
+                plans,
+                cost,
+            } => {
+                part_id.checkuser(user, store).await?;
+                Service::create(part_id, time, name, notes, None, plans, cost, user, store).await
+            }
+            Operation::ChangePart {
+                part_id,
+                name,
+                vendor,
+                model,
+                purchase,
+                notes,
+                price,
+            } => {
+                let part = part_id
+                    .change(name, vendor, model, purchase, notes, price, user, store)
+                    .await?;
+                Ok(Summary {
+                    parts: vec![part],
+                    ..Default::default()
+                })
+            }
+        }
+    }
+
+    /// Execute the operations in order
+    ///
+    /// # Arguments
+    ///
+    /// * `operations` - the operations to execute
+    ///
+    /// # Returns
+    ///
+    /// The combined summary of all operations.
+    /// The store must not be committed if an error is returned.
+    pub async fn batch(
+        operations: Vec<Operation>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        if operations.len() > MAX_OPERATIONS {
+            return Err(Error::BadRequest(format!(
+                "a batch can have at most {MAX_OPERATIONS} operations"
+            )));
+        }
+        debug!(
+            "executing {} operations for user {}",
+            operations.len(),
+            user.user_id()
+        );
+        let mut hash = SumHash::default();
+        for (index, op) in operations.into_iter().enumerate() {
+            hash += op
+                .execute(user, store)
+                .await
+                .map_err(|e| in_operation(e, index))?;
+        }
+        Ok(hash.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn parse_operations() {
+        let ops: Vec<Operation> = serde_json::from_str(
+            r#"[
+                {"op": "attach", "part_id": 2, "time": "2024-01-01T00:00:00Z", "gear": 1, "hook": 1},
+                {"op": "detach", "part_id": 3, "time": "2024-01-01T00:00:00Z", "all": true},
+                {"op": "service", "part_id": 2, "time": "2024-01-02T00:00:00Z", "name": "waxed"},
+                {"op": "change_part", "part_id": 2, "name": "chain", "vendor": "", "model": "",
+                 "purchase": "2023-01-01T00:00:00Z", "notes": "", "price": null}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ops[0],
+            Operation::Attach {
+                part_id: 2.into(),
+                time: datetime!(2024-01-01 0:00 UTC),
+                gear: 1.into(),
+                hook: 1.into(),
+                all: false,
+            }
+        );
+        assert!(matches!(ops[1], Operation::Detach { all: true, .. }));
+        assert!(
+            matches!(&ops[2], Operation::Service { plans, cost: None, .. } if plans.is_empty())
+        );
+        assert!(matches!(
+            ops[3],
+            Operation::ChangePart {
+                price: Some(None),
+                ..
+            }
+        ));
+        // only attachments and disposals can be undone
+        assert!(ops[0].undoable() && ops[1].undoable());
+        assert!(!ops[2].undoable() && !ops[3].undoable());
+
+        assert!(serde_json::from_str::<Operation>(r#"{"op": "delete", "part_id": 2}"#).is_err());
+    }
+}
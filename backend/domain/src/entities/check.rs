@@ -0,0 +1,274 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Consistency checks for attachments and usages
+//!
+//! The usages are updated incrementally whenever an activity or an attachment changes.
+//! The checks recalculate them from the activities and compare them with the stored ones.
+//! They also find attachments which overlap and attachments of disposed parts.
+//!
+//! A repair stores the recalculated usages and deletes orphaned usages.
+//! Overlapping attachments and attachments of disposed parts need a decision of the user
+//! and are only reported.
+
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// An inconsistency found by a check
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Inconsistency {
+    /// The part is attached twice at the same time or two parts share a position
+    Overlap {
+        first: Attachment,
+        second: Attachment,
+    },
+    /// The attachment starts or ends after the part was disposed
+    AttachedWhenDisposed {
+        attachment: Attachment,
+        #[serde(with = "time::serde::rfc3339")]
+        disposed_at: OffsetDateTime,
+    },
+    /// The stored usage differs from the recalculated one
+    WrongUsage {
+        /// "part", "attachment" or "service"
+        entity: String,
+        id: String,
+        stored: Usage,
+        expected: Usage,
+    },
+    /// Nothing refers to the usage
+    OrphanedUsage { usage: UsageId },
+}
+
+/// The result of a check
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CheckReport {
+    /// The user checked, none for checks of the whole database
+    pub user: Option<UserId>,
+    pub issues: Vec<Inconsistency>,
+    /// The number of repaired issues
+    pub repaired: usize,
+}
+
+/// all pairs of attachments overlapping in time
+///
+/// Every attachment is compared with the one reaching furthest among the earlier ones
+fn overlaps(mut atts: Vec<Attachment>) -> Vec<(Attachment, Attachment)> {
+    atts.sort_by_key(|a| a.attached);
+    let mut res = Vec::new();
+    let mut reach: Option<Attachment> = None;
+    for att in atts {
+        if let Some(prev) = reach {
+            if att.attached < prev.detached {
+                res.push((prev, att));
+            }
+            if prev.detached >= att.detached {
+                continue;
+            }
+        }
+        reach = Some(att);
+    }
+    res
+}
+
+/// does the attachment start or end after the disposal
+fn after_disposal(att: &Attachment, disposed_at: OffsetDateTime) -> bool {
+    att.attached > disposed_at || (att.detached > disposed_at && att.detached < MAX_TIME)
+}
+
+/// compare the stored usage with the expected one
+async fn check_usage(
+    entity: &str,
+    id: impl ToString,
+    expected: Usage,
+    issues: &mut Vec<Inconsistency>,
+    store: &mut impl Store,
+) -> TbResult<()> {
+    let stored = expected.id.read(store).await?;
+    if stored != expected {
+        issues.push(Inconsistency::WrongUsage {
+            entity: entity.to_string(),
+            id: id.to_string(),
+            stored,
+            expected,
+        });
+    }
+    Ok(())
+}
+
+impl UserId {
+    /// Check the attachments and usages of the parts of the user
+    ///
+    /// # Arguments
+    ///
+    /// * `repair` - store the recalculated usages
+    ///
+    /// # Returns
+    ///
+    /// The inconsistencies found
+    pub async fn check(self, repair: bool, store: &mut impl Store) -> TbResult<CheckReport> {
+        info!("checking user {self}");
+        let parts = Part::get_all(&self, store).await?;
+        let types: HashMap<_, _> = parts.iter().map(|p| (p.id, p.what)).collect();
+        let mut issues = Vec::new();
+
+        for part in &parts {
+            let atts = store.attachments_all_by_part(part.id).await?;
+            for (first, second) in overlaps(atts.clone()) {
+                issues.push(Inconsistency::Overlap { first, second });
+            }
+            if let Some(disposed_at) = part.disposed_at {
+                for attachment in atts.iter().filter(|a| after_disposal(a, disposed_at)) {
+                    issues.push(Inconsistency::AttachedWhenDisposed {
+                        attachment: *attachment,
+                        disposed_at,
+                    });
+                }
+            }
+
+            // every position of a gear holds one part at a time
+            if part.what.is_main()? {
+                let mut positions: HashMap<_, Vec<_>> = HashMap::new();
+                for att in store.attachments_all_by_gear(part.id).await? {
+                    let what = match types.get(&att.part_id) {
+                        Some(what) => *what,
+                        None => att.part_id.read(store).await?.what,
+                    };
+                    positions.entry((att.hook, what)).or_default().push(att);
+                }
+                for atts in positions.into_values() {
+                    for (first, second) in overlaps(atts) {
+                        issues.push(Inconsistency::Overlap { first, second });
+                    }
+                }
+            }
+
+            for att in &atts {
+                let expected = att.calculate_usage(store).await?;
+                check_usage("attachment", att.usage, expected, &mut issues, store).await?;
+            }
+            for service in store.services_by_part(part.id).await? {
+                let expected = service.calculate_usage(store).await?;
+                check_usage("service", service.id, expected, &mut issues, store).await?;
+            }
+            let expected = part
+                .id
+                .usages(MIN_TIME, MAX_TIME, store)
+                .await?
+                .into_iter()
+                .fold(Usage::new(part.usage()), |usage, (_, act)| usage + act);
+            check_usage("part", part.id, expected, &mut issues, store).await?;
+        }
+
+        let mut repaired = 0;
+        if repair {
+            let usages: Vec<_> = issues
+                .iter()
+                .filter_map(|i| match i {
+                    Inconsistency::WrongUsage { expected, .. } => Some(expected),
+                    _ => None,
+                })
+                .collect();
+            Usage::update_vec(&usages, store).await?;
+            repaired = usages.len();
+        }
+        if !issues.is_empty() {
+            warn!("user {self} has {} inconsistencies", issues.len());
+        }
+        Ok(CheckReport {
+            user: Some(self),
+            issues,
+            repaired,
+        })
+    }
+}
+
+impl Usage {
+    /// Find the usages nothing refers to
+    ///
+    /// # Arguments
+    ///
+    /// * `repair` - delete them
+    pub async fn check_orphans(repair: bool, store: &mut impl Store) -> TbResult<CheckReport> {
+        let orphans = store.usages_orphaned().await?;
+        let mut repaired = 0;
+        if repair {
+            let usages: Vec<_> = orphans.iter().map(|id| Usage::new(*id)).collect();
+            repaired = store.usages_delete(&usages).await?;
+        }
+        Ok(CheckReport {
+            user: None,
+            issues: orphans
+                .into_iter()
+                .map(|usage| Inconsistency::OrphanedUsage { usage })
+                .collect(),
+            repaired,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn find_overlaps() {
+        let jan = datetime!(2024-01-01 0:00 UTC);
+        let feb = datetime!(2024-02-01 0:00 UTC);
+        let mar = datetime!(2024-03-01 0:00 UTC);
+        let apr = datetime!(2024-04-01 0:00 UTC);
+
+        // adjacent attachments do not overlap
+        assert!(
+            overlaps(vec![
+                att(2, feb, mar),
+                att(3, jan, feb),
+                att(4, mar, MAX_TIME)
+            ])
+            .is_empty()
+        );
+
+        let long = att(2, jan, apr);
+        assert_eq!(
+            overlaps(vec![att(4, mar, MAX_TIME), long, att(3, feb, mar)]),
+            vec![(long, att(3, feb, mar)), (long, att(4, mar, MAX_TIME))]
+        );
+    }
+
+    #[test]
+    fn disposal() {
+        let jan = datetime!(2024-01-01 0:00 UTC);
+        let feb = datetime!(2024-02-01 0:00 UTC);
+        let mar = datetime!(2024-03-01 0:00 UTC);
+
+        // a disposed part may stay attached
+        assert!(!after_disposal(&att(2, jan, MAX_TIME), feb));
+        assert!(!after_disposal(&att(2, jan, feb), feb));
+        assert!(after_disposal(&att(2, jan, mar), feb));
+        assert!(after_disposal(&att(2, mar, MAX_TIME), feb));
+    }
+}
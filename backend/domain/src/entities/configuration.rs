@@ -0,0 +1,283 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Named configurations of a gear
+//!
+//! A `Configuration` is a set of parts and the hooks they belong to on a gear,
+//! e.g. the winter wheelset with spiked tires. Applying a configuration attaches all
+//! of its parts at once. Whatever is attached at the same position gets detached.
+//! Parts which are not mentioned in the configuration stay where they are.
+
+use derive_more::{Display, From, Into};
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// A part of a configuration and where it belongs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigPart {
+    pub part: PartId,
+    pub hook: PartTypeId,
+}
+
+/// A named set of parts for a gear
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Configuration {
+    /// The primary key
+    pub id: ConfigurationId,
+    /// The owner
+    pub owner: UserId,
+    /// The gear the configuration belongs to
+    pub gear: PartId,
+    /// The name of the configuration
+    pub name: String,
+    /// The parts and their hooks
+    pub parts: Vec<ConfigPart>,
+}
+
+#[derive(Clone, Copy, Debug, Display, From, Into, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigurationId(i32);
+
+/// check that the parts can be attached to the gear
+async fn check_parts(
+    gear: PartId,
+    parts: &[ConfigPart],
+    user: &dyn Session,
+    store: &mut impl Store,
+) -> TbResult<()> {
+    let gear = gear.part(user, store).await?;
+    if !gear.what.is_main()? {
+        return Err(Error::BadRequest(format!("part {} is not a gear", gear.id)));
+    }
+    let mut names = Vec::new();
+    let mut positions = Vec::new();
+    for ConfigPart { part, hook } in parts {
+        let part = part.part(user, store).await?;
+        let parttype = part.what.get()?;
+        if !parttype.hooks.contains(hook)
+            || !(parttype.main == gear.what || parttype.hooks.contains(&gear.what))
+        {
+            return Err(Error::BadRequest(format!(
+                "{} cannot be attached to hook {hook} of {}",
+                part.name, gear.name
+            )));
+        }
+        positions.push((part.id, part.what, *hook));
+        names.push(part.name);
+    }
+    if let Some(i) = conflict(&positions) {
+        return Err(Error::BadRequest(format!(
+            "{} conflicts with another part of the configuration",
+            names[i]
+        )));
+    }
+    Ok(())
+}
+
+/// find the first part which is already in the configuration or takes the position of another
+///
+/// `parts` holds the id, the type and the hook of the part.
+/// A part can only be at one position and a position can only hold one part.
+fn conflict(parts: &[(PartId, PartTypeId, PartTypeId)]) -> Option<usize> {
+    (0..parts.len()).find(|&i| {
+        let (id, what, hook) = parts[i];
+        parts[..i]
+            .iter()
+            .any(|&(other, w, h)| other == id || (w == what && h == hook))
+    })
+}
+
+/// order the parts so that every part comes after the part it hooks into
+///
+/// `parts` holds the type of the part and its hook
+fn attach_order(parts: &[(PartTypeId, PartTypeId)]) -> Vec<usize> {
+    let depth = |mut hook: PartTypeId| {
+        let mut depth = 0;
+        while let Some((_, parent)) = parts.iter().find(|(what, _)| *what == hook) {
+            depth += 1;
+            if depth > parts.len() {
+                break;
+            }
+            hook = *parent;
+        }
+        depth
+    };
+    let mut order: Vec<_> = (0..parts.len()).collect();
+    order.sort_by_key(|i| depth(parts[*i].1));
+    order
+}
+
+impl ConfigurationId {
+    /// get the configuration, checks that the user owns it
+    pub async fn get(self, user: &dyn Session, store: &mut impl Store) -> TbResult<Configuration> {
+        let config = store.configuration_get(self).await?;
+        user.check_owner(
+            config.owner,
+            format!("user {} cannot access configuration {self}", user.user_id()),
+        )?;
+        Ok(config)
+    }
+
+    pub async fn update(
+        self,
+        name: String,
+        parts: Vec<ConfigPart>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Configuration> {
+        let config = self.get(user, store).await?;
+        check_parts(config.gear, &parts, user, store).await?;
+        let new = store
+            .configuration_update(Configuration {
+                name,
+                parts,
+                ..config.clone()
+            })
+            .await?;
+        new.audit(Some(&config), Some(&new), user, store).await?;
+        Ok(new)
+    }
+
+    pub async fn delete(
+        self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<ConfigurationId> {
+        let config = self.get(user, store).await?;
+        store.configuration_delete(self).await?;
+        config.audit(Some(&config), None, user, store).await?;
+        Ok(self)
+    }
+
+    /// Attach all parts of the configuration to its gear at `time`
+    ///
+    /// Parts are attached with their subparts. Parts which are already in place are skipped.
+    ///
+    /// # Returns
+    ///
+    /// The summary of all attachments and detachments
+    pub async fn apply(
+        self,
+        time: OffsetDateTime,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        let time = round_time(time);
+        let config = self.get(user, store).await?;
+        check_parts(config.gear, &config.parts, user, store).await?;
+        info!(
+            "applying configuration {} to gear {}",
+            config.name, config.gear
+        );
+
+        let mut parts = Vec::new();
+        for ConfigPart { part, hook } in &config.parts {
+            let part = part.read(store).await?;
+            if part.disposed_at.is_some_and(|d| d <= time) {
+                return Err(Error::BadRequest(format!("{} is disposed", part.name)));
+            }
+            parts.push((part.what, *hook));
+        }
+
+        let mut hash = SumHash::default();
+        for i in attach_order(&parts) {
+            let ConfigPart { part, hook } = config.parts[i];
+            let current = store.attachment_get_by_part_and_time(part, time).await?;
+            if current.is_some_and(|a| a.gear == config.gear && a.hook == hook) {
+                continue;
+            }
+            hash += attach_assembly(user, part, time, config.gear, hook, true, store).await?;
+        }
+        Ok(hash.into())
+    }
+}
+
+impl Configuration {
+    /// create a configuration for the gear
+    pub async fn create(
+        gear: PartId,
+        name: String,
+        parts: Vec<ConfigPart>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Configuration> {
+        check_parts(gear, &parts, user, store).await?;
+        let config = store
+            .configuration_create(user.user_id(), gear, name, parts)
+            .await?;
+        config.audit(None, Some(&config), user, store).await?;
+        Ok(config)
+    }
+
+    /// record the change of the configuration
+    async fn audit(
+        &self,
+        before: Option<&Configuration>,
+        after: Option<&Configuration>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        AuditEntry::record(
+            user,
+            "configuration",
+            self.id,
+            Some(self.gear),
+            before,
+            after,
+            store,
+        )
+        .await
+    }
+
+    /// all configurations of the user
+    pub async fn for_user(
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<Configuration>> {
+        store.configurations_for_user(user.user_id()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_parts() {
+        let t = |what: i32, hook: i32| (PartTypeId::from(what), PartTypeId::from(hook));
+        // tire on the rear wheel, the rear wheel on the bike and a chain
+        let parts = vec![t(3, 5), t(5, 1), t(4, 1), t(9, 3)];
+        assert_eq!(attach_order(&parts), vec![1, 2, 0, 3]);
+        // a loop must not hang
+        assert_eq!(attach_order(&[t(3, 5), t(5, 3)]).len(), 2);
+    }
+
+    #[test]
+    fn conflicting_parts() {
+        let p = |id: i32, what: i32, hook: i32| (PartId::from(id), what.into(), hook.into());
+        // front and rear tire
+        assert_eq!(conflict(&[p(1, 3, 4), p(2, 3, 5)]), None);
+        // two tires on the rear wheel
+        assert_eq!(conflict(&[p(1, 3, 5), p(2, 9, 1), p(3, 3, 5)]), Some(2));
+        // the same tire front and rear
+        assert_eq!(conflict(&[p(1, 3, 4), p(1, 3, 5)]), Some(1));
+    }
+}
@@ -0,0 +1,385 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Export and import of all data of a user
+//!
+//! An `Export` can be imported into a different account or a different instance.
+//! All ids are remapped, so existing data is never touched. Usages are not imported
+//! but recalculated from the activities.
+//!
+//! The private part types of the user are recreated, the global catalog is expected
+//! to be the same on both sides. Everything else finds the types in the cached catalog,
+//! so they are committed on their own first and removed again if the rest fails. Stock, usage rules, gear rules, configurations and
+//! notification preferences are imported as well.
+//!
+//! Shops are part of the export for reference only. They are not imported
+//! and imported parts are not registered to any shop.
+
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::*;
+
+/// All data of a user
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Export {
+    pub user: User,
+    pub parts: Vec<Part>,
+    pub attachments: Vec<AttachmentDetail>,
+    pub services: Vec<Service>,
+    pub plans: Vec<ServicePlan>,
+    pub usages: Vec<Usage>,
+    pub activities: Vec<Activity>,
+    pub shops: Vec<Shop>,
+    #[serde(default)]
+    pub types: Vec<PartType>,
+    #[serde(default)]
+    pub stock: Vec<Stock>,
+    #[serde(default)]
+    pub usage_rules: Vec<UsageRule>,
+    #[serde(default)]
+    pub gear_rules: Vec<GearRule>,
+    #[serde(default)]
+    pub configurations: Vec<Configuration>,
+    pub notify: Option<NotifyPrefs>,
+}
+
+/// find the new id for an id of the export
+fn remap<K, V>(map: &HashMap<K, V>, id: K, kind: &str) -> TbResult<V>
+where
+    K: std::hash::Hash + Eq + std::fmt::Display,
+    V: Copy,
+{
+    map.get(&id)
+        .copied()
+        .ok_or_else(|| Error::BadRequest(format!("export references unknown {kind} {id}")))
+}
+
+impl Export {
+    /// export all data of the user
+    pub async fn new(user: &dyn Session, store: &mut impl Store) -> TbResult<Self> {
+        let user_id = user.user_id();
+        let Summary {
+            activities,
+            parts,
+            attachments,
+            usages,
+            services,
+            plans,
+            shops,
+            ..
+        } = user_id.get_summary(None, store).await?;
+        let types = PartType::for_user(user_id)
+            .into_iter()
+            .filter(|t| t.owner.is_some())
+            .collect();
+        Ok(Export {
+            user: user_id.read(store).await?,
+            parts,
+            attachments,
+            services,
+            plans,
+            usages,
+            activities,
+            shops,
+            types,
+            stock: Stock::for_user(user, store).await?,
+            usage_rules: UsageRule::for_user(user, store).await?,
+            gear_rules: GearRule::for_user(user, store).await?,
+            configurations: Configuration::for_user(user, store).await?,
+            notify: store.notify_prefs_get(user_id).await?,
+        })
+    }
+
+    /// recreate the private part types of the export
+    ///
+    /// Everything else refers to the types through the catalog, so they have to be
+    /// committed and the catalog reloaded before the rest is imported with `import`.
+    /// Private types can hook into each other, so a type is only created after its hooks.
+    ///
+    /// # Returns
+    ///
+    /// The ids in the export and the new ids of the types, in the order they were created
+    pub async fn import_types(
+        &mut self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<(PartTypeId, PartTypeId)>> {
+        let types = std::mem::take(&mut self.types);
+        let private: Vec<_> = types.iter().map(|t| t.id).collect();
+        let mut map = HashMap::new();
+        let mut created = Vec::new();
+        let mut res = Vec::new();
+        let mut pending = types;
+        while !pending.is_empty() {
+            let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|t| {
+                t.hooks
+                    .iter()
+                    .all(|h| !private.contains(h) || map.contains_key(h))
+            });
+            if ready.is_empty() {
+                return Err(Error::BadRequest(
+                    "export contains part types hooking into each other".into(),
+                ));
+            }
+            for t in ready {
+                let id = t.id;
+                let hooks = t.hooks.iter().map(|h| *map.get(h).unwrap_or(h)).collect();
+                let new = PartType { hooks, ..t }
+                    .create_after(&created, Some(user), store)
+                    .await?;
+                map.insert(id, new.id);
+                res.push((id, new.id));
+                created.push(new);
+            }
+            pending = rest;
+        }
+        Ok(res)
+    }
+
+    /// remove the types of `import_types` if the rest of the import failed
+    ///
+    /// The catalog has to be reloaded after the transaction is committed.
+    pub async fn discard_types(
+        types: &[(PartTypeId, PartTypeId)],
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        // a type is created after its hooks, so it has to go first
+        for (_, new) in types.iter().rev() {
+            new.discard(user, store).await?;
+        }
+        Ok(())
+    }
+
+    /// import the data into the account of the user
+    ///
+    /// Part types, parts, activities, attachments, service plans, services and the
+    /// rules and configurations get new ids.
+    /// Activities keep their id if it is not used yet, so they still match their source.
+    ///
+    /// The private types have to be imported before with `import_types`, `types` are
+    /// the ids it returned.
+    ///
+    /// # Returns
+    ///
+    /// The summary of the user after the import
+    pub async fn import(
+        self,
+        types: &[(PartTypeId, PartTypeId)],
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        let uid = user.user_id();
+        info!(
+            "importing {} parts and {} activities of user {} for user {uid}",
+            self.parts.len(),
+            self.activities.len(),
+            self.user.id
+        );
+
+        let what = |t: PartTypeId| {
+            types
+                .iter()
+                .find(|(old, _)| *old == t)
+                .map_or(t, |(_, new)| *new)
+        };
+
+        // the rules need to be in place before the activities are registered
+        for rule in self.usage_rules {
+            UsageRule::create(rule.what, what(rule.hook), rule.factor, user, store).await?;
+        }
+
+        let mut parts = HashMap::new();
+        for part in self.parts {
+            // the source must stay unique
+            let source = match part.source {
+                Some(source) if store.partid_get_by_source(&source).await?.is_none() => {
+                    Some(source)
+                }
+                _ => None,
+            };
+            let new = Part::create(
+                part.name,
+                part.vendor,
+                part.model,
+                what(part.what),
+                source,
+                part.purchase,
+                part.notes,
+                part.price,
+                user,
+                store,
+            )
+            .await?;
+            let new = store
+                .part_update(Part {
+                    last_used: part.last_used,
+                    disposed_at: part.disposed_at,
+                    ..new
+                })
+                .await?;
+            parts.insert(part.id, new.id);
+        }
+
+        for act in self.activities {
+            let id = match store.activity_read_by_id(act.id).await? {
+                Some(_) => store.activity_new_id().await?,
+                None => act.id,
+            };
+            let gear = act.gear.map(|g| remap(&parts, g, "part")).transpose()?;
+            let gears = act
+                .gears
+                .iter()
+                .map(|g| remap(&parts, *g, "part"))
+                .collect::<TbResult<_>>()?;
+            Activity {
+                id,
+                user_id: uid,
+                gear,
+                gears,
+                ..act
+            }
+            .upsert(user, store)
+            .await?;
+        }
+
+        for att in self.attachments {
+            let att = att.a;
+            let (Some(part_id), Some(gear)) = (parts.get(&att.part_id), parts.get(&att.gear))
+            else {
+                warn!(
+                    "skipping attachment of {} to unknown gear {}",
+                    att.part_id, att.gear
+                );
+                continue;
+            };
+            Attachment {
+                part_id: *part_id,
+                gear: *gear,
+                hook: what(att.hook),
+                ..att
+            }
+            .create(user, store)
+            .await?;
+        }
+
+        let mut plans = HashMap::new();
+        for plan in self.plans {
+            let part = plan.part.map(|p| remap(&parts, p, "part")).transpose()?;
+            let id = plan.id;
+            let new = ServicePlan {
+                part,
+                what: what(plan.what),
+                hook: plan.hook.map(what),
+                ..plan
+            }
+            .create(user, store)
+            .await?;
+            plans.insert(id, new.id);
+        }
+
+        let mut services = HashMap::new();
+        let mut created = Vec::new();
+        for service in self.services {
+            let plans = service
+                .plans
+                .iter()
+                .map(|p| remap(&plans, *p, "service plan"))
+                .collect::<TbResult<_>>()?;
+            let new = Service::create(
+                remap(&parts, service.part_id, "part")?,
+                service.time,
+                service.name.clone(),
+                service.notes.clone(),
+                None,
+                plans,
+                service.cost.clone(),
+                user,
+                store,
+            )
+            .await?
+            .services
+            .remove(0);
+            services.insert(service.id, new.id);
+            created.push((service, new));
+        }
+        // the successors are only known after all services were created
+        for (service, new) in created {
+            let successor = service
+                .successor
+                .map(|s| remap(&services, s, "service"))
+                .transpose()?;
+            Service {
+                redone: service.redone,
+                successor,
+                ..new
+            }
+            .update(user, store)
+            .await?;
+        }
+
+        for stock in self.stock {
+            Stock::create(
+                what(stock.what),
+                stock.hook.map(what),
+                stock.name,
+                stock.vendor,
+                stock.model,
+                stock.quantity,
+                stock.min_quantity,
+                stock.price,
+                stock.notes,
+                user,
+                store,
+            )
+            .await?;
+        }
+
+        let mut gear_rules = self.gear_rules;
+        gear_rules.sort_by_key(|r| r.position);
+        for rule in gear_rules {
+            let gear = remap(&parts, rule.gear, "part")?;
+            GearRule::create(gear, rule.conditions, user, store).await?;
+        }
+
+        for config in self.configurations {
+            let config_parts = config
+                .parts
+                .into_iter()
+                .map(|p| {
+                    Ok(ConfigPart {
+                        part: remap(&parts, p.part, "part")?,
+                        hook: what(p.hook),
+                    })
+                })
+                .collect::<TbResult<_>>()?;
+            let gear = remap(&parts, config.gear, "part")?;
+            Configuration::create(gear, config.name, config_parts, user, store).await?;
+        }
+
+        if let Some(notify) = self.notify {
+            notify.set(user, store).await?;
+        }
+
+        uid.get_summary(None, store).await
+    }
+}
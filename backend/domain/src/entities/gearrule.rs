@@ -0,0 +1,421 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Rules for the automatic assignment of gears
+//!
+//! A `GearRule` assigns its gear to an activity if all of its conditions match.
+//! Conditions which are not set match every activity. The rules of a user are evaluated
+//! by their position and the first matching rule wins. Rules whose gear cannot be used
+//! for the type of the activity are skipped.
+//!
+//! The rules are only applied to new activities without a gear, be they synchronized
+//! from Strava or uploaded. Existing activities keep their gear, so manual changes stick.
+
+use derive_more::{Display, From, Into};
+use serde_derive::{Deserialize, Serialize};
+use time::Time;
+
+use crate::*;
+
+time::serde::format_description!(hour_minute, Time, "[hour]:[minute]");
+
+/// The conditions of a gear rule
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GearMatch {
+    /// The activity type
+    #[serde(default)]
+    pub what: Option<ActTypeId>,
+    /// The name of the recording device, ignoring case
+    #[serde(default)]
+    pub device_name: Option<String>,
+    /// The name of the activity contains the keyword, ignoring case
+    #[serde(default)]
+    pub keyword: Option<String>,
+    /// The activity starts on one of these days, 1 is monday
+    #[serde(default)]
+    pub weekdays: Vec<i32>,
+    /// The activity starts at or after this local time
+    #[serde(default, with = "hour_minute::option")]
+    pub from: Option<Time>,
+    /// The activity starts before this local time
+    ///
+    /// if it is earlier than from the time window spans midnight
+    #[serde(default, with = "hour_minute::option")]
+    pub until: Option<Time>,
+    /// The minimal distance
+    #[serde(default)]
+    pub min_distance: Option<i32>,
+    /// The maximal distance
+    #[serde(default)]
+    pub max_distance: Option<i32>,
+}
+
+/// A rule for the automatic assignment of a gear
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GearRule {
+    /// The primary key
+    pub id: GearRuleId,
+    /// The owner
+    pub owner: UserId,
+    /// The rules are evaluated by ascending position
+    pub position: i32,
+    /// The gear to assign
+    pub gear: PartId,
+    #[serde(flatten)]
+    pub conditions: GearMatch,
+}
+
+#[derive(Clone, Copy, Debug, Display, From, Into, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GearRuleId(i32);
+
+/// An activity which would get a different gear by the rules
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GearReassignment {
+    pub activity: ActivityId,
+    pub name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: time::OffsetDateTime,
+    /// the current gear
+    pub gear: Option<PartId>,
+    /// the gear assigned by the rule
+    pub new_gear: PartId,
+    pub rule: GearRuleId,
+}
+
+impl GearMatch {
+    /// check and normalize the conditions
+    fn check(self) -> TbResult<Self> {
+        if let Some(what) = self.what {
+            what.get()?;
+        }
+        if self.weekdays.iter().any(|d| !(1..=7).contains(d)) {
+            return Err(Error::BadRequest("weekdays are numbered 1 to 7".into()));
+        }
+        if let (Some(min), Some(max)) = (self.min_distance, self.max_distance)
+            && min > max
+        {
+            return Err(Error::BadRequest(format!(
+                "minimal distance {min} is bigger than maximal distance {max}"
+            )));
+        }
+        let text = |s: Option<String>| s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let mut weekdays = self.weekdays;
+        weekdays.sort_unstable();
+        weekdays.dedup();
+        Ok(GearMatch {
+            device_name: text(self.device_name),
+            keyword: text(self.keyword),
+            weekdays,
+            ..self
+        })
+    }
+
+    /// do all conditions match the activity
+    fn matches(&self, act: &Activity) -> bool {
+        let time = act.start.time();
+        let distance = act.distance.unwrap_or(0);
+
+        self.what.is_none_or(|what| what == act.what)
+            && self.device_name.as_ref().is_none_or(|device| {
+                act.device_name
+                    .as_ref()
+                    .is_some_and(|d| d.eq_ignore_ascii_case(device))
+            })
+            && self
+                .keyword
+                .as_ref()
+                .is_none_or(|keyword| act.name.to_lowercase().contains(&keyword.to_lowercase()))
+            && (self.weekdays.is_empty()
+                || self
+                    .weekdays
+                    .contains(&(act.start.weekday().number_from_monday() as i32)))
+            && match (self.from, self.until) {
+                (Some(from), Some(until)) if until < from => time >= from || time < until,
+                (from, until) => {
+                    from.is_none_or(|from| time >= from) && until.is_none_or(|until| time < until)
+                }
+            }
+            && self.min_distance.is_none_or(|min| distance >= min)
+            && self.max_distance.is_none_or(|max| distance <= max)
+    }
+}
+
+/// the first rule which matches the activity and whose gear fits the activity type
+fn first_match<'a>(rules: &'a [GearRule], gears: &[Part], act: &Activity) -> Option<&'a GearRule> {
+    rules.iter().find(|rule| {
+        rule.conditions.matches(act)
+            && gears
+                .iter()
+                .find(|g| g.id == rule.gear)
+                .is_some_and(|g| g.disposed_at.is_none() && g.what.act_types().contains(&act.what))
+    })
+}
+
+/// the rules of the user and their gears
+async fn rules_and_gears(
+    user: UserId,
+    store: &mut impl Store,
+) -> TbResult<(Vec<GearRule>, Vec<Part>)> {
+    let rules = store.gear_rules_for_user(user).await?;
+    let mut gears = Vec::new();
+    for rule in &rules {
+        gears.push(rule.gear.read(store).await?);
+    }
+    Ok((rules, gears))
+}
+
+/// check that the gear is a main gear of the user
+async fn check_gear(gear: PartId, user: &dyn Session, store: &mut impl Store) -> TbResult<()> {
+    if !gear.part(user, store).await?.what.is_main()? {
+        return Err(Error::BadRequest(format!("part {gear} is not a gear")));
+    }
+    Ok(())
+}
+
+impl GearRuleId {
+    /// get the rule, checks that the user owns it
+    pub async fn rule(self, user: &dyn Session, store: &mut impl Store) -> TbResult<GearRule> {
+        let rule = store.gear_rule_get(self).await?;
+        user.check_owner(
+            rule.owner,
+            format!("user {} cannot access gear rule {self}", user.user_id()),
+        )?;
+        Ok(rule)
+    }
+
+    pub async fn update(
+        self,
+        position: i32,
+        gear: PartId,
+        conditions: GearMatch,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<GearRule> {
+        let rule = self.rule(user, store).await?;
+        check_gear(gear, user, store).await?;
+        let conditions = conditions.check()?;
+        let new = store
+            .gear_rule_update(GearRule {
+                position,
+                gear,
+                conditions,
+                ..rule.clone()
+            })
+            .await?;
+        new.audit(Some(&rule), Some(&new), user, store).await?;
+        Ok(new)
+    }
+
+    pub async fn delete(self, user: &dyn Session, store: &mut impl Store) -> TbResult<GearRuleId> {
+        let rule = self.rule(user, store).await?;
+        store.gear_rule_delete(self).await?;
+        rule.audit(Some(&rule), None, user, store).await?;
+        Ok(self)
+    }
+}
+
+impl GearRule {
+    /// create a new rule after all existing rules
+    pub async fn create(
+        gear: PartId,
+        conditions: GearMatch,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<GearRule> {
+        check_gear(gear, user, store).await?;
+        let conditions = conditions.check()?;
+        let owner = user.user_id();
+        let position = store
+            .gear_rules_for_user(owner)
+            .await?
+            .iter()
+            .map(|r| r.position + 1)
+            .max()
+            .unwrap_or(0);
+        let rule = store
+            .gear_rule_create(owner, position, gear, conditions)
+            .await?;
+        rule.audit(None, Some(&rule), user, store).await?;
+        Ok(rule)
+    }
+
+    /// record the change of the rule
+    async fn audit(
+        &self,
+        before: Option<&GearRule>,
+        after: Option<&GearRule>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        AuditEntry::record(
+            user,
+            "gear_rule",
+            self.id,
+            Some(self.gear),
+            before,
+            after,
+            store,
+        )
+        .await
+    }
+
+    /// all rules of the user in the order of evaluation
+    pub async fn for_user(user: &dyn Session, store: &mut impl Store) -> TbResult<Vec<GearRule>> {
+        store.gear_rules_for_user(user.user_id()).await
+    }
+
+    /// Set the gear of the activity according to the rules of its user
+    ///
+    /// The activity is left alone if no rule matches
+    pub async fn assign(act: &mut Activity, store: &mut impl Store) -> TbResult<()> {
+        let (rules, gears) = rules_and_gears(act.user_id, store).await?;
+        if let Some(rule) = first_match(&rules, &gears, act) {
+            debug!("rule {} assigns gear {} to {}", rule.id, rule.gear, act.id);
+            act.gear = Some(rule.gear);
+        }
+        Ok(())
+    }
+
+    /// All existing activities of the user which would get a different gear by the rules
+    ///
+    /// Nothing is changed
+    pub async fn dry_run(
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<GearReassignment>> {
+        let (rules, gears) = rules_and_gears(user.user_id(), store).await?;
+        if rules.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut res: Vec<_> = store
+            .get_all(&user.user_id())
+            .await?
+            .into_iter()
+            .filter_map(|act| {
+                let rule = first_match(&rules, &gears, &act)?;
+                (act.gear != Some(rule.gear)).then_some(GearReassignment {
+                    activity: act.id,
+                    name: act.name,
+                    start: act.start,
+                    gear: act.gear,
+                    new_gear: rule.gear,
+                    rule: rule.id,
+                })
+            })
+            .collect();
+        res.sort_by_key(|r| r.start);
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, time};
+
+    use super::*;
+
+    fn activity() -> Activity {
+        Activity {
+            what: 5.into(),
+            name: "Zwift - Watopia".into(),
+            duration: 3600,
+            time: Some(3600),
+            distance: Some(30_000),
+            device_name: Some("Zwift".into()),
+            // a tuesday evening
+            ..Activity::test_ride(datetime!(2024-01-09 19:30 +1), None)
+        }
+    }
+
+    #[test]
+    fn match_conditions() {
+        let act = activity();
+        assert!(GearMatch::default().matches(&act));
+
+        let keyword = GearMatch {
+            keyword: Some("zwift".into()),
+            device_name: Some("ZWIFT".into()),
+            ..Default::default()
+        };
+        assert!(keyword.matches(&act));
+        assert!(
+            !GearMatch {
+                what: Some(1.into()),
+                ..keyword
+            }
+            .matches(&act)
+        );
+
+        let days = |weekdays| GearMatch {
+            weekdays,
+            ..Default::default()
+        };
+        assert!(days(vec![2, 4]).matches(&act));
+        assert!(!days(vec![1, 3]).matches(&act));
+
+        let window = |from, until| GearMatch {
+            from,
+            until,
+            ..Default::default()
+        };
+        assert!(window(Some(time!(18:00)), None).matches(&act));
+        assert!(!window(Some(time!(6:00)), Some(time!(9:00))).matches(&act));
+        assert!(window(Some(time!(19:00)), Some(time!(1:00))).matches(&act));
+        assert!(!window(Some(time!(20:00)), Some(time!(1:00))).matches(&act));
+
+        let distance = |min_distance, max_distance| GearMatch {
+            min_distance,
+            max_distance,
+            ..Default::default()
+        };
+        assert!(distance(Some(30_000), None).matches(&act));
+        assert!(!distance(None, Some(20_000)).matches(&act));
+    }
+
+    #[test]
+    fn check_conditions() {
+        let conditions = GearMatch {
+            keyword: Some("  ".into()),
+            weekdays: vec![5, 1, 5],
+            ..Default::default()
+        }
+        .check()
+        .unwrap();
+        assert_eq!(conditions.keyword, None);
+        assert_eq!(conditions.weekdays, vec![1, 5]);
+        assert!(
+            GearMatch {
+                weekdays: vec![0],
+                ..Default::default()
+            }
+            .check()
+            .is_err()
+        );
+        assert!(
+            GearMatch {
+                min_distance: Some(2),
+                max_distance: Some(1),
+                ..Default::default()
+            }
+            .check()
+            .is_err()
+        );
+    }
+}
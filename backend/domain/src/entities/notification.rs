@@ -0,0 +1,419 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Notifications about due service plans
+//!
+//! Users can choose to get notified by email and/or a webhook when a service plan
+//! is due soon or overdue. The `NotifyPrefs` hold these preferences per user.
+//!
+//! `NotifyPrefs::check` evaluates all service plans of the user and hands a `Notice`
+//! to the `Notifier` whenever a plan crosses a threshold. The last state is remembered per
+//! plan and part, so every crossing is only reported once. After a service the state returns
+//! to `DueState::Ok` and the next crossing gets reported again.
+
+use serde_derive::{Deserialize, Serialize};
+use std::net::IpAddr;
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// How a user wants to be notified about due service plans
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NotifyPrefs {
+    #[serde(default)]
+    pub user: UserId,
+    /// send notices to this email address
+    pub email: Option<String>,
+    /// post notices as json to this url
+    pub webhook: Option<String>,
+    /// notify when a plan is due soon
+    pub soon: bool,
+    /// notify when a plan is overdue
+    pub overdue: bool,
+}
+
+/// A service plan crossed a threshold
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Notice {
+    pub user: UserId,
+    pub plan: ServicePlanId,
+    pub plan_name: String,
+    pub part: PartId,
+    pub part_name: String,
+    pub state: DueState,
+}
+
+/// Can a webhook be posted to this address
+///
+/// Only public addresses are allowed, the server must not be used to reach
+/// loopback, private or link-local services.
+pub fn webhook_address_allowed(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // shared address space 100.64.0.0/10
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => webhook_address_allowed(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// the host part of an http(s) url
+fn webhook_host(url: &str) -> Option<&str> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host_port = authority.rsplit('@').next()?;
+    let host = match host_port.strip_prefix('[') {
+        Some(v6) => v6.split(']').next()?,
+        None => host_port.split(':').next()?,
+    };
+    (!host.is_empty()).then_some(host)
+}
+
+/// check that the webhook is an http(s) url which does not point to the internal network
+///
+/// Host names are resolved when the notice is posted, the addresses get checked there again.
+fn check_webhook(url: &str) -> TbResult<()> {
+    let invalid = || Error::BadRequest(format!("invalid webhook url {url}"));
+    let host = webhook_host(url).ok_or_else(invalid)?.to_ascii_lowercase();
+    if host == "localhost" || host.ends_with(".localhost") {
+        return Err(invalid());
+    }
+    if let Ok(ip) = host.parse::<IpAddr>()
+        && !webhook_address_allowed(ip)
+    {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+impl NotifyPrefs {
+    fn new(user: UserId) -> Self {
+        Self {
+            user,
+            email: None,
+            webhook: None,
+            soon: true,
+            overdue: true,
+        }
+    }
+
+    /// get the preferences of the user
+    ///
+    /// Returns the defaults if the user never stored any
+    pub async fn get(user: &dyn Session, store: &mut impl Store) -> TbResult<Self> {
+        let user = user.user_id();
+        Ok(store
+            .notify_prefs_get(user)
+            .await?
+            .unwrap_or_else(|| Self::new(user)))
+    }
+
+    /// store the preferences for the user
+    pub async fn set(mut self, user: &dyn Session, store: &mut impl Store) -> TbResult<Self> {
+        self.user = user.user_id();
+        self.email = self.email.filter(|e| !e.trim().is_empty());
+        self.webhook = self.webhook.filter(|w| !w.trim().is_empty());
+        if let Some(email) = &self.email
+            && !email.contains('@')
+        {
+            return Err(Error::BadRequest(format!("invalid email address {email}")));
+        }
+        if let Some(webhook) = &self.webhook {
+            check_webhook(webhook)?;
+        }
+        let before = store.notify_prefs_get(self.user).await?;
+        let prefs = store.notify_prefs_set(self).await?;
+        AuditEntry::record(
+            user,
+            "notify_prefs",
+            prefs.user,
+            None,
+            before.as_ref(),
+            Some(&prefs),
+            store,
+        )
+        .await?;
+        Ok(prefs)
+    }
+
+    /// all users who want to get notified
+    pub async fn all_active(store: &mut impl Store) -> TbResult<Vec<Self>> {
+        Ok(store
+            .notify_prefs_all()
+            .await?
+            .into_iter()
+            .filter(|p| (p.soon || p.overdue) && (p.email.is_some() || p.webhook.is_some()))
+            .collect())
+    }
+
+    fn wants(&self, state: DueState) -> bool {
+        match state {
+            DueState::Ok => false,
+            DueState::Soon => self.soon,
+            DueState::Overdue => self.overdue,
+        }
+    }
+
+    /// Check all service plans of the user and send notices for new threshold crossings
+    ///
+    /// If the notice cannot be delivered the state is not recorded,
+    /// so the delivery is retried with the next check.
+    ///
+    /// # Returns
+    ///
+    /// The number of notices sent
+    pub async fn check(&self, notifier: &impl Notifier, store: &mut impl Store) -> TbResult<usize> {
+        let now = OffsetDateTime::now_utc();
+        let mut plans = ServicePlan::for_user(&self.user, store).await?;
+        for part in Part::get_all(&self.user, store).await? {
+            plans.append(&mut ServicePlan::for_part(part.id, store).await?);
+        }
+
+        let mut notices = Vec::new();
+        for plan in plans {
+            for part in plan.parts(store).await? {
+                let (start, used) = plan.usage_since_service(&part, store).await?;
+                notices.push(Notice {
+                    user: self.user,
+                    plan: plan.id,
+                    plan_name: plan.name.clone(),
+                    part: part.id,
+                    part_name: part.name.clone(),
+                    state: PlanStatus::new(&plan, part.id, start, &used, now).state,
+                });
+            }
+        }
+        self.deliver(notices, notifier, store).await
+    }
+
+    /// send the notices whose state was not reported yet
+    ///
+    /// `notices` holds the current state of every plan and part, including `DueState::Ok`
+    async fn deliver(
+        &self,
+        notices: Vec<Notice>,
+        notifier: &impl Notifier,
+        store: &mut impl NotificationStore,
+    ) -> TbResult<usize> {
+        let mut sent = 0;
+        for notice in notices {
+            let (plan, part, state) = (notice.plan, notice.part, notice.state);
+            let last = store.notice_get(plan, part).await?;
+            if last.unwrap_or_default() == state {
+                continue;
+            }
+            if state == DueState::Ok {
+                store.notice_delete(plan, part).await?;
+                continue;
+            }
+            if state > last.unwrap_or_default() && self.wants(state) {
+                if let Err(e) = notifier.notify(self, &notice).await {
+                    warn!("could not deliver notice {notice:?}: {e}");
+                    continue;
+                }
+                sent += 1;
+            }
+            store.notice_set(plan, part, state).await?;
+        }
+        Ok(sent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemStore(HashMap<(ServicePlanId, PartId), DueState>);
+
+    #[async_trait::async_trait]
+    impl NotificationStore for MemStore {
+        async fn notify_prefs_get(&mut self, _: UserId) -> TbResult<Option<NotifyPrefs>> {
+            todo!()
+        }
+
+        async fn notify_prefs_set(&mut self, _: NotifyPrefs) -> TbResult<NotifyPrefs> {
+            todo!()
+        }
+
+        async fn notify_prefs_all(&mut self) -> TbResult<Vec<NotifyPrefs>> {
+            todo!()
+        }
+
+        async fn notice_get(
+            &mut self,
+            plan: ServicePlanId,
+            part: PartId,
+        ) -> TbResult<Option<DueState>> {
+            Ok(self.0.get(&(plan, part)).copied())
+        }
+
+        async fn notice_set(
+            &mut self,
+            plan: ServicePlanId,
+            part: PartId,
+            state: DueState,
+        ) -> TbResult<()> {
+            self.0.insert((plan, part), state);
+            Ok(())
+        }
+
+        async fn notice_delete(&mut self, plan: ServicePlanId, part: PartId) -> TbResult<()> {
+            self.0.remove(&(plan, part));
+            Ok(())
+        }
+    }
+
+    /// records the delivered states, fails while `down` is set
+    #[derive(Default)]
+    struct MockNotifier {
+        sent: Mutex<Vec<DueState>>,
+        down: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Notifier for MockNotifier {
+        async fn notify(&self, _: &NotifyPrefs, notice: &Notice) -> TbResult<()> {
+            if self.down {
+                return Err(Error::BadRequest("down".into()));
+            }
+            self.sent.lock().unwrap().push(notice.state);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn notify_once_per_crossing() -> TbResult<()> {
+        let prefs = NotifyPrefs::new(1.into());
+        let notifier = MockNotifier::default();
+        let store = &mut MemStore::default();
+        let plan = ServicePlanId::new();
+        let check = |state| {
+            vec![Notice {
+                user: 1.into(),
+                plan,
+                plan_name: "chain".into(),
+                part: 2.into(),
+                part_name: "KMC".into(),
+                state,
+            }]
+        };
+
+        // sent once
+        assert_eq!(
+            prefs
+                .deliver(check(DueState::Soon), &notifier, store)
+                .await?,
+            1
+        );
+        assert_eq!(
+            prefs
+                .deliver(check(DueState::Soon), &notifier, store)
+                .await?,
+            0
+        );
+        // overdue after due-soon
+        assert_eq!(
+            prefs
+                .deliver(check(DueState::Overdue), &notifier, store)
+                .await?,
+            1
+        );
+        assert_eq!(
+            prefs
+                .deliver(check(DueState::Soon), &notifier, store)
+                .await?,
+            0
+        );
+        // re-sent after reset
+        assert_eq!(
+            prefs.deliver(check(DueState::Ok), &notifier, store).await?,
+            0
+        );
+        assert_eq!(
+            prefs
+                .deliver(check(DueState::Soon), &notifier, store)
+                .await?,
+            1
+        );
+        assert_eq!(
+            *notifier.sent.lock().unwrap(),
+            vec![DueState::Soon, DueState::Overdue, DueState::Soon]
+        );
+
+        // failed deliveries are retried
+        let down = MockNotifier {
+            down: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            prefs
+                .deliver(check(DueState::Overdue), &down, store)
+                .await?,
+            0
+        );
+        assert_eq!(
+            prefs
+                .deliver(check(DueState::Overdue), &notifier, store)
+                .await?,
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn webhooks() {
+        assert!(check_webhook("https://hooks.example.com/tendabike").is_ok());
+        assert!(check_webhook("http://user@example.com:8080?x=1").is_ok());
+        assert!(check_webhook("ftp://example.com").is_err());
+        assert!(check_webhook("https://").is_err());
+        assert!(check_webhook("http://localhost:8000/").is_err());
+        assert!(check_webhook("http://127.0.0.1/").is_err());
+        assert!(check_webhook("http://169.254.169.254/latest/meta-data").is_err());
+        assert!(check_webhook("https://10.1.2.3/").is_err());
+        assert!(check_webhook("https://192.168.1.1:443/").is_err());
+        assert!(check_webhook("https://[::1]/").is_err());
+        assert!(check_webhook("https://[::ffff:127.0.0.1]/").is_err());
+        assert!(check_webhook("https://[fe80::1]/").is_err());
+        assert!(check_webhook("https://93.184.216.34/").is_ok());
+    }
+}
@@ -42,6 +42,14 @@ use time::format_description::well_known::Rfc3339;
 
 use crate::*;
 
+mod cost;
+pub use cost::*;
+mod duplicate;
+mod history;
+pub use history::*;
+mod timeline;
+pub use timeline::*;
+
 /// The database's representation of a part.
 #[serde_as]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -74,6 +82,9 @@ pub struct Part {
     pub notes: String,
     /// Optional shop for delegated maintenance
     pub shop: Option<ShopId>,
+    /// The purchase price
+    #[serde(default)]
+    pub price: Option<Money>,
 }
 
 #[derive(Clone, Copy, Debug, Display, From, Into, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -100,6 +111,8 @@ impl PartId {
         if !plans.is_empty() {
             return Err(Error::Conflict("Part has active serviceplan".into()));
         }
+        let part = self.read(store).await?;
+        AuditEntry::record(user, "part", self, Some(self), Some(&part), None, store).await?;
         store.part_delete(self).await
     }
 
@@ -140,6 +153,29 @@ impl PartId {
         part.what.is_main()
     }
 
+    /// find the usage of all activities the part was used for in the given time frame
+    ///
+    /// for main parts these are the activities of the gear,
+    /// for all other parts the activities of the gears they were attached to.
+    /// The usage is weighted according to the usage rules of the owner.
+    ///
+    /// returns the start and the usage of each activity
+    pub(crate) async fn usages(
+        self,
+        begin: OffsetDateTime,
+        end: OffsetDateTime,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<(OffsetDateTime, Usage)>> {
+        let part = self.read(store).await?;
+        let rules = store.usage_rules_for_user(part.owner).await?;
+        if part.what.is_main()? {
+            let acts = Activity::find(self, begin, end, store).await?;
+            Ok(UsageRule::weigh(&rules, part.what, acts))
+        } else {
+            Attachment::usages_by_part(self, &rules, begin, end, store).await
+        }
+    }
+
     /// check if the given user is the owner or an authorized shop owner.
     /// Returns Forbidden if not.
     pub async fn checkuser(
@@ -172,19 +208,30 @@ impl PartId {
     pub(crate) async fn dispose(
         &self,
         time: OffsetDateTime,
+        user: &dyn Session,
         store: &mut impl Store,
     ) -> Result<Part, Error> {
         debug!("-- disposing part {self} at {time}");
-        let mut part = self.read(store).await?;
-        part.disposed_at = Some(time);
-        store.part_update(part).await
+        let part = self.read(store).await?;
+        let new = Part {
+            disposed_at: Some(time),
+            ..part.clone()
+        };
+        part.audit(new, user, store).await
     }
 
-    pub(crate) async fn restore(&self, store: &mut impl Store) -> TbResult<Part> {
+    pub(crate) async fn restore(
+        &self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Part> {
         debug!("-- restoring part {self}");
-        let mut part = self.read(store).await?;
-        part.disposed_at = None;
-        store.part_update(part).await
+        let part = self.read(store).await?;
+        let new = Part {
+            disposed_at: None,
+            ..part.clone()
+        };
+        part.audit(new, user, store).await
     }
 
     pub async fn change(
@@ -194,36 +241,46 @@ impl PartId {
         model: String,
         purchase: OffsetDateTime,
         notes: String,
+        price: Option<Option<Money>>,
         user: &dyn Session,
         store: &mut impl Store,
     ) -> TbResult<Part> {
         info!("Change {self:?}");
 
-        let mut part = self.part(user, store).await?;
+        let part = self.part(user, store).await?;
 
         let purchase = round_time(purchase);
-        part = Part {
+        let price = match price {
+            Some(price) => price.map(Money::check).transpose()?,
+            None => part.price.clone(),
+        };
+        let new = Part {
             name,
             vendor,
             model,
             purchase,
             notes,
-            ..part
+            price,
+            ..part.clone()
         };
-        store.part_update(part).await
+        part.audit(new, user, store).await
     }
 
     pub(crate) async fn set_owner_and_shop(
         &self,
         gear: PartId,
+        user: &dyn Session,
         store: &mut impl Store,
     ) -> TbResult<Part> {
-        let mut part = self.read(store).await?;
+        let part = self.read(store).await?;
         let gear = gear.read(store).await?;
         if part.owner != gear.owner || part.shop != gear.shop {
-            part.owner = gear.owner;
-            part.shop = gear.shop;
-            return store.part_update(part).await;
+            let new = Part {
+                owner: gear.owner,
+                shop: gear.shop,
+                ..part.clone()
+            };
+            return part.audit(new, user, store).await;
         }
         Ok(part)
     }
@@ -238,6 +295,27 @@ impl Part {
         self.usage
     }
 
+    /// store the changed part `new` and record the change
+    pub(crate) async fn audit(
+        self,
+        new: Part,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Part> {
+        let new = store.part_update(new).await?;
+        AuditEntry::record(
+            user,
+            "part",
+            self.id,
+            Some(self.id),
+            Some(&self),
+            Some(&new),
+            store,
+        )
+        .await?;
+        Ok(new)
+    }
+
     pub async fn create(
         name: String,
         vendor: String,
@@ -246,13 +324,16 @@ impl Part {
         source: Option<String>,
         purchase: OffsetDateTime,
         notes: String,
+        price: Option<Money>,
         user: &dyn Session,
-        store: &mut impl PartStore,
+        store: &mut impl Store,
     ) -> TbResult<Part> {
         debug!("Create {name} {vendor} {model}");
+        what.visible(user.user_id())?;
+        let price = price.map(Money::check).transpose()?;
 
         let purchase = round_time(purchase);
-        store
+        let part = store
             .part_create(
                 what,
                 name,
@@ -261,11 +342,23 @@ impl Part {
                 purchase,
                 source,
                 notes,
+                price,
                 UsageId::new(),
                 user.user_id(),
                 user.shop(),
             )
-            .await
+            .await?;
+        AuditEntry::record(
+            user,
+            "part",
+            part.id,
+            Some(part.id),
+            None,
+            Some(&part),
+            store,
+        )
+        .await?;
+        Ok(part)
     }
 
     pub async fn categories(
@@ -0,0 +1,236 @@
+//! Cost tracking for parts
+//!
+//! Parts can have a purchase price and services can have a cost. The cost report sums these up
+//! per part and relates them to the usage, so users can see what a part costs per kilometer or hour.
+//!
+//! For gears the costs of the attached parts are added. A part which was attached to several gears
+//! is split by the distance it was used on each of them.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde_derive::{Deserialize, Serialize};
+use serde_with::serde_as;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+use crate::*;
+
+/// An amount of money
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Money {
+    /// The amount in the smallest unit of the currency, e.g. cents
+    pub amount: i32,
+    /// The ISO 4217 currency code, e.g. EUR
+    pub currency: String,
+}
+
+impl Money {
+    pub fn new(amount: i32, currency: &str) -> Self {
+        Self {
+            amount,
+            currency: currency.to_uppercase(),
+        }
+    }
+
+    /// check the amount and normalize the currency code
+    pub(crate) fn check(self) -> TbResult<Money> {
+        if self.amount < 0 {
+            return Err(Error::BadRequest(format!(
+                "amount {} must not be negative",
+                self.amount
+            )));
+        }
+        if self.currency.len() != 3 || !self.currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(Error::BadRequest(format!(
+                "invalid currency {}",
+                self.currency
+            )));
+        }
+        Ok(Money::new(self.amount, &self.currency))
+    }
+}
+
+/// The costs of a part in one currency
+///
+/// All amounts are in the smallest unit of the currency
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cost {
+    pub currency: String,
+    /// the purchase price
+    pub price: i64,
+    /// the sum of all services
+    pub services: i64,
+    /// the share of the parts which were attached to a gear
+    pub parts: i64,
+    /// price, services and parts
+    pub total: i64,
+    /// the total per kilometer, None if the part was not used
+    pub per_km: Option<f64>,
+    /// the total per hour, None if the part was not used
+    pub per_hour: Option<f64>,
+}
+
+impl Cost {
+    fn own(&self) -> i64 {
+        self.price + self.services
+    }
+}
+
+/// The costs of a single part
+#[serde_as]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartCost {
+    pub part: PartId,
+    pub what: PartTypeId,
+    pub name: String,
+    /// is it a gear
+    pub gear: bool,
+    #[serde_as(as = "Option<Rfc3339>")]
+    pub disposed_at: Option<OffsetDateTime>,
+    /// the distance it was used
+    pub km: f64,
+    /// the time it was used
+    pub hours: f64,
+    /// the costs per currency
+    pub costs: Vec<Cost>,
+}
+
+type Costs = BTreeMap<String, Cost>;
+
+fn entry<'a>(costs: &'a mut Costs, currency: &str) -> &'a mut Cost {
+    costs.entry(currency.to_string()).or_insert_with(|| Cost {
+        currency: currency.to_string(),
+        ..Default::default()
+    })
+}
+
+/// split amount by the given weights
+///
+/// falls back to equal shares if all weights are zero
+fn split(amount: i64, weights: &[i64]) -> Vec<i64> {
+    let sum: i64 = weights.iter().sum();
+    if sum == 0 {
+        let n = weights.len().max(1) as i64;
+        return weights.iter().map(|_| amount / n).collect();
+    }
+    weights
+        .iter()
+        .map(|w| (amount as f64 * *w as f64 / sum as f64).round() as i64)
+        .collect()
+}
+
+impl PartCost {
+    fn new(part: &Part, usage: &Usage, costs: Costs) -> TbResult<Self> {
+        let km = usage.distance as f64 / 1000.0;
+        let hours = usage.time as f64 / 3600.0;
+        let costs = costs
+            .into_values()
+            .map(|mut cost| {
+                cost.total = cost.own() + cost.parts;
+                cost.per_km = (km > 0.0).then(|| cost.total as f64 / km);
+                cost.per_hour = (hours > 0.0).then(|| cost.total as f64 / hours);
+                cost
+            })
+            .collect();
+        Ok(Self {
+            part: part.id,
+            what: part.what,
+            name: part.name.clone(),
+            gear: part.what.is_main()?,
+            disposed_at: part.disposed_at,
+            km,
+            hours,
+            costs,
+        })
+    }
+}
+
+impl Part {
+    /// The costs of all parts of the user, including disposed ones
+    ///
+    /// Gears come first. The costs of parts are added to the gears they were attached to.
+    /// If a part was used on several gears, it is split by the distance on each of them,
+    /// or by the time if there is no distance.
+    pub async fn cost_report(
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<PartCost>> {
+        let parts = Part::get_all(&user.user_id(), store).await?;
+
+        let mut own: HashMap<PartId, Costs> = HashMap::new();
+        for part in &parts {
+            let costs = own.entry(part.id).or_default();
+            if let Some(price) = &part.price {
+                entry(costs, &price.currency).price += price.amount as i64;
+            }
+            for service in store.services_by_part(part.id).await? {
+                if let Some(cost) = &service.cost {
+                    entry(costs, &cost.currency).services += cost.amount as i64;
+                }
+            }
+        }
+
+        let mut shares: HashMap<PartId, Costs> = HashMap::new();
+        for part in &parts {
+            if part.what.is_main()? || own[&part.id].is_empty() {
+                continue;
+            }
+            let mut gears = Vec::new();
+            let mut usages = Vec::new();
+            for att in store.attachments_all_by_part(part.id).await? {
+                if !own.contains_key(&att.gear) {
+                    continue;
+                }
+                gears.push(att.gear);
+                usages.push(att.usage(store).await?);
+            }
+            if gears.is_empty() {
+                continue;
+            }
+            let mut weights: Vec<_> = usages.iter().map(|u| u.distance as i64).collect();
+            if weights.iter().all(|w| *w == 0) {
+                weights = usages.iter().map(|u| u.time as i64).collect();
+            }
+            for cost in own[&part.id].values() {
+                for (gear, share) in gears.iter().zip(split(cost.own(), &weights)) {
+                    entry(shares.entry(*gear).or_default(), &cost.currency).parts += share;
+                }
+            }
+        }
+
+        let mut res = Vec::new();
+        for part in &parts {
+            let mut costs = own.remove(&part.id).unwrap_or_default();
+            for (currency, share) in shares.remove(&part.id).unwrap_or_default() {
+                entry(&mut costs, &currency).parts += share.parts;
+            }
+            let usage = part.usage.read(store).await?;
+            res.push(PartCost::new(part, &usage, costs)?);
+        }
+        res.sort_by_key(|c| (!c.gear, c.part.0));
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_money() {
+        assert_eq!(
+            Money::new(1999, "eur").check().unwrap(),
+            Money::new(1999, "EUR")
+        );
+        assert!(Money::new(-1, "EUR").check().is_err());
+        assert!(Money::new(100, "EURO").check().is_err());
+        assert!(Money::new(100, "E1R").check().is_err());
+    }
+
+    #[test]
+    fn split_costs() {
+        assert_eq!(split(1000, &[3, 1]), vec![750, 250]);
+        assert_eq!(split(1000, &[0, 0]), vec![500, 500]);
+        assert_eq!(split(1000, &[0, 5]), vec![0, 1000]);
+    }
+}
@@ -0,0 +1,86 @@
+//! Duplicating a gear with its assembly
+//!
+//! Setting up identical gears should not mean entering every part again.
+//! The duplicate gets new parts for all parts currently attached to the original,
+//! attached to the same hooks. Service plans for specific parts are copied as well.
+//! Usage, services and the history of the original are not copied.
+
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// create a new part with the data and the service plans of `part`
+async fn copy_part(
+    part: &Part,
+    name: String,
+    purchase: OffsetDateTime,
+    hash: &mut SumHash,
+    user: &dyn Session,
+    store: &mut impl Store,
+) -> TbResult<PartId> {
+    let new = Part::create(
+        name,
+        part.vendor.clone(),
+        part.model.clone(),
+        part.what,
+        None,
+        purchase,
+        part.notes.clone(),
+        part.price.clone(),
+        user,
+        store,
+    )
+    .await?;
+    let mut plans = Vec::new();
+    for plan in ServicePlan::for_part(part.id, store).await? {
+        let plan = ServicePlan {
+            part: Some(new.id),
+            ..plan
+        };
+        plans.push(plan.create(user, store).await?);
+    }
+    let id = new.id;
+    *hash += Summary {
+        parts: vec![new],
+        plans,
+        ..Default::default()
+    };
+    Ok(id)
+}
+
+impl PartId {
+    /// Duplicate the gear and all parts currently attached to it
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the new gear
+    /// * `time` - the purchase time of the new parts, they are attached at that time
+    ///
+    /// # Returns
+    ///
+    /// The summary with the new parts and their attachments
+    pub async fn duplicate(
+        self,
+        name: String,
+        time: OffsetDateTime,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        let gear = self.part(user, store).await?;
+        if !gear.what.is_main()? {
+            return Err(Error::BadRequest(format!("{} is not a gear", gear.name)));
+        }
+        info!("duplicating gear {} as {name}", gear.name);
+
+        let mut hash = SumHash::default();
+        let new_gear = copy_part(&gear, name, time, &mut hash, user, store).await?;
+
+        let attachments = subattachments(self, self, OffsetDateTime::now_utc(), store).await?;
+        for att in attachments {
+            let part = att.part_id.read(store).await?;
+            let new = copy_part(&part, part.name.clone(), time, &mut hash, user, store).await?;
+            hash += attach_assembly(user, new, time, new_gear, att.hook, false, store).await?;
+        }
+        Ok(hash.into())
+    }
+}
@@ -0,0 +1,212 @@
+//! Usage of a part over time
+//!
+//! The `Usage` of a part only holds the current counters. Here the usage is calculated
+//! from the activities the part was used for, either at a point in time or as a series
+//! of weekly or monthly buckets for charting the wear of a part.
+
+use serde_derive::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime, Time};
+
+use crate::*;
+
+/// Do not calculate more buckets than that
+const MAX_BUCKETS: usize = 1000;
+
+/// The size of a bucket in a usage series
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    /// calendar weeks starting on monday
+    Week,
+    /// calendar months
+    #[default]
+    Month,
+}
+
+impl Bucket {
+    /// the start of the bucket which contains time
+    pub(super) fn start(self, time: OffsetDateTime) -> OffsetDateTime {
+        let time = time
+            .to_offset(time::UtcOffset::UTC)
+            .replace_time(Time::MIDNIGHT);
+        match self {
+            Bucket::Week => time - Duration::days(time.weekday().number_days_from_monday() as i64),
+            Bucket::Month => time.replace_day(1).expect("day 1 is always valid"),
+        }
+    }
+
+    /// the start of the bucket following the bucket starting at start
+    pub(super) fn next(self, start: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            Bucket::Week => start + Duration::weeks(1),
+            Bucket::Month => {
+                let year = match start.month() {
+                    time::Month::December => start.year() + 1,
+                    _ => start.year(),
+                };
+                start
+                    .replace_day(1)
+                    .and_then(|s| s.replace_year(year))
+                    .and_then(|s| s.replace_month(start.month().next()))
+                    .expect("the first of a month is always valid")
+            }
+        }
+    }
+}
+
+/// The usage of a part within one bucket
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UsagePoint {
+    #[serde(with = "time::serde::rfc3339")]
+    pub start: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub end: OffsetDateTime,
+    /// the usage within the bucket
+    pub usage: Usage,
+    /// the usage of the part at the end of the bucket
+    pub total: Usage,
+}
+
+/// sum up the usage of activities per bucket
+///
+/// `acts` holds the start and the usage of the activities
+fn series(
+    bucket: Bucket,
+    from: OffsetDateTime,
+    to: OffsetDateTime,
+    mut acts: Vec<(OffsetDateTime, Usage)>,
+) -> TbResult<Vec<UsagePoint>> {
+    acts.sort_by_key(|(start, _)| *start);
+    let mut acts = acts.into_iter().peekable();
+
+    let mut start = bucket.start(from);
+    let mut total = Usage::default();
+    while let Some((_, usage)) = acts.next_if(|(s, _)| *s < start) {
+        total = total + usage;
+    }
+
+    let mut res = Vec::new();
+    while start < to {
+        if res.len() >= MAX_BUCKETS {
+            return Err(Error::BadRequest(format!(
+                "more than {MAX_BUCKETS} buckets requested"
+            )));
+        }
+        let end = bucket.next(start);
+        let mut usage = Usage::default();
+        while let Some((_, act)) = acts.next_if(|(s, _)| *s < end) {
+            usage = usage + act;
+        }
+        total = total + &usage;
+        res.push(UsagePoint {
+            start,
+            end,
+            usage,
+            total: total.clone(),
+        });
+        start = end;
+    }
+    Ok(res)
+}
+
+impl PartId {
+    /// The usage of the part at a point in time
+    ///
+    /// Sums up all activities of the gears the part was attached to until `at`
+    /// weighted by the usage rules
+    pub async fn usage_at(
+        self,
+        at: OffsetDateTime,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Usage> {
+        self.checkuser(user, store).await?;
+        self.usage_until(at, store).await
+    }
+
+    /// sum up the activities of the part until `at`
+    pub(crate) async fn usage_until(
+        self,
+        at: OffsetDateTime,
+        store: &mut impl Store,
+    ) -> TbResult<Usage> {
+        Ok(self
+            .usages(MIN_TIME, at, store)
+            .await?
+            .into_iter()
+            .fold(Usage::default(), |usage, (_, act)| usage + act))
+    }
+
+    /// The usage of the part per week or month
+    ///
+    /// # Arguments
+    ///
+    /// * `bucket` - the size of the buckets
+    /// * `from` - the first bucket contains this time, defaults to the purchase of the part
+    /// * `to` - the last bucket contains this time, defaults to now or the disposal of the part
+    pub async fn usage_series(
+        self,
+        bucket: Bucket,
+        from: Option<OffsetDateTime>,
+        to: Option<OffsetDateTime>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<UsagePoint>> {
+        let part = self.part(user, store).await?;
+        let from = from.unwrap_or(part.purchase);
+        let to = to
+            .or(part.disposed_at)
+            .unwrap_or_else(OffsetDateTime::now_utc);
+        if from > to {
+            return Err(Error::BadRequest(format!("{from} is later than {to}")));
+        }
+        let acts = self.usages(MIN_TIME, to, store).await?;
+        series(bucket, from, to, acts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn bucket_bounds() {
+        let time = datetime!(2024-12-18 15:30 +2);
+        let week = Bucket::Week.start(time);
+        assert_eq!(week, datetime!(2024-12-16 0:00 UTC));
+        assert_eq!(Bucket::Week.next(week), datetime!(2024-12-23 0:00 UTC));
+        let month = Bucket::Month.start(time);
+        assert_eq!(month, datetime!(2024-12-01 0:00 UTC));
+        assert_eq!(Bucket::Month.next(month), datetime!(2025-01-01 0:00 UTC));
+    }
+
+    #[test]
+    fn monthly_series() {
+        let km = |km| Usage {
+            distance: km * 1000,
+            count: 1,
+            ..Default::default()
+        };
+        let acts = vec![
+            (datetime!(2024-03-10 10:00 UTC), km(30)),
+            (datetime!(2024-01-05 10:00 UTC), km(10)),
+            (datetime!(2024-03-01 0:00 UTC), km(20)),
+        ];
+        let res = series(
+            Bucket::Month,
+            datetime!(2024-02-15 0:00 UTC),
+            datetime!(2024-03-20 0:00 UTC),
+            acts,
+        )
+        .unwrap();
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].start, datetime!(2024-02-01 0:00 UTC));
+        assert_eq!(res[0].usage.distance, 0);
+        assert_eq!(res[0].total.distance, 10_000);
+        assert_eq!(res[1].usage.distance, 50_000);
+        assert_eq!(res[1].usage.count, 2);
+        assert_eq!(res[1].total.distance, 60_000);
+    }
+}
@@ -0,0 +1,332 @@
+//! The life story of a part
+//!
+//! The timeline merges everything that happened to a part into one ordered list:
+//! the purchase, attaching and detaching, services, disposal and recovery.
+//! For gears the parts mounted to and removed from it are listed as well.
+//!
+//! Activities are aggregated between these events, but never beyond a week or a month.
+//! Every entry carries the usage of the part at that point.
+//!
+//! Disposals and recoveries are taken from the audit log, a recovery shows up at the time
+//! it was made. A disposal from before the audit log only shows up while the part is disposed.
+
+use serde_derive::{Deserialize, Serialize};
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+use crate::*;
+
+/// Something which happened to a part
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TimelineEvent {
+    Purchase,
+    /// the part was attached to a gear
+    Attach {
+        gear: PartId,
+        hook: PartTypeId,
+    },
+    /// the part was detached from a gear
+    Detach {
+        gear: PartId,
+        hook: PartTypeId,
+    },
+    /// a part was mounted to the gear
+    Mount {
+        part: PartId,
+        hook: PartTypeId,
+    },
+    /// a part was removed from the gear
+    Unmount {
+        part: PartId,
+        hook: PartTypeId,
+    },
+    Service {
+        service: ServiceId,
+        name: String,
+    },
+    /// a service was done again
+    Redo {
+        service: ServiceId,
+        name: String,
+        previous: ServiceId,
+    },
+    Dispose,
+    /// the disposed part was taken into use again
+    Recover,
+    /// the part was used for activities
+    Activities {
+        /// the start of the last activity
+        #[serde(with = "time::serde::rfc3339")]
+        until: OffsetDateTime,
+        /// the usage of these activities
+        usage: Usage,
+    },
+}
+
+/// An entry of the timeline
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+    /// the usage of the part at that point
+    pub total: Usage,
+}
+
+/// the disposals and recoveries of `part` in the entries of the audit log
+///
+/// Only `disposed_at` is read from the recorded parts, older entries may lack other fields.
+/// The current disposal is added if it is not recorded
+fn disposals(
+    part: &Part,
+    entries: &[AuditEntry],
+) -> TbResult<Vec<(OffsetDateTime, TimelineEvent)>> {
+    let disposed_at = |value: &Option<serde_json::Value>| -> TbResult<Option<OffsetDateTime>> {
+        match value.as_ref().and_then(|v| v.get("disposed_at")) {
+            Some(serde_json::Value::String(time)) => OffsetDateTime::parse(time, &Rfc3339)
+                .map(Some)
+                .map_err(|e| Error::AnyFailure(e.into())),
+            _ => Ok(None),
+        }
+    };
+    let mut res = Vec::new();
+    for entry in entries {
+        match (disposed_at(&entry.before)?, disposed_at(&entry.after)?) {
+            (None, Some(time)) => res.push((time, TimelineEvent::Dispose)),
+            (Some(_), None) => res.push((entry.time, TimelineEvent::Recover)),
+            _ => (),
+        }
+    }
+    if let Some(disposed) = part.disposed_at
+        && !res.contains(&(disposed, TimelineEvent::Dispose))
+    {
+        res.push((disposed, TimelineEvent::Dispose));
+    }
+    Ok(res)
+}
+
+/// merge the events and the activities into one timeline
+///
+/// `acts` holds the start and the usage of the activities
+fn merge(
+    bucket: Bucket,
+    mut events: Vec<(OffsetDateTime, TimelineEvent)>,
+    mut acts: Vec<(OffsetDateTime, Usage)>,
+) -> Vec<TimelineEntry> {
+    // stable sort keeps the purchase in front
+    events.sort_by_key(|(time, _)| *time);
+    acts.sort_by_key(|(start, _)| *start);
+    let mut acts = acts.into_iter().peekable();
+    let mut total = Usage::default();
+    let mut res = Vec::new();
+
+    let mut flush = |end: OffsetDateTime, total: &mut Usage, res: &mut Vec<TimelineEntry>| {
+        while let Some((time, usage)) = acts.next_if(|(s, _)| *s < end) {
+            let bucket_end = bucket.next(bucket.start(time));
+            let mut until = time;
+            let mut usage = usage;
+            while let Some((start, act)) = acts.next_if(|(s, _)| *s < end && *s < bucket_end) {
+                until = start;
+                usage = usage + act;
+            }
+            *total = &*total + &usage;
+            res.push(TimelineEntry {
+                time,
+                event: TimelineEvent::Activities { until, usage },
+                total: total.clone(),
+            });
+        }
+    };
+
+    for (time, event) in events {
+        flush(time, &mut total, &mut res);
+        res.push(TimelineEntry {
+            time,
+            event,
+            total: total.clone(),
+        });
+    }
+    flush(MAX_TIME, &mut total, &mut res);
+    res
+}
+
+impl PartId {
+    /// The timeline of the part
+    ///
+    /// # Arguments
+    ///
+    /// * `bucket` - activities are not aggregated beyond a week or a month
+    pub async fn timeline(
+        self,
+        bucket: Bucket,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<TimelineEntry>> {
+        let part = self.part(user, store).await?;
+
+        let mut events = vec![(part.purchase, TimelineEvent::Purchase)];
+        for att in store.attachments_all_by_part(self).await? {
+            let Attachment {
+                gear,
+                hook,
+                attached,
+                detached,
+                ..
+            } = att;
+            events.push((attached, TimelineEvent::Attach { gear, hook }));
+            if detached < MAX_TIME {
+                events.push((detached, TimelineEvent::Detach { gear, hook }));
+            }
+        }
+        for att in store.attachments_all_by_gear(self).await? {
+            let Attachment {
+                part_id: part,
+                hook,
+                attached,
+                detached,
+                ..
+            } = att;
+            events.push((attached, TimelineEvent::Mount { part, hook }));
+            if detached < MAX_TIME {
+                events.push((detached, TimelineEvent::Unmount { part, hook }));
+            }
+        }
+
+        let services = store.services_by_part(self).await?;
+        for service in &services {
+            let previous = services
+                .iter()
+                .find(|s| s.successor == Some(service.id))
+                .map(|s| s.id);
+            let (time, service, name) = (service.time, service.id, service.name.clone());
+            let event = match previous {
+                Some(previous) => TimelineEvent::Redo {
+                    service,
+                    name,
+                    previous,
+                },
+                None => TimelineEvent::Service { service, name },
+            };
+            events.push((time, event));
+        }
+        let entries = store.audit_part_disposals(self).await?;
+        events.append(&mut disposals(&part, &entries)?);
+
+        let acts = self.usages(MIN_TIME, MAX_TIME, store).await?;
+        Ok(merge(bucket, events, acts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn merge_events_and_activities() {
+        let km = |km| Usage {
+            distance: km * 1000,
+            count: 1,
+            ..Default::default()
+        };
+        let events = vec![
+            (datetime!(2024-02-10 0:00 UTC), TimelineEvent::Dispose),
+            (datetime!(2024-01-01 0:00 UTC), TimelineEvent::Purchase),
+        ];
+        let acts = vec![
+            (datetime!(2024-01-05 0:00 UTC), km(10)),
+            (datetime!(2024-01-20 0:00 UTC), km(20)),
+            (datetime!(2024-02-01 0:00 UTC), km(30)),
+            (datetime!(2024-02-20 0:00 UTC), km(40)),
+        ];
+        let res = merge(Bucket::Month, events, acts);
+        let kinds: Vec<_> = res
+            .iter()
+            .map(|e| match &e.event {
+                TimelineEvent::Activities { usage, .. } => usage.distance / 1000,
+                TimelineEvent::Purchase => -1,
+                _ => -2,
+            })
+            .collect();
+        assert_eq!(kinds, vec![-1, 30, 30, -2, 40]);
+        assert_eq!(res[1].time, datetime!(2024-01-05 0:00 UTC));
+        assert_eq!(
+            res[1].event,
+            TimelineEvent::Activities {
+                until: datetime!(2024-01-20 0:00 UTC),
+                usage: Usage {
+                    distance: 30_000,
+                    count: 2,
+                    ..Default::default()
+                }
+            }
+        );
+        assert_eq!(res[3].total.distance, 60_000);
+        assert_eq!(res[4].total.distance, 100_000);
+    }
+
+    #[test]
+    fn dispose_and_recover() {
+        let part = |disposed_at| Part {
+            id: 1.into(),
+            owner: 1.into(),
+            what: 1.into(),
+            name: "chain".into(),
+            vendor: String::new(),
+            model: String::new(),
+            purchase: datetime!(2024-01-01 0:00 UTC),
+            last_used: datetime!(2024-01-01 0:00 UTC),
+            disposed_at,
+            usage: UsageId::default(),
+            source: None,
+            shop: None,
+            notes: String::new(),
+            price: None,
+        };
+        let entry =
+            |time, before: Option<OffsetDateTime>, after: Option<OffsetDateTime>| AuditEntry {
+                id: 1.into(),
+                time,
+                actor: 1.into(),
+                user: 1.into(),
+                entity: "part".into(),
+                entity_id: "1".into(),
+                part: Some(1.into()),
+                before: Some(serde_json::to_value(part(before)).unwrap()),
+                after: Some(serde_json::to_value(part(after)).unwrap()),
+            };
+        let feb = datetime!(2024-02-01 0:00 UTC);
+        let mar = datetime!(2024-03-01 0:00 UTC);
+        let apr = datetime!(2024-04-01 0:00 UTC);
+        let entries = vec![
+            entry(datetime!(2024-02-03 0:00 UTC), None, Some(feb)),
+            entry(mar, Some(feb), None),
+            entry(apr, None, Some(apr)),
+        ];
+        assert_eq!(
+            disposals(&part(Some(apr)), &entries).unwrap(),
+            vec![
+                (feb, TimelineEvent::Dispose),
+                (mar, TimelineEvent::Recover),
+                (apr, TimelineEvent::Dispose)
+            ]
+        );
+        // a disposal from before the audit log
+        assert_eq!(
+            disposals(&part(Some(feb)), &[]).unwrap(),
+            vec![(feb, TimelineEvent::Dispose)]
+        );
+        // the part looked different back then
+        let old = AuditEntry {
+            before: Some(serde_json::json!({"name": "chain", "weight": 250})),
+            after: Some(serde_json::json!({"disposed_at": "2024-02-01T00:00:00Z"})),
+            ..entries[0].clone()
+        };
+        assert_eq!(
+            disposals(&part(Some(feb)), &[old]).unwrap(),
+            vec![(feb, TimelineEvent::Dispose)]
+        );
+    }
+}
@@ -32,14 +32,23 @@ impl ServiceId {
 
         // set successors to none
         let mut res = Vec::new();
-        for mut s in services {
-            s.successor = None;
-            res.push(ServiceStore::update(store, s).await?);
+        for s in services {
+            let new = ServiceStore::update(
+                store,
+                Service {
+                    successor: None,
+                    ..s.clone()
+                },
+            )
+            .await?;
+            s.audit(Some(&s), Some(&new), user, store).await?;
+            res.push(new);
         }
 
         // delete service
         service.usage.delete(store).await?;
         ServiceStore::delete(store, self).await?;
+        service.audit(Some(&service), None, user, store).await?;
         Ok(Summary {
             services: res,
             ..Default::default()
@@ -72,9 +81,13 @@ pub struct Service {
     pub successor: Option<ServiceId>,
     // an optional ServicePlan it is fullfilling
     pub plans: Vec<ServicePlanId>,
+    /// what the service cost
+    #[serde(default)]
+    pub cost: Option<Money>,
 }
 
 impl Service {
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         part_id: PartId,
         time: OffsetDateTime,
@@ -82,8 +95,11 @@ impl Service {
         notes: String,
         successor: Option<ServiceId>,
         plans: Vec<ServicePlanId>,
+        cost: Option<Money>,
+        user: &dyn Session,
         store: &mut impl Store,
     ) -> TbResult<Summary> {
+        let cost = cost.map(Money::check).transpose()?;
         let service = Service {
             id: ServiceId::new(),
             part_id,
@@ -94,9 +110,11 @@ impl Service {
             usage: UsageId::new(),
             successor,
             plans,
+            cost,
         };
         let usage = service.calculate_usage(store).await?.update(store).await?;
         let service = ServiceStore::create(store, service).await?;
+        service.audit(None, Some(&service), user, store).await?;
         Ok(Summary {
             services: vec![service],
             usages: vec![usage],
@@ -104,14 +122,33 @@ impl Service {
         })
     }
 
-    async fn calculate_usage(&self, store: &mut impl Store) -> TbResult<Usage> {
-        Ok(if self.part_id.is_main(store).await? {
-            Activity::find(self.part_id, MIN_TIME, self.time, store).await?
-        } else {
-            Attachment::activities_by_part(self.part_id, MIN_TIME, self.time, store).await?
-        }
-        .into_iter()
-        .fold(Usage::new(self.usage), |usage, act| usage + &act.usage()))
+    /// record the change of the service
+    async fn audit(
+        &self,
+        before: Option<&Service>,
+        after: Option<&Service>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        AuditEntry::record(
+            user,
+            "service",
+            self.id,
+            Some(self.part_id),
+            before,
+            after,
+            store,
+        )
+        .await
+    }
+
+    pub(crate) async fn calculate_usage(&self, store: &mut impl Store) -> TbResult<Usage> {
+        Ok(self
+            .part_id
+            .usages(MIN_TIME, self.time, store)
+            .await?
+            .into_iter()
+            .fold(Usage::new(self.usage), |usage, (_, act)| usage + act))
     }
 
     pub async fn redo(self, user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
@@ -120,6 +157,7 @@ impl Service {
             notes,
             time,
             plans,
+            cost,
             ..
         } = self;
         let mut old = id.get(store).await?;
@@ -132,6 +170,8 @@ impl Service {
                 notes,
                 Some(old.id),
                 plans,
+                cost,
+                user,
                 store,
             )
             .await
@@ -143,17 +183,28 @@ impl Service {
                 notes,
                 None,
                 plans,
+                cost,
+                user,
                 store,
             )
             .await?;
             old.successor = Some(res.services[0].id);
-            Ok(res + old.update_unchecked(store).await?)
+            Ok(res + old.update_unchecked(user, store).await?)
         }
     }
 
-    async fn update_unchecked(self, store: &mut impl Store) -> TbResult<Summary> {
+    async fn update_unchecked(
+        self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        let before = self.id.get(store).await?;
         let usages = vec![self.calculate_usage(store).await?.update(store).await?];
-        let services = vec![ServiceStore::update(store, self).await?];
+        let service = ServiceStore::update(store, self).await?;
+        service
+            .audit(Some(&before), Some(&service), user, store)
+            .await?;
+        let services = vec![service];
         Ok(Summary {
             usages,
             services,
@@ -163,9 +214,10 @@ impl Service {
 
     pub async fn update(mut self, user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
         self.part_id.checkuser(user, store).await?;
+        self.cost = self.cost.map(Money::check).transpose()?;
         let service = self.id.get(store).await?;
         self.usage = service.usage;
-        self.update_unchecked(store).await
+        self.update_unchecked(user, store).await
     }
 
     pub(crate) async fn get_usageids(
@@ -1,9 +1,16 @@
 use derive_more::{Display, From, Into};
 use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::*;
 
+mod forecast;
+pub use forecast::*;
+
+mod status;
+pub use status::*;
+
 #[derive(
     Clone, Copy, Debug, Display, From, Into, Hash, PartialEq, Eq, Serialize, Deserialize, Default,
 )]
@@ -14,7 +21,7 @@ impl ServicePlanId {
         Uuid::now_v7().into()
     }
 
-    async fn get(self, store: &mut impl ServicePlanStore) -> TbResult<ServicePlan> {
+    pub(crate) async fn get(self, store: &mut impl ServicePlanStore) -> TbResult<ServicePlan> {
         store.get(self).await
     }
 
@@ -30,6 +37,7 @@ impl ServicePlanId {
 
         // delete service
         ServicePlanStore::delete(store, self).await?;
+        plan.audit(Some(&plan), None, user, store).await?;
         Ok(res)
     }
 }
@@ -81,17 +89,24 @@ impl ServicePlan {
         Ok(())
     }
 
-    pub async fn create(
-        mut self,
-        user: &dyn Session,
-        store: &mut (impl ServicePlanStore + PartStore),
-    ) -> TbResult<Self> {
+    pub async fn create(mut self, user: &dyn Session, store: &mut impl Store) -> TbResult<Self> {
         self.id = ServicePlanId::new();
         self.uid = match self.part {
             Some(_) => None,
             None => Some(user.user_id()),
         };
-        store.create(self).await
+        // private part types can only be used by their owner
+        let owner = match self.part {
+            Some(part) => part.read(store).await?.owner,
+            None => user.user_id(),
+        };
+        self.what.visible(owner)?;
+        if let Some(hook) = self.hook {
+            hook.visible(owner)?;
+        }
+        let plan = ServicePlanStore::create(store, self).await?;
+        plan.audit(None, Some(&plan), user, store).await?;
+        Ok(plan)
     }
 
     pub async fn update(
@@ -106,7 +121,29 @@ impl ServicePlan {
         self.what = plan.what;
         self.hook = plan.hook;
         self.uid = plan.uid;
-        store.plan_update(self).await
+        let new = store.plan_update(self).await?;
+        new.audit(Some(&plan), Some(&new), user, store).await?;
+        Ok(new)
+    }
+
+    /// record the change of the plan
+    async fn audit(
+        &self,
+        before: Option<&ServicePlan>,
+        after: Option<&ServicePlan>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        AuditEntry::record(
+            user,
+            "service_plan",
+            self.id,
+            self.part,
+            before,
+            after,
+            store,
+        )
+        .await
     }
 
     pub(crate) async fn for_part(
@@ -122,4 +159,86 @@ impl ServicePlan {
     ) -> TbResult<Vec<Self>> {
         store.by_user(*uid).await
     }
+
+    /// The parts the plan applies to right now
+    ///
+    /// A plan for a specific part applies to that gear only.
+    /// A generic plan applies to all gears of the user with the matching type
+    /// unless there is a specific plan for the same type and hook on that gear.
+    /// If the plan has a hook, the part currently attached there is returned.
+    pub(crate) async fn parts(&self, store: &mut impl Store) -> TbResult<Vec<Part>> {
+        let gears = match (self.part, self.uid) {
+            (Some(part), _) => vec![part.read(store).await?],
+            (None, Some(uid)) => {
+                let main = self.what.get()?.main;
+                let mut gears = Vec::new();
+                for gear in Part::get_all(&uid, store).await? {
+                    if gear.disposed_at.is_some() || gear.what != main {
+                        continue;
+                    }
+                    let specific = ServicePlan::for_part(gear.id, store)
+                        .await?
+                        .into_iter()
+                        .any(|p| p.hook == self.hook && p.what == self.what);
+                    if !specific {
+                        gears.push(gear);
+                    }
+                }
+                gears
+            }
+            (None, None) => return Ok(Vec::new()),
+        };
+
+        let mut res = Vec::new();
+        for gear in gears {
+            if let Some(part) = self.part_at_hook(gear, store).await? {
+                res.push(part);
+            }
+        }
+        Ok(res)
+    }
+
+    /// The part attached to the hook of the plan on the gear
+    async fn part_at_hook(&self, gear: Part, store: &mut impl Store) -> TbResult<Option<Part>> {
+        let part = match self.hook {
+            Some(hook) => {
+                let att = store
+                    .attachment_find_part_of_type_at_hook_and_time(
+                        self.what,
+                        gear.id,
+                        hook,
+                        OffsetDateTime::now_utc(),
+                    )
+                    .await?;
+                match att {
+                    Some(att) => att.part_id.read(store).await?,
+                    None => return Ok(None),
+                }
+            }
+            None => gear,
+        };
+        Ok((part.what == self.what).then_some(part))
+    }
+
+    /// The usage of the part since the last service for this plan
+    ///
+    /// Returns the start of the period, which is the time of the last service
+    /// or the purchase date, and the usage since then.
+    pub(crate) async fn usage_since_service(
+        &self,
+        part: &Part,
+        store: &mut impl Store,
+    ) -> TbResult<(OffsetDateTime, Usage)> {
+        let service = store
+            .services_by_part(part.id)
+            .await?
+            .into_iter()
+            .filter(|s| s.plans.contains(&self.id))
+            .max_by_key(|s| s.time);
+        let usage = part.usage.read(store).await?;
+        Ok(match service {
+            Some(service) => (service.time, usage - service.usage.read(store).await?),
+            None => (part.purchase, usage),
+        })
+    }
 }
@@ -0,0 +1,314 @@
+//! Forecast when a service plan will be due
+//!
+//! The usage since the last service is compared to the limits of the plan.
+//! The remaining amount is projected into the future using the average usage rate
+//! of the part over the last weeks. The metric which reaches its limit first determines
+//! the due date of the plan.
+
+use serde_derive::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::*;
+
+/// The default number of weeks used to determine the usage rate
+pub const FORECAST_WEEKS: u32 = 8;
+
+/// The metrics a service plan can limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Metric {
+    Days,
+    Rides,
+    Hours,
+    Km,
+    Climb,
+    Descend,
+    #[serde(rename = "kJ")]
+    Energy,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 7] = [
+        Metric::Days,
+        Metric::Rides,
+        Metric::Hours,
+        Metric::Km,
+        Metric::Climb,
+        Metric::Descend,
+        Metric::Energy,
+    ];
+
+    /// The limit the plan sets for this metric
+    pub fn limit(self, plan: &ServicePlan) -> Option<i32> {
+        match self {
+            Metric::Days => plan.days,
+            Metric::Rides => plan.rides,
+            Metric::Hours => plan.hours,
+            Metric::Km => plan.km,
+            Metric::Climb => plan.climb,
+            Metric::Descend => plan.descend,
+            Metric::Energy => plan.energy,
+        }
+        .filter(|&l| l > 0)
+    }
+
+    /// The usage in the unit of the limit
+    ///
+    /// Days are not part of the usage and are always 0
+    pub fn amount(self, usage: &Usage) -> f64 {
+        match self {
+            Metric::Days => 0.0,
+            Metric::Rides => usage.count as f64,
+            Metric::Hours => usage.time as f64 / 3600.0,
+            Metric::Km => usage.distance as f64 / 1000.0,
+            Metric::Climb => usage.climb as f64,
+            Metric::Descend => usage.descend as f64,
+            Metric::Energy => usage.energy as f64,
+        }
+    }
+
+    /// What is left until the limit is reached
+    ///
+    /// Like in the frontend, hours and km are rounded down before subtracting.
+    pub fn remaining(
+        self,
+        limit: i32,
+        start: OffsetDateTime,
+        used: &Usage,
+        now: OffsetDateTime,
+    ) -> i32 {
+        let used = match self {
+            Metric::Days => (now - start).whole_days() as i32,
+            _ => self.amount(used).floor() as i32,
+        };
+        limit - used
+    }
+}
+
+/// The projection for a single metric of a plan
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetricForecast {
+    pub metric: Metric,
+    /// the limit of the plan
+    pub limit: i32,
+    /// what is left until the limit is reached, negative if it is overdue
+    pub remaining: i32,
+    /// the average usage per week in the unit of the limit
+    pub per_week: f64,
+    /// when the limit will be reached
+    ///
+    /// Overdue metrics are due now, the days limit is due at its exact date.
+    /// None if the part was not used in the observed period.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub due: Option<OffsetDateTime>,
+}
+
+/// The projection of a service plan for one part
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlanForecast {
+    pub plan: ServicePlanId,
+    /// the part the plan applies to
+    pub part: PartId,
+    /// the metric which reaches its limit first
+    pub limiting: Option<Metric>,
+    /// when the plan will be due
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub due: Option<OffsetDateTime>,
+    /// the projection for every metric the plan limits
+    pub metrics: Vec<MetricForecast>,
+}
+
+impl PlanForecast {
+    /// Project the limits of the plan
+    ///
+    /// # Arguments
+    ///
+    /// * `plan` - the service plan
+    /// * `part` - the part the plan applies to
+    /// * `start` - the last service or the purchase of the part
+    /// * `used` - the usage since start
+    /// * `rate` - the usage in the observed period
+    /// * `days` - the length of the observed period in days
+    /// * `now` - the point in time to project from
+    pub(crate) fn project(
+        plan: &ServicePlan,
+        part: PartId,
+        start: OffsetDateTime,
+        used: &Usage,
+        rate: &Usage,
+        days: f64,
+        now: OffsetDateTime,
+    ) -> Self {
+        let metrics: Vec<_> = Metric::ALL
+            .into_iter()
+            .filter_map(|metric| {
+                let limit = metric.limit(plan)?;
+                let remaining = metric.remaining(limit, start, used, now);
+                let per_day = match metric {
+                    Metric::Days => 1.0,
+                    _ => metric.amount(rate) / days,
+                };
+                let due = if metric == Metric::Days {
+                    start.checked_add(Duration::days(limit.into()))
+                } else if remaining <= 0 {
+                    Some(now)
+                } else if per_day > 0.0 {
+                    now.checked_add(Duration::seconds_f64(remaining as f64 / per_day * 86400.0))
+                } else {
+                    None
+                };
+                Some(MetricForecast {
+                    metric,
+                    limit,
+                    remaining,
+                    per_week: per_day * 7.0,
+                    due,
+                })
+            })
+            .collect();
+
+        let first = metrics
+            .iter()
+            .filter_map(|m| m.due.map(|due| (due, m.metric)))
+            .min_by_key(|(due, _)| *due);
+
+        PlanForecast {
+            plan: plan.id,
+            part,
+            limiting: first.map(|(_, metric)| metric),
+            due: first.map(|(due, _)| due),
+            metrics,
+        }
+    }
+}
+
+impl ServicePlanId {
+    /// Forecast when the plan will be due
+    ///
+    /// The usage rate is the average over the last `weeks` weeks,
+    /// or since the purchase if the part is younger.
+    ///
+    /// # Returns
+    ///
+    /// A forecast for every part the plan currently applies to.
+    /// Generic plans may apply to several parts.
+    pub async fn forecast(
+        self,
+        weeks: u32,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<PlanForecast>> {
+        if weeks == 0 {
+            return Err(Error::BadRequest("weeks must be positive".into()));
+        }
+        let plan = self.get(store).await?;
+        plan.checkuser(user, store).await?;
+
+        let now = OffsetDateTime::now_utc();
+        let mut res = Vec::new();
+        for part in plan.parts(store).await? {
+            let (start, used) = plan.usage_since_service(&part, store).await?;
+            let begin = std::cmp::max(now - Duration::weeks(weeks.into()), part.purchase);
+            let rate = part
+                .id
+                .usages(begin, now, store)
+                .await?
+                .into_iter()
+                .fold(Usage::default(), |usage, (_, act)| usage + act);
+            let days = ((now - begin).as_seconds_f64() / 86400.0).max(1.0);
+            res.push(PlanForecast::project(
+                &plan, part.id, start, &used, &rate, days, now,
+            ));
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+
+    use crate::*;
+
+    fn assert_near(due: Option<OffsetDateTime>, expected: OffsetDateTime) {
+        let due = due.expect("no due date");
+        assert!((due - expected).abs() < Duration::seconds(1));
+    }
+
+    #[test]
+    fn earliest_metric_limits() {
+        let now = OffsetDateTime::now_utc();
+        let start = now - Duration::days(10);
+        let plan = ServicePlan {
+            id: ServicePlanId::default(),
+            part: Some(1.into()),
+            what: 1.into(),
+            hook: None,
+            name: "chain".into(),
+            days: Some(365),
+            hours: None,
+            km: Some(1000),
+            climb: Some(20000),
+            descend: None,
+            rides: None,
+            uid: None,
+            energy: None,
+        };
+        // 400 km and 2000 m since the last service
+        let used = Usage {
+            distance: 400_000,
+            climb: 2000,
+            ..Default::default()
+        };
+        // 100 km and 1000 m per week
+        let rate = Usage {
+            distance: 200_000,
+            climb: 2000,
+            ..Default::default()
+        };
+        let forecast = PlanForecast::project(&plan, 1.into(), start, &used, &rate, 14.0, now);
+
+        assert_eq!(forecast.metrics.len(), 3);
+        assert_eq!(forecast.limiting, Some(Metric::Km));
+        assert_near(forecast.due, now + Duration::weeks(6));
+        let days = &forecast.metrics[0];
+        assert_eq!(days.remaining, 355);
+        assert_eq!(days.due, Some(start + Duration::days(365)));
+        let climb = &forecast.metrics[2];
+        assert_eq!(climb.remaining, 18000);
+        assert_near(climb.due, now + Duration::weeks(18));
+    }
+
+    #[test]
+    fn unused_part_is_never_due() {
+        let now = OffsetDateTime::now_utc();
+        let plan = ServicePlan {
+            id: ServicePlanId::default(),
+            part: Some(1.into()),
+            what: 1.into(),
+            hook: None,
+            name: "fork".into(),
+            days: None,
+            hours: Some(50),
+            km: None,
+            climb: None,
+            descend: None,
+            rides: Some(10),
+            uid: None,
+            energy: None,
+        };
+        let used = Usage {
+            count: 12,
+            time: 3600,
+            ..Default::default()
+        };
+        let forecast =
+            PlanForecast::project(&plan, 1.into(), now, &used, &Usage::default(), 56.0, now);
+
+        assert_eq!(forecast.limiting, Some(Metric::Rides));
+        assert_eq!(forecast.due, Some(now));
+        assert_eq!(forecast.metrics[0].remaining, -2);
+        assert_eq!(forecast.metrics[1].remaining, 49);
+        assert_eq!(forecast.metrics[1].due, None);
+    }
+}
@@ -0,0 +1,230 @@
+//! The status of a service plan
+//!
+//! For every part a plan applies to, the usage since the last service is compared to the limits.
+//! The result tells how much is left per metric and how urgent a service is.
+//! This mirrors what the frontend used to compute, so all clients get the same answer.
+
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// Below this fraction of the limit a plan is due soon
+const SOON: f64 = 0.05;
+
+/// How urgent a service plan is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum DueState {
+    /// No limit is close
+    #[default]
+    Ok = 0,
+    /// Less than 5% of a limit is left
+    Soon = 1,
+    /// A limit was exceeded
+    Overdue = 2,
+}
+
+impl std::convert::TryFrom<i32> for DueState {
+    type Error = Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Ok),
+            1 => Ok(Self::Soon),
+            2 => Ok(Self::Overdue),
+            _ => Err(Error::BadRequest(format!("Invalid due state: {}", value))),
+        }
+    }
+}
+
+impl From<DueState> for i32 {
+    fn from(state: DueState) -> i32 {
+        state as i32
+    }
+}
+
+impl DueState {
+    /// The state of a single limit
+    pub fn of_limit(limit: i32, remaining: i32) -> Self {
+        if remaining < 0 {
+            DueState::Overdue
+        } else if (remaining as f64) < limit as f64 * SOON {
+            DueState::Soon
+        } else {
+            DueState::Ok
+        }
+    }
+}
+
+/// The status of a single metric of a plan
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetricStatus {
+    pub metric: Metric,
+    /// the limit of the plan
+    pub limit: i32,
+    /// what is left until the limit is reached, negative if it is overdue
+    pub remaining: i32,
+    /// the percentage of the limit used up, more than 100 if it is overdue
+    pub percent: f64,
+    pub state: DueState,
+}
+
+/// The status of a service plan for one part
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlanStatus {
+    pub plan: ServicePlanId,
+    /// the part the plan applies to
+    pub part: PartId,
+    /// the last service for this plan or the purchase of the part
+    #[serde(with = "time::serde::rfc3339")]
+    pub since: OffsetDateTime,
+    /// the most urgent state of all metrics
+    pub state: DueState,
+    /// the status of every metric the plan limits
+    pub metrics: Vec<MetricStatus>,
+}
+
+impl PlanStatus {
+    /// Compare the usage since the last service to the limits of the plan
+    ///
+    /// # Arguments
+    ///
+    /// * `plan` - the service plan
+    /// * `part` - the part the plan applies to
+    /// * `since` - the last service or the purchase of the part
+    /// * `used` - the usage since then
+    /// * `now` - the point in time to evaluate the days limit
+    pub(crate) fn new(
+        plan: &ServicePlan,
+        part: PartId,
+        since: OffsetDateTime,
+        used: &Usage,
+        now: OffsetDateTime,
+    ) -> Self {
+        let metrics: Vec<_> = Metric::ALL
+            .into_iter()
+            .filter_map(|metric| {
+                let limit = metric.limit(plan)?;
+                let remaining = metric.remaining(limit, since, used, now);
+                Some(MetricStatus {
+                    metric,
+                    limit,
+                    remaining,
+                    percent: (limit - remaining) as f64 * 100.0 / limit as f64,
+                    state: DueState::of_limit(limit, remaining),
+                })
+            })
+            .collect();
+        PlanStatus {
+            plan: plan.id,
+            part,
+            since,
+            state: metrics.iter().map(|m| m.state).max().unwrap_or_default(),
+            metrics,
+        }
+    }
+
+    /// The status of the plan for every part it currently applies to
+    pub(crate) async fn for_plan(
+        plan: &ServicePlan,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<Self>> {
+        let now = OffsetDateTime::now_utc();
+        let mut res = Vec::new();
+        for part in plan.parts(store).await? {
+            let (since, used) = plan.usage_since_service(&part, store).await?;
+            res.push(Self::new(plan, part.id, since, &used, now));
+        }
+        Ok(res)
+    }
+
+    /// The status of all given plans
+    pub(crate) async fn for_plans(
+        plans: &[ServicePlan],
+        store: &mut impl Store,
+    ) -> TbResult<Vec<Self>> {
+        let mut res = Vec::new();
+        for plan in plans {
+            res.append(&mut Self::for_plan(plan, store).await?);
+        }
+        Ok(res)
+    }
+
+    /// The status of all service plans of the user
+    pub async fn for_user(user: &dyn Session, store: &mut impl Store) -> TbResult<Vec<Self>> {
+        let user = user.user_id();
+        let mut plans = ServicePlan::for_user(&user, store).await?;
+        for part in Part::get_all(&user, store).await? {
+            plans.append(&mut ServicePlan::for_part(part.id, store).await?);
+        }
+        Self::for_plans(&plans, store).await
+    }
+}
+
+impl ServicePlanId {
+    /// The status of the plan for every part it currently applies to
+    pub async fn status(
+        self,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<PlanStatus>> {
+        let plan = self.get(store).await?;
+        plan.checkuser(user, store).await?;
+        PlanStatus::for_plan(&plan, store).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Duration, OffsetDateTime};
+
+    use crate::*;
+
+    #[test]
+    fn status_of_plan() {
+        let now = OffsetDateTime::now_utc();
+        let plan = ServicePlan {
+            id: ServicePlanId::default(),
+            part: Some(1.into()),
+            what: 1.into(),
+            hook: None,
+            name: "brake pads".into(),
+            days: Some(100),
+            hours: None,
+            km: Some(1000),
+            climb: None,
+            descend: None,
+            rides: None,
+            uid: None,
+            energy: None,
+        };
+        let used = |km| Usage {
+            distance: km * 1000,
+            ..Default::default()
+        };
+        let status = |since, km| PlanStatus::new(&plan, 1.into(), since, &used(km), now);
+
+        let start = now - Duration::days(10);
+        let ok = status(start, 900);
+        assert_eq!(ok.state, DueState::Ok);
+        assert_eq!(ok.metrics.len(), 2);
+        assert_eq!(ok.metrics[0].metric, Metric::Days);
+        assert_eq!(ok.metrics[0].remaining, 90);
+        assert_eq!(ok.metrics[0].percent, 10.0);
+        assert_eq!(ok.metrics[1].remaining, 100);
+        assert_eq!(ok.metrics[1].percent, 90.0);
+
+        assert_eq!(status(start, 951).state, DueState::Soon);
+        let overdue = status(start, 1001);
+        assert_eq!(overdue.state, DueState::Overdue);
+        assert_eq!(overdue.metrics[0].state, DueState::Ok);
+        assert_eq!(overdue.metrics[1].remaining, -1);
+
+        assert_eq!(
+            status(now - Duration::days(101), 0).state,
+            DueState::Overdue
+        );
+    }
+}
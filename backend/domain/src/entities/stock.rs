@@ -0,0 +1,380 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Spare parts on stock
+//!
+//! A `Stock` describes a kind of spare part and how many of them are on hand,
+//! e.g. a 5-pack of brake pads. When an item is attached to a gear, a new `Part`
+//! is created from the stock and the quantity is reduced by one.
+//!
+//! Stock gets low if there are fewer items than the minimum quantity or than parts of that type
+//! which are due for service according to the service plans. Stock for a hook, e.g. front
+//! brake pads, only counts the plans for that hook.
+
+#![allow(clippy::too_many_arguments)]
+use std::collections::HashSet;
+
+use derive_more::{Display, From, Into};
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// Spare parts of one kind
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stock {
+    /// The primary key
+    pub id: StockId,
+    /// The owner
+    pub owner: UserId,
+    /// The type of the parts
+    pub what: PartTypeId,
+    /// The hook the parts are for, none for any hook
+    #[serde(default)]
+    pub hook: Option<PartTypeId>,
+    /// The name for new parts
+    pub name: String,
+    /// The vendor name
+    pub vendor: String,
+    /// The model name
+    pub model: String,
+    /// Number of items on hand
+    pub quantity: i32,
+    /// Warn if fewer items are on hand
+    pub min_quantity: i32,
+    /// The price of a single item
+    #[serde(default)]
+    pub price: Option<Money>,
+    /// Notes for new parts
+    pub notes: String,
+}
+
+#[derive(Clone, Copy, Debug, Display, From, Into, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StockId(i32);
+
+/// A stock which is running low
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StockWarning {
+    pub stock: Stock,
+    /// the number of parts of that type which are due for service
+    pub needed: usize,
+    /// the status of the service plans which are due
+    pub due: Vec<PlanStatus>,
+}
+
+fn check_quantities(quantity: i32, min_quantity: i32) -> TbResult<()> {
+    if quantity < 0 || min_quantity < 0 {
+        return Err(Error::BadRequest("quantities must not be negative".into()));
+    }
+    Ok(())
+}
+
+impl StockId {
+    /// get the stock, checks that the user owns it
+    pub async fn stock(self, user: &dyn Session, store: &mut impl Store) -> TbResult<Stock> {
+        let stock = store.stock_get(self).await?;
+        user.check_owner(
+            stock.owner,
+            format!("user {} cannot access stock {self}", user.user_id()),
+        )?;
+        Ok(stock)
+    }
+
+    pub async fn update(
+        self,
+        hook: Option<PartTypeId>,
+        name: String,
+        vendor: String,
+        model: String,
+        quantity: i32,
+        min_quantity: i32,
+        price: Option<Money>,
+        notes: String,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Stock> {
+        let stock = self.stock(user, store).await?;
+        if let Some(hook) = hook {
+            hook.visible(user.user_id())?;
+        }
+        check_quantities(quantity, min_quantity)?;
+        let price = price.map(Money::check).transpose()?;
+        let new = store
+            .stock_update(Stock {
+                hook,
+                name,
+                vendor,
+                model,
+                quantity,
+                min_quantity,
+                price,
+                notes,
+                ..stock.clone()
+            })
+            .await?;
+        new.audit(Some(&stock), Some(&new), user, store).await?;
+        Ok(new)
+    }
+
+    pub async fn delete(self, user: &dyn Session, store: &mut impl Store) -> TbResult<StockId> {
+        let stock = self.stock(user, store).await?;
+        store.stock_delete(self).await?;
+        stock.audit(Some(&stock), None, user, store).await?;
+        Ok(self)
+    }
+
+    /// Take one item from the stock and attach it to a gear
+    ///
+    /// A new part is created with the data of the stock and a purchase date of `time`.
+    /// Stock for a hook can only be attached there.
+    ///
+    /// # Returns
+    ///
+    /// The summary of the attachment including the new part
+    pub async fn attach(
+        self,
+        time: OffsetDateTime,
+        gear: PartId,
+        hook: PartTypeId,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Summary> {
+        let stock = self.stock(user, store).await?;
+        if stock.hook.is_some_and(|h| h != hook) {
+            return Err(Error::BadRequest(format!(
+                "{} is not for hook {hook}",
+                stock.name
+            )));
+        }
+        if stock.quantity < 1 {
+            return Err(Error::Conflict(format!("{} is out of stock", stock.name)));
+        }
+
+        let part = Part::create(
+            stock.name.clone(),
+            stock.vendor.clone(),
+            stock.model.clone(),
+            stock.what,
+            None,
+            time,
+            stock.notes.clone(),
+            stock.price.clone(),
+            user,
+            store,
+        )
+        .await?;
+        let new = store
+            .stock_update(Stock {
+                quantity: stock.quantity - 1,
+                ..stock.clone()
+            })
+            .await?;
+        new.audit(Some(&stock), Some(&new), user, store).await?;
+
+        let mut hash = SumHash::default();
+        hash += part.clone();
+        hash += attach_assembly(user, part.id, time, gear, hook, false, store).await?;
+        Ok(hash.into())
+    }
+}
+
+impl Stock {
+    pub async fn create(
+        what: PartTypeId,
+        hook: Option<PartTypeId>,
+        name: String,
+        vendor: String,
+        model: String,
+        quantity: i32,
+        min_quantity: i32,
+        price: Option<Money>,
+        notes: String,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Stock> {
+        what.visible(user.user_id())?;
+        if let Some(hook) = hook {
+            hook.visible(user.user_id())?;
+        }
+        check_quantities(quantity, min_quantity)?;
+        let price = price.map(Money::check).transpose()?;
+        let stock = store
+            .stock_create(
+                user.user_id(),
+                what,
+                hook,
+                name,
+                vendor,
+                model,
+                quantity,
+                min_quantity,
+                price,
+                notes,
+            )
+            .await?;
+        stock.audit(None, Some(&stock), user, store).await?;
+        Ok(stock)
+    }
+
+    /// does the stock replace parts due according to the plan
+    fn covers(&self, plan: &ServicePlan) -> bool {
+        plan.what == self.what && (self.hook.is_none() || self.hook == plan.hook)
+    }
+
+    /// record the change of the stock
+    async fn audit(
+        &self,
+        before: Option<&Stock>,
+        after: Option<&Stock>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        AuditEntry::record(user, "stock", self.id, None, before, after, store).await
+    }
+
+    /// all stock of the user
+    pub async fn for_user(user: &dyn Session, store: &mut impl Store) -> TbResult<Vec<Stock>> {
+        store.stocks_for_user(user.user_id()).await
+    }
+
+    /// is the stock too low for the given number of parts which need a replacement
+    fn is_low(&self, needed: usize) -> bool {
+        self.quantity < self.min_quantity || (self.quantity as usize) < needed
+    }
+
+    /// All stock of the user which is running low
+    ///
+    /// The service plans which are due soon or overdue for parts of the type of the stock
+    /// are considered. If the stock is for a hook, only the plans for that hook count.
+    pub async fn warnings(
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<Vec<StockWarning>> {
+        let stocks = Stock::for_user(user, store).await?;
+        if stocks.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut due = Vec::new();
+        for status in PlanStatus::for_user(user, store).await? {
+            if status.state != DueState::Ok {
+                let plan = status.plan.get(store).await?;
+                due.push((plan, status));
+            }
+        }
+
+        Ok(stocks
+            .into_iter()
+            .filter_map(|stock| {
+                let due: Vec<_> = due
+                    .iter()
+                    .filter(|(plan, _)| stock.covers(plan))
+                    .map(|(_, status)| status.clone())
+                    .collect();
+                let needed = due.iter().map(|s| s.part).collect::<HashSet<_>>().len();
+                stock
+                    .is_low(needed)
+                    .then_some(StockWarning { stock, needed, due })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_stock() {
+        let stock = Stock {
+            id: StockId(1),
+            owner: 1.into(),
+            what: 6.into(),
+            hook: None,
+            name: "brake pads".into(),
+            vendor: String::new(),
+            model: String::new(),
+            quantity: 2,
+            min_quantity: 1,
+            price: None,
+            notes: String::new(),
+        };
+        assert!(!stock.is_low(0));
+        assert!(!stock.is_low(2));
+        assert!(stock.is_low(3));
+        assert!(
+            Stock {
+                min_quantity: 3,
+                ..stock
+            }
+            .is_low(0)
+        );
+    }
+
+    #[test]
+    fn covered_plans() {
+        let stock = Stock {
+            id: StockId(1),
+            owner: 1.into(),
+            what: 6.into(),
+            hook: Some(3.into()),
+            name: "brake pads".into(),
+            vendor: String::new(),
+            model: String::new(),
+            quantity: 2,
+            min_quantity: 1,
+            price: None,
+            notes: String::new(),
+        };
+        let plan = ServicePlan {
+            id: ServicePlanId::default(),
+            part: None,
+            what: 6.into(),
+            hook: Some(3.into()),
+            name: "front pads".into(),
+            days: None,
+            hours: None,
+            km: Some(1000),
+            climb: None,
+            descend: None,
+            rides: None,
+            uid: Some(1.into()),
+            energy: None,
+        };
+        assert!(stock.covers(&plan));
+        let rear = ServicePlan {
+            hook: Some(4.into()),
+            ..plan.clone()
+        };
+        assert!(!stock.covers(&rear));
+        assert!(!stock.covers(&ServicePlan {
+            what: 7.into(),
+            ..plan
+        }));
+        // stock without a hook is for all of them
+        assert!(
+            Stock {
+                hook: None,
+                ..stock
+            }
+            .covers(&rear)
+        );
+    }
+}
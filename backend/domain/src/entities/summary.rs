@@ -25,6 +25,8 @@ pub struct Summary {
     pub usages: Vec<Usage>,
     pub services: Vec<Service>,
     pub plans: Vec<ServicePlan>,
+    /// the status of the plans, only part of the full summary of a user
+    pub statuses: Vec<PlanStatus>,
     pub shops: Vec<Shop>,
     pub users: Vec<UserPublic>,
 }
@@ -38,6 +40,7 @@ impl From<SumHash> for Summary {
             usages: value.uses.into_values().collect(),
             services: value.servs.into_values().collect(),
             plans: value.plans.into_values().collect(),
+            statuses: value.statuses.into_values().collect(),
             shops: value.shops.into_values().collect(),
             users: value.users.into_values().collect(),
         }
@@ -61,6 +64,7 @@ pub(crate) struct SumHash {
     uses: HashMap<UsageId, Usage>,
     servs: HashMap<ServiceId, Service>,
     plans: HashMap<ServicePlanId, ServicePlan>,
+    statuses: HashMap<(ServicePlanId, PartId), PlanStatus>,
     shops: HashMap<ShopId, Shop>,
     users: HashMap<UserId, UserPublic>,
 }
@@ -93,6 +97,9 @@ impl AddAssign<Summary> for SumHash {
         for x in rhs.plans {
             self.plans.insert(x.id, x);
         }
+        for x in rhs.statuses {
+            self.statuses.insert((x.plan, x.part), x);
+        }
         for x in rhs.shops {
             self.shops.insert(x.id, x);
         }
@@ -0,0 +1,178 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Incremental synchronization of the summary
+//!
+//! Activities, parts, attachments, usages, services and service plans carry the transaction
+//! which changed them last. A `SyncCursor` is taken before the summary is read. All changes
+//! before the cursor are part of the summary. Changes of transactions still running at that
+//! time are returned again with the next synchronization, so a client may get an entity
+//! twice but never misses one.
+//!
+//! Deleted entities are reported by their key. Usages are deleted together with their part,
+//! attachment or service and are not reported on their own.
+//! Shops, users and the plan statuses are always complete, since the statuses depend on the time.
+//! Computing the statuses reads every part, plan and service of the user on every synchronization.
+
+use derive_more::{Display, From, Into};
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// A point in the history of the changes
+#[derive(
+    Clone, Copy, Debug, Display, From, Into, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+pub struct SyncCursor(i64);
+
+/// An entity which was deleted
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum Deletion {
+    Activity {
+        id: ActivityId,
+    },
+    Part {
+        id: PartId,
+    },
+    Attachment {
+        part_id: PartId,
+        #[serde(with = "time::serde::rfc3339")]
+        attached: OffsetDateTime,
+    },
+    Service {
+        id: ServiceId,
+    },
+    Plan {
+        id: ServicePlanId,
+    },
+}
+
+/// The summary of a user with the deletions and the cursor for the next synchronization
+#[derive(Clone, Debug, Serialize)]
+pub struct SyncSummary {
+    #[serde(flatten)]
+    pub summary: Summary,
+    /// the entities deleted since the last synchronization
+    pub deleted: Vec<Deletion>,
+    pub cursor: SyncCursor,
+}
+
+impl UserId {
+    /// Get the summary of the user, or only the changes since `since`
+    ///
+    /// # Arguments
+    ///
+    /// * `since` - the cursor of the last synchronization, none for the full summary
+    /// * `shop` - the shop to get the parts for, only for the full summary
+    ///
+    /// # Returns
+    ///
+    /// The summary with the cursor for the next synchronization
+    pub async fn sync_summary(
+        self,
+        since: Option<SyncCursor>,
+        shop: Option<ShopId>,
+        store: &mut impl Store,
+    ) -> TbResult<SyncSummary> {
+        // changes made while reading are returned again with the next synchronization
+        let cursor = store.sync_cursor().await?;
+        let Some(since) = since else {
+            let summary = self.get_summary(shop, store).await?;
+            return Ok(SyncSummary {
+                summary,
+                deleted: Vec::new(),
+                cursor,
+            });
+        };
+        if shop.is_some() {
+            return Err(Error::BadRequest(
+                "the summary of a shop cannot be synchronized".into(),
+            ));
+        }
+
+        let activities = store.activities_changed(self, since).await?;
+        let parts = store.parts_changed(self, since).await?;
+        let mut attachments = Vec::new();
+        for att in store.attachments_changed(self, since).await? {
+            attachments.push(att.read_details(store).await?);
+        }
+        let usages = store.usages_changed(self, since).await?;
+        let services = store.services_changed(self, since).await?;
+        let plans = store.serviceplans_changed(self, since).await?;
+
+        let mut all_plans = ServicePlan::for_user(&self, store).await?;
+        for part in Part::get_all(&self, store).await? {
+            all_plans.append(&mut ServicePlan::for_part(part.id, store).await?);
+        }
+        let statuses = PlanStatus::for_plans(&all_plans, store).await?;
+        let shops = Shop::get_all_for_user(&self, store).await?;
+        let users = Shop::get_users(&shops, &self, store).await?;
+        let deleted = store.deletions_since(self, since).await?;
+        debug!(
+            "sync of user {self} since {since}: {} activities, {} parts, {} deletions",
+            activities.len(),
+            parts.len(),
+            deleted.len()
+        );
+
+        Ok(SyncSummary {
+            summary: Summary {
+                activities,
+                parts,
+                attachments,
+                usages,
+                services,
+                plans,
+                statuses,
+                shops,
+                users,
+            },
+            deleted,
+            cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn serialize_deletions() {
+        let deleted = vec![
+            Deletion::Part { id: 3.into() },
+            Deletion::Attachment {
+                part_id: 3.into(),
+                attached: datetime!(2024-01-02 0:00 UTC),
+            },
+        ];
+        assert_eq!(
+            serde_json::to_value(&deleted).unwrap(),
+            serde_json::json!([
+                {"entity": "part", "id": 3},
+                {"entity": "attachment", "part_id": 3, "attached": "2024-01-02T00:00:00Z"}
+            ])
+        );
+    }
+}
@@ -23,14 +23,51 @@
 //! It includes the types for parts and activities, as well as their relationships.
 //!
 //! The types defined in this module are used throughout the application to ensure type safety and consistency.
+//!
+//! The catalog of types is stored in the database and maintained by the admins.
+//! Since the types are needed all over the place, a copy is cached in memory.
+//! It needs to be loaded with `reload_types` at startup and after every change.
+
+use std::collections::BTreeMap;
+use std::sync::RwLock;
 
 use derive_more::{Display, From, Into};
 use serde_derive::{Deserialize, Serialize};
 
 use crate::*;
 
-mod objects;
-use objects::{ACTTYPES, PARTTYPES};
+/// The cached copy of the type catalog
+struct Catalog {
+    parts: BTreeMap<PartTypeId, PartType>,
+    acts: BTreeMap<ActTypeId, ActivityType>,
+}
+
+static CATALOG: RwLock<Catalog> = RwLock::new(Catalog {
+    parts: BTreeMap::new(),
+    acts: BTreeMap::new(),
+});
+
+fn catalog() -> std::sync::RwLockReadGuard<'static, Catalog> {
+    CATALOG.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Load the type catalog from the store into the cache
+pub async fn reload_types(store: &mut impl TypeStore) -> TbResult<()> {
+    let parts = store
+        .parttypes_get_all()
+        .await?
+        .into_iter()
+        .map(|t| (t.id, t))
+        .collect();
+    let acts = store
+        .acttypes_get_all()
+        .await?
+        .into_iter()
+        .map(|t| (t.id, t))
+        .collect();
+    *CATALOG.write().unwrap_or_else(|e| e.into_inner()) = Catalog { parts, acts };
+    Ok(())
+}
 
 #[derive(
     Clone,
@@ -68,11 +105,162 @@ pub struct PartType {
     pub order: i32,
     /// Potential group
     pub group: Option<String>,
+    /// The user for private types, None for the global catalog
+    #[serde(default)]
+    pub owner: Option<UserId>,
 }
 
 impl PartType {
+    /// all part types of the global catalog
     pub fn all_ordered() -> Vec<Self> {
-        PARTTYPES.values().cloned().collect()
+        catalog()
+            .parts
+            .values()
+            .filter(|t| t.owner.is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// all part types visible for a user
+    ///
+    /// These are the global types plus the private types of the user
+    pub fn for_user(user: UserId) -> Vec<Self> {
+        catalog()
+            .parts
+            .values()
+            .filter(|t| t.visible(user))
+            .cloned()
+            .collect()
+    }
+
+    fn visible(&self, user: UserId) -> bool {
+        self.owner.is_none() || self.owner == Some(user)
+    }
+
+    /// Check that the type fits into the catalog
+    ///
+    /// `pending` are types created in the same transaction, which are not cached yet
+    fn check(&self, pending: &[PartType]) -> TbResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(Error::BadRequest("part type needs a name".into()));
+        }
+        if self.hooks.is_empty() {
+            if self.owner.is_some() {
+                return Err(Error::BadRequest(
+                    "private part types need to hook into another type".into(),
+                ));
+            }
+            return Ok(());
+        }
+        let main = self.main.get()?;
+        if !main.hooks.is_empty() || main.owner.is_some() {
+            return Err(Error::BadRequest(format!(
+                "part type {} is no main type",
+                self.main
+            )));
+        }
+        for hook in &self.hooks {
+            if *hook == self.id {
+                return Err(Error::BadRequest(
+                    "part type cannot hook into itself".into(),
+                ));
+            }
+            let hook = match pending.iter().find(|t| t.id == *hook) {
+                Some(t) => t.clone(),
+                None => hook.get()?,
+            };
+            if hook.main != self.main || !(hook.owner.is_none() || hook.owner == self.owner) {
+                return Err(Error::BadRequest(format!(
+                    "hook {} does not belong to main type {}",
+                    hook.id, self.main
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a new part type
+    ///
+    /// The id is assigned by the store.
+    /// Types created by a user are private to that user, without a user they are part of the global catalog.
+    /// A global type without hooks is a main type and becomes its own main type.
+    ///
+    /// The cache needs to be reloaded after the change is committed.
+    pub async fn create(
+        self,
+        user: Option<&dyn Session>,
+        store: &mut impl Store,
+    ) -> TbResult<PartType> {
+        self.create_after(&[], user, store).await
+    }
+
+    /// Create a new part type which may hook into the `pending` types
+    ///
+    /// The pending types were created in the same transaction and are not cached yet.
+    pub(crate) async fn create_after(
+        self,
+        pending: &[PartType],
+        user: Option<&dyn Session>,
+        store: &mut impl Store,
+    ) -> TbResult<PartType> {
+        let owner = user.map(|u| u.user_id());
+        let new = PartType { owner, ..self };
+        new.check(pending)?;
+        info!("Creating part type {} for {:?}", new.name, owner);
+        let new = store.parttype_create(new).await?;
+        if new.hooks.is_empty() && new.main != new.id {
+            let main = new.id;
+            return store.parttype_update(PartType { main, ..new }).await;
+        }
+        new.audit(None, Some(&new), user, store).await?;
+        Ok(new)
+    }
+
+    /// Change an existing part type
+    ///
+    /// Private types can only be changed by their owner, global types only without a user.
+    /// The cache needs to be reloaded after the change is committed.
+    pub async fn update(
+        self,
+        user: Option<&dyn Session>,
+        store: &mut impl Store,
+    ) -> TbResult<PartType> {
+        let owner = user.map(|u| u.user_id());
+        let old = self.id.get_owned(owner)?;
+        let new = PartType { owner, ..self };
+        new.check(&[])?;
+        if old.hooks.is_empty() != new.hooks.is_empty() {
+            return Err(Error::BadRequest(
+                "cannot change between main type and spare type".into(),
+            ));
+        }
+        let main = if new.hooks.is_empty() {
+            new.id
+        } else {
+            new.main
+        };
+        info!("Updating part type {}", new.id);
+        let new = store.parttype_update(PartType { main, ..new }).await?;
+        new.audit(Some(&old), Some(&new), user, store).await?;
+        Ok(new)
+    }
+
+    /// record the change of a private type
+    ///
+    /// The global catalog is maintained by the admins and not recorded
+    async fn audit(
+        &self,
+        before: Option<&PartType>,
+        after: Option<&PartType>,
+        user: Option<&dyn Session>,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        match user {
+            Some(user) => {
+                AuditEntry::record(user, "part_type", self.id, None, before, after, store).await
+            }
+            None => Ok(()),
+        }
     }
 }
 
@@ -109,7 +297,8 @@ pub struct ActivityType {
 impl PartTypeId {
     /// get the full type for a type_id
     pub(crate) fn get(self) -> TbResult<PartType> {
-        PARTTYPES
+        catalog()
+            .parts
             .get(&self)
             .cloned()
             .ok_or(crate::Error::NotFound(format!(
@@ -117,6 +306,24 @@ impl PartTypeId {
             )))
     }
 
+    /// get the full type for a type_id if the user may use it
+    pub(crate) fn visible(self, user: UserId) -> TbResult<PartType> {
+        let t = self.get()?;
+        if !t.visible(user) {
+            return Err(Error::NotFound(format!("parttype {self} does not exist")));
+        }
+        Ok(t)
+    }
+
+    /// get the full type for a type_id with the given owner
+    fn get_owned(self, owner: Option<UserId>) -> TbResult<PartType> {
+        let t = self.get()?;
+        if t.owner != owner {
+            return Err(Error::NotFound(format!("parttype {self} does not exist")));
+        }
+        Ok(t)
+    }
+
     pub(crate) fn is_main(self) -> TbResult<bool> {
         let t = self.get()?;
         Ok(t.hooks.is_empty())
@@ -145,8 +352,10 @@ impl PartTypeId {
     }
 
     /// get all the type_ids you can attach - even indirectly - to this type_id
-    pub(crate) fn subtypes(self) -> Vec<PartTypeId> {
-        let mut types = PartType::all_ordered();
+    ///
+    /// includes the private types of user
+    pub(crate) fn subtypes(self, user: UserId) -> Vec<PartTypeId> {
+        let mut types = PartType::for_user(user);
         self.filter_types(&mut types)
             .into_iter()
             .map(|t| t.id)
@@ -155,16 +364,128 @@ impl PartTypeId {
 
     /// Get the activity types valid for this part_type
     pub(crate) fn act_types(&self) -> Vec<ActTypeId> {
-        ACTTYPES
+        catalog()
+            .acts
             .values()
             .filter(|a| a.gear_type == *self)
             .map(|a| a.id)
             .collect()
     }
+
+    /// Delete a part type
+    ///
+    /// Private types can only be deleted by their owner, global types only without a user.
+    /// Types still in use by parts or other types cannot be deleted.
+    /// The cache needs to be reloaded after the change is committed.
+    pub async fn delete(
+        self,
+        user: Option<&dyn Session>,
+        store: &mut impl Store,
+    ) -> TbResult<PartTypeId> {
+        let old = self.get_owned(user.map(|u| u.user_id()))?;
+        if catalog()
+            .parts
+            .values()
+            .any(|t| t.id != self && (t.main == self || t.hooks.contains(&self)))
+        {
+            return Err(Error::Conflict(format!(
+                "part type {self} is used by other part types"
+            )));
+        }
+        if ActivityType::all_ordered()
+            .iter()
+            .any(|a| a.gear_type == self)
+        {
+            return Err(Error::Conflict(format!(
+                "part type {self} is used by activity types"
+            )));
+        }
+        if store.parttype_in_use(self).await? {
+            return Err(Error::Conflict(format!("part type {self} is still in use")));
+        }
+        info!("Deleting part type {self}");
+        store.parttype_delete(self).await?;
+        old.audit(Some(&old), None, user, store).await?;
+        Ok(self)
+    }
+
+    /// Delete a private type of the user which was just created, e.g. by a failed import
+    ///
+    /// Unlike `delete` the other types in the cache are not checked, since types created
+    /// together hook into each other. The cache needs to be reloaded after the change is committed.
+    pub(crate) async fn discard(self, user: &dyn Session, store: &mut impl Store) -> TbResult<()> {
+        let old = self.get_owned(Some(user.user_id()))?;
+        info!("Discarding part type {self}");
+        store.parttype_delete(self).await?;
+        old.audit(Some(&old), None, Some(user), store).await
+    }
 }
 
 impl ActivityType {
     pub fn all_ordered() -> Vec<ActivityType> {
-        ACTTYPES.values().cloned().collect()
+        catalog().acts.values().cloned().collect()
+    }
+
+    /// Check that the type fits into the catalog
+    fn check(&self) -> TbResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(Error::BadRequest("activity type needs a name".into()));
+        }
+        if !self.gear_type.is_main()? {
+            return Err(Error::BadRequest(format!(
+                "part type {} is no main type",
+                self.gear_type
+            )));
+        }
+        Ok(())
+    }
+
+    /// Create a new activity type
+    ///
+    /// The id is assigned by the store.
+    /// The cache needs to be reloaded after the change is committed.
+    pub async fn create(self, store: &mut impl TypeStore) -> TbResult<ActivityType> {
+        self.check()?;
+        info!("Creating activity type {}", self.name);
+        store.acttype_create(self).await
+    }
+
+    /// Change an existing activity type
+    ///
+    /// The cache needs to be reloaded after the change is committed.
+    pub async fn update(self, store: &mut impl TypeStore) -> TbResult<ActivityType> {
+        self.id.get()?;
+        self.check()?;
+        info!("Updating activity type {}", self.id);
+        store.acttype_update(self).await
+    }
+}
+
+impl ActTypeId {
+    /// get the full type for a type_id
+    pub(crate) fn get(self) -> TbResult<ActivityType> {
+        catalog()
+            .acts
+            .get(&self)
+            .cloned()
+            .ok_or(crate::Error::NotFound(format!(
+                "activity type {self} does not exist"
+            )))
+    }
+
+    /// Delete an activity type
+    ///
+    /// Types still used by activities cannot be deleted.
+    /// The cache needs to be reloaded after the change is committed.
+    pub async fn delete(self, store: &mut impl TypeStore) -> TbResult<ActTypeId> {
+        self.get()?;
+        if store.acttype_in_use(self).await? {
+            return Err(Error::Conflict(format!(
+                "activity type {self} is still in use"
+            )));
+        }
+        info!("Deleting activity type {self}");
+        store.acttype_delete(self).await?;
+        Ok(self)
     }
 }
@@ -0,0 +1,323 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Undo the last operation on attachments
+//!
+//! Attaching, detaching, disposing and recovering parts record the changes of the request
+//! from the audit log as the `Undo` of the user. Only the last operation can be undone.
+//!
+//! Undoing deletes the attachments the operation created and recreates the ones it deleted,
+//! which recalculates all usages. Disposal and ownership of the parts are reset as well.
+//! If anything was changed since, or a recreated attachment would overlap a current one,
+//! the undo is refused. Any other change of an attachment drops the undo of the user.
+
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// A change of an attachment or a part
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum Change {
+    Attachment {
+        before: Option<Attachment>,
+        after: Option<Attachment>,
+    },
+    Part {
+        before: Box<Part>,
+        after: Box<Part>,
+    },
+}
+
+/// The changes of the last operation of a user
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Undo {
+    /// The user who made the operation
+    pub owner: UserId,
+    /// The operation, e.g. "attach"
+    pub operation: String,
+    /// when the operation was made
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+    /// The changes in the order they were made
+    pub changes: Vec<Change>,
+}
+
+impl Change {
+    /// the change recorded in the entry if it is about an attachment or a part
+    fn from_entry(entry: AuditEntry) -> TbResult<Option<Change>> {
+        fn parse<T: serde::de::DeserializeOwned>(
+            value: Option<serde_json::Value>,
+        ) -> TbResult<Option<T>> {
+            value
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(|e| Error::AnyFailure(e.into()))
+        }
+        Ok(match entry.entity.as_str() {
+            "attachment" => Some(Change::Attachment {
+                before: parse(entry.before)?,
+                after: parse(entry.after)?,
+            }),
+            "part" => match (parse(entry.before)?, parse(entry.after)?) {
+                (Some(before), Some(after)) => Some(Change::Part {
+                    before: Box::new(before),
+                    after: Box::new(after),
+                }),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+}
+
+/// The net effect of the changes
+///
+/// returns the attachments to remove, the attachments to restore
+/// and the parts with their first and last state
+fn net_effect(changes: &[Change]) -> (Vec<Attachment>, Vec<Attachment>, Vec<(Part, Part)>) {
+    // the first and the last state of every attachment
+    let mut atts: Vec<(Option<Attachment>, Option<Attachment>)> = Vec::new();
+    let mut parts: Vec<(Part, Part)> = Vec::new();
+    for change in changes {
+        match change {
+            Change::Attachment { before, after } => {
+                let Some(att) = before.or(*after) else {
+                    continue;
+                };
+                let same = |a: &Attachment| a.part_id == att.part_id && a.attached == att.attached;
+                match atts
+                    .iter_mut()
+                    .find(|(first, last)| first.or(*last).is_some_and(|a| same(&a)))
+                {
+                    Some((_, last)) => *last = *after,
+                    None => atts.push((*before, *after)),
+                }
+            }
+            Change::Part { before, after } => {
+                match parts.iter_mut().find(|(p, _)| p.id == before.id) {
+                    Some((_, last)) => *last = (**after).clone(),
+                    None => parts.push(((**before).clone(), (**after).clone())),
+                }
+            }
+        }
+    }
+    atts.retain(|(first, last)| first != last);
+    let remove = atts.iter().filter_map(|(_, last)| *last).collect();
+    let restore = atts.iter().filter_map(|(first, _)| *first).collect();
+    (remove, restore, parts)
+}
+
+/// does the current attachment `other` of a part of type `other_what` stand in the way
+/// of restoring `att` for a part of type `what`
+///
+/// Both are in the way of each other if they overlap in time and are for the same part
+/// or the same position, i.e. gear, hook and part type.
+/// Attachments which get removed by the undo are not in the way.
+fn in_the_way(
+    att: &Attachment,
+    what: PartTypeId,
+    other: &Attachment,
+    other_what: PartTypeId,
+    remove: &[Attachment],
+) -> bool {
+    let same = |a: &Attachment| a.part_id == other.part_id && a.attached == other.attached;
+    let overlaps = other.attached < att.detached && att.attached < other.detached;
+    let position = other.gear == att.gear && other.hook == att.hook && other_what == what;
+    overlaps && (other.part_id == att.part_id || position) && !remove.iter().any(same)
+}
+
+/// the fields of a part an operation on attachments changes
+fn part_state(part: &Part) -> (Option<OffsetDateTime>, UserId, Option<ShopId>) {
+    (part.disposed_at, part.owner, part.shop)
+}
+
+impl Undo {
+    /// Record the changes to attachments and parts made in the current transaction
+    /// as the undo of the user
+    ///
+    /// Nothing is recorded if there are no such changes
+    pub async fn record(
+        operation: &str,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        let mut changes = Vec::new();
+        for entry in store.audit_for_transaction().await? {
+            changes.extend(Change::from_entry(entry)?);
+        }
+        if changes.is_empty() {
+            return Ok(());
+        }
+        store
+            .undo_set(Undo {
+                owner: user.user_id(),
+                operation: operation.to_string(),
+                time: OffsetDateTime::now_utc(),
+                changes,
+            })
+            .await
+    }
+
+    /// The operation which would be undone
+    pub async fn get(user: &dyn Session, store: &mut impl Store) -> TbResult<Undo> {
+        store
+            .undo_get(user.user_id())
+            .await?
+            .ok_or(Error::NotFound("there is nothing to undo".into()))
+    }
+
+    /// Undo the last operation of the user
+    ///
+    /// # Returns
+    ///
+    /// The summary of all restored attachments, usages and parts
+    pub async fn undo(user: &dyn Session, store: &mut impl Store) -> TbResult<Summary> {
+        let undo = Undo::get(user, store).await?;
+        info!("undoing {} of user {}", undo.operation, undo.owner);
+        let (remove, restore, parts) = net_effect(&undo.changes);
+
+        let changed = || {
+            Error::Conflict(format!(
+                "cannot undo {}, the parts were changed since",
+                undo.operation
+            ))
+        };
+        for att in &remove {
+            att.part_id.checkuser(user, store).await?;
+            let current = store
+                .attachment_get_by_part_and_time(att.part_id, att.attached)
+                .await?;
+            if current.as_ref() != Some(att) {
+                return Err(changed());
+            }
+        }
+        for (_, last) in &parts {
+            let current = last.id.part(user, store).await?;
+            if part_state(&current) != part_state(last) {
+                return Err(changed());
+            }
+        }
+        for att in &restore {
+            let what = att.part_id.part(user, store).await?.what;
+            let mut current = store.attachments_all_by_part(att.part_id).await?;
+            current.append(&mut store.attachments_all_by_gear(att.gear).await?);
+            for other in current {
+                let other_what = other.part_id.read(store).await?.what;
+                if in_the_way(att, what, &other, other_what, &remove) {
+                    return Err(changed());
+                }
+            }
+        }
+
+        let mut hash = SumHash::default();
+        for att in remove {
+            hash += att.delete(user, store).await?;
+        }
+        for att in restore {
+            hash += att.create(user, store).await?;
+        }
+        for (first, _) in parts {
+            let current = first.id.read(store).await?;
+            if part_state(&current) != part_state(&first) {
+                let part = Part {
+                    disposed_at: first.disposed_at,
+                    owner: first.owner,
+                    shop: first.shop,
+                    ..current.clone()
+                };
+                hash += current.audit(part, user, store).await?;
+            }
+        }
+        store.undo_delete(undo.owner).await?;
+        Ok(hash.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn net_changes() {
+        let jan = datetime!(2024-01-01 0:00 UTC);
+        let feb = datetime!(2024-02-01 0:00 UTC);
+        let mar = datetime!(2024-03-01 0:00 UTC);
+        let change = |before, after| Change::Attachment { before, after };
+        let changes = vec![
+            // detach part 2 in march
+            change(Some(att(2, jan, MAX_TIME)), None),
+            change(None, Some(att(2, jan, mar))),
+            // attach part 3 in february, merged with a later attachment
+            change(None, Some(att(3, feb, mar))),
+            change(Some(att(3, feb, mar)), None),
+            change(Some(att(3, mar, MAX_TIME)), None),
+            change(None, Some(att(3, feb, MAX_TIME))),
+        ];
+        let (remove, restore, parts) = net_effect(&changes);
+        assert_eq!(remove, vec![att(2, jan, mar), att(3, feb, MAX_TIME)]);
+        assert_eq!(restore, vec![att(2, jan, MAX_TIME), att(3, mar, MAX_TIME)]);
+        assert!(parts.is_empty());
+
+        // an attachment which was created and deleted again is not touched
+        let (remove, restore, _) = net_effect(&changes[2..4]);
+        assert!(remove.is_empty() && restore.is_empty());
+    }
+
+    #[test]
+    fn restore_in_the_way() {
+        let jan = datetime!(2024-01-01 0:00 UTC);
+        let feb = datetime!(2024-02-01 0:00 UTC);
+        let mar = datetime!(2024-03-01 0:00 UTC);
+        let (tire, chain) = (PartTypeId::from(3), PartTypeId::from(4));
+        let restore = att(2, jan, MAX_TIME);
+
+        // another part of the same type on the same hook
+        assert!(in_the_way(
+            &restore,
+            tire,
+            &att(3, feb, MAX_TIME),
+            tire,
+            &[]
+        ));
+        // unless the undo removes it
+        let other = att(3, feb, MAX_TIME);
+        assert!(!in_the_way(&restore, tire, &other, tire, &[other]));
+        // a different part type can share the hook
+        assert!(!in_the_way(
+            &restore,
+            tire,
+            &att(3, feb, MAX_TIME),
+            chain,
+            &[]
+        ));
+        // the part itself attached elsewhere
+        let elsewhere = Attachment {
+            gear: 5.into(),
+            ..att(2, feb, mar)
+        };
+        assert!(in_the_way(&restore, tire, &elsewhere, tire, &[]));
+        // no overlap
+        assert!(!in_the_way(&att(2, jan, feb), tire, &elsewhere, tire, &[]));
+    }
+}
@@ -76,17 +76,6 @@ impl Usage {
     pub(crate) async fn delete_all(store: &mut impl UsageStore) -> TbResult<usize> {
         store.delete_all().await
     }
-
-    pub(crate) async fn get_vec(
-        vec: &[UsageId],
-        store: &mut impl UsageStore,
-    ) -> TbResult<Vec<Usage>> {
-        let mut res = Vec::new();
-        for id in vec {
-            res.push(id.read(store).await?);
-        }
-        Ok(res)
-    }
 }
 
 impl UsageId {
@@ -237,9 +226,22 @@ mod tests {
         async fn usages_delete(&mut self, _: &[Usage]) -> TbResult<usize> {
             todo!()
         }
+
+        async fn usages_orphaned(&mut self) -> TbResult<Vec<UsageId>> {
+            todo!()
+        }
+
+        async fn usages_changed(
+            &mut self,
+            _: crate::UserId,
+            _: crate::SyncCursor,
+        ) -> TbResult<Vec<Usage>> {
+            todo!()
+        }
     }
 
     #[tokio::test]
+    #[allow(clippy::needless_borrow)]
     async fn create_usage_returns() -> TbResult<()> {
         let mut store = MemStore(HashMap::new());
         let store = &mut store;
@@ -0,0 +1,250 @@
+/*
+   tendabike - the bike maintenance tracker
+
+   Copyright (C) 2023  Christoph Rohland
+
+   This program is free software: you can redistribute it and/or modify
+   it under the terms of the GNU Affero General Public License as published
+   by the Free Software Foundation, either version 3 of the License, or
+   (at your option) any later version.
+
+   This program is distributed in the hope that it will be useful,
+   but WITHOUT ANY WARRANTY; without even the implied warranty of
+   MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+   GNU Affero General Public License for more details.
+
+   You should have received a copy of the GNU Affero General Public License
+   along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+*/
+
+//! Rules how activities are accounted to parts
+//!
+//! By default every activity counts fully for all parts of the gear.
+//! A `UsageRule` changes that for an activity type and a hook, e.g. a virtual ride
+//! should not wear out the rear tire of the trainer bike. All parts attached to the hook
+//! get the usage multiplied by the factor. A factor of 0 means the activity does not count at all.
+//! The gear itself is matched by its own type.
+//!
+//! Creating, changing or deleting a rule recalculates the usage of all parts of the owner,
+//! so activities are always unregistered with the factor they were registered with.
+
+use derive_more::{Display, From, Into};
+use serde_derive::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::*;
+
+/// Do not allow factors bigger than that
+const MAX_FACTOR: f64 = 100.0;
+
+/// How an activity type counts for the parts on a hook
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UsageRule {
+    /// The primary key
+    pub id: UsageRuleId,
+    /// The owner
+    pub owner: UserId,
+    /// The activity type
+    pub what: ActTypeId,
+    /// The hook the parts are attached to
+    pub hook: PartTypeId,
+    /// The usage of the activity is multiplied by this factor
+    pub factor: f64,
+}
+
+#[derive(Clone, Copy, Debug, Display, From, Into, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRuleId(i32);
+
+fn check_factor(factor: f64) -> TbResult<f64> {
+    if !(0.0..=MAX_FACTOR).contains(&factor) {
+        return Err(Error::BadRequest(format!(
+            "factor must be between 0 and {MAX_FACTOR}"
+        )));
+    }
+    Ok(factor)
+}
+
+/// the factor for parts on `hook` according to the rules
+fn factor(rules: &[UsageRule], what: ActTypeId, hook: PartTypeId) -> f64 {
+    rules
+        .iter()
+        .find(|r| r.what == what && r.hook == hook)
+        .map_or(1.0, |r| r.factor)
+}
+
+impl UsageRuleId {
+    /// get the rule, checks that the user owns it
+    pub async fn rule(self, user: &dyn Session, store: &mut impl Store) -> TbResult<UsageRule> {
+        let rule = store.usage_rule_get(self).await?;
+        user.check_owner(
+            rule.owner,
+            format!("user {} cannot access usage rule {self}", user.user_id()),
+        )?;
+        Ok(rule)
+    }
+
+    pub async fn update(
+        self,
+        factor: f64,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<UsageRule> {
+        let rule = self.rule(user, store).await?;
+        let factor = check_factor(factor)?;
+        let new = store
+            .usage_rule_update(UsageRule {
+                factor,
+                ..rule.clone()
+            })
+            .await?;
+        new.audit(Some(&rule), Some(&new), user, store).await?;
+        Activity::rescan_user(new.owner, store).await?;
+        Ok(new)
+    }
+
+    pub async fn delete(self, user: &dyn Session, store: &mut impl Store) -> TbResult<UsageRuleId> {
+        let rule = self.rule(user, store).await?;
+        store.usage_rule_delete(self).await?;
+        rule.audit(Some(&rule), None, user, store).await?;
+        Activity::rescan_user(rule.owner, store).await?;
+        Ok(self)
+    }
+}
+
+impl UsageRule {
+    /// create a rule for the activity type and the hook
+    ///
+    /// There can only be one rule per activity type and hook
+    pub async fn create(
+        what: ActTypeId,
+        hook: PartTypeId,
+        factor: f64,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<UsageRule> {
+        what.get()?;
+        hook.visible(user.user_id())?;
+        let factor = check_factor(factor)?;
+        let owner = user.user_id();
+        if store
+            .usage_rules_for_user(owner)
+            .await?
+            .iter()
+            .any(|r| r.what == what && r.hook == hook)
+        {
+            return Err(Error::Conflict(format!(
+                "there is already a rule for activity type {what} and hook {hook}"
+            )));
+        }
+        let rule = store.usage_rule_create(owner, what, hook, factor).await?;
+        rule.audit(None, Some(&rule), user, store).await?;
+        Activity::rescan_user(owner, store).await?;
+        Ok(rule)
+    }
+
+    /// record the change of the rule
+    async fn audit(
+        &self,
+        before: Option<&UsageRule>,
+        after: Option<&UsageRule>,
+        user: &dyn Session,
+        store: &mut impl Store,
+    ) -> TbResult<()> {
+        AuditEntry::record(user, "usage_rule", self.id, None, before, after, store).await
+    }
+
+    /// all rules of the user
+    pub async fn for_user(user: &dyn Session, store: &mut impl Store) -> TbResult<Vec<UsageRule>> {
+        store.usage_rules_for_user(user.user_id()).await
+    }
+
+    /// all rules of the owner of the part
+    pub(crate) async fn for_part(part: PartId, store: &mut impl Store) -> TbResult<Vec<UsageRule>> {
+        let owner = part.read(store).await?.owner;
+        store.usage_rules_for_user(owner).await
+    }
+
+    /// does an activity of type `what` count at all for the parts attached to `hook`
+    pub(crate) fn counts(rules: &[UsageRule], what: ActTypeId, hook: PartTypeId) -> bool {
+        factor(rules, what, hook) != 0.0
+    }
+
+    /// the usage of an activity of type `what` for the parts attached to `hook`
+    ///
+    /// The count is kept unless the activity does not count at all
+    pub(crate) fn apply(
+        rules: &[UsageRule],
+        what: ActTypeId,
+        hook: PartTypeId,
+        usage: &Usage,
+    ) -> Usage {
+        let factor = factor(rules, what, hook);
+        if factor == 1.0 {
+            return usage.clone();
+        }
+        let weight = |v: i32| (v as f64 * factor).round() as i32;
+        Usage {
+            id: usage.id,
+            time: weight(usage.time),
+            distance: weight(usage.distance),
+            climb: weight(usage.climb),
+            descend: weight(usage.descend),
+            energy: weight(usage.energy),
+            count: if factor == 0.0 { 0 } else { usage.count },
+        }
+    }
+
+    /// the start and the usage of the activities for the parts attached to `hook`
+    pub(crate) fn weigh(
+        rules: &[UsageRule],
+        hook: PartTypeId,
+        acts: Vec<Activity>,
+    ) -> Vec<(OffsetDateTime, Usage)> {
+        acts.into_iter()
+            .map(|act| {
+                let usage = UsageRule::apply(rules, act.what, hook, &act.usage());
+                (act.start, usage)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_rules() {
+        let rule = |hook: i32, factor| UsageRule {
+            id: UsageRuleId(1),
+            owner: 1.into(),
+            what: 5.into(),
+            hook: hook.into(),
+            factor,
+        };
+        let rules = vec![rule(3, 0.0), rule(4, 0.5)];
+        let usage = Usage {
+            time: 3600,
+            distance: 30_001,
+            climb: 101,
+            descend: 101,
+            energy: 500,
+            count: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(UsageRule::apply(&rules, 1.into(), 3.into(), &usage), usage);
+        assert_eq!(UsageRule::apply(&rules, 5.into(), 1.into(), &usage), usage);
+        assert_eq!(
+            UsageRule::apply(&rules, 5.into(), 3.into(), &usage),
+            Usage::default()
+        );
+        let half = UsageRule::apply(&rules, 5.into(), 4.into(), &usage);
+        assert_eq!(half.distance, 15_001);
+        assert_eq!(half.climb, 51);
+        assert_eq!(half.count, 1);
+        // unregistering must undo registering
+        assert_eq!(UsageRule::apply(&rules, 5.into(), 4.into(), &-usage), -half);
+    }
+}
@@ -255,12 +255,14 @@ impl UserId {
             services.append(&mut servs);
             plans.append(&mut splans)
         }
+        let statuses = PlanStatus::for_plans(&plans, store).await?;
         Ok(Summary {
             parts,
             usages,
             attachments,
             services,
             plans,
+            statuses,
             ..Default::default()
         })
     }
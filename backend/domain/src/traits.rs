@@ -22,6 +22,36 @@ pub use service::*;
 mod serviceplan;
 pub use serviceplan::*;
 
+mod source;
+pub use source::*;
+
+mod types;
+pub use types::*;
+
+mod notification;
+pub use notification::*;
+
+mod stock;
+pub use stock::*;
+
+mod usagerule;
+pub use usagerule::*;
+
+mod gearrule;
+pub use gearrule::*;
+
+mod configuration;
+pub use configuration::*;
+
+mod audit;
+pub use audit::*;
+
+mod undo;
+pub use undo::*;
+
+mod sync;
+pub use sync::*;
+
 use crate::{ShopId, TbResult, UserId};
 
 #[async_trait::async_trait]
@@ -36,6 +66,15 @@ pub trait Store:
     + UsageStore
     + ServiceStore
     + ServicePlanStore
+    + TypeStore
+    + NotificationStore
+    + StockStore
+    + UsageRuleStore
+    + GearRuleStore
+    + ConfigurationStore
+    + AuditStore
+    + UndoStore
+    + SyncStore
 {
     async fn commit(self) -> TbResult<()>;
 }
@@ -46,6 +85,10 @@ pub trait Session: Send + Sync {
     fn shop(&self) -> Option<ShopId>;
     fn set_shop(&mut self, shop: Option<ShopId>) -> TbResult<()>;
     fn is_admin(&self) -> bool;
+    /// The user who actually acts, differs from `user_id` if an admin acts for a user
+    fn actor(&self) -> UserId {
+        self.user_id()
+    }
     fn check_owner(&self, owner: UserId, error: String) -> crate::TbResult<()> {
         self.user_id().check_owner(owner, error)
     }
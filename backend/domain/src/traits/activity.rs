@@ -1,6 +1,6 @@
 use time::OffsetDateTime;
 
-use crate::{ActTypeId, Activity, ActivityId, PartId, TbResult, UserId};
+use crate::{ActTypeId, Activity, ActivityId, ActivityQuery, PartId, SyncCursor, TbResult, UserId};
 
 // A trait for storing and retrieving activities.
 /// A trait defining the methods for storing and retrieving activities.
@@ -106,6 +106,29 @@ pub trait ActivityStore {
         rstart: OffsetDateTime,
     ) -> TbResult<Activity>;
 
+    /// Retrieves the activity of a user starting in the same minute as the given time.
+    ///
+    /// # Arguments
+    ///
+    /// * `uid` - The ID of the user to retrieve the activity for.
+    /// * `start` - The start time of the activity.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the `Activity` if there is one or an error if the operation fails.
+    async fn activity_get_by_user_and_start(
+        &mut self,
+        uid: UserId,
+        start: OffsetDateTime,
+    ) -> TbResult<Option<Activity>>;
+
+    /// Allocates a new id for an activity which does not come from an external source.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the new `ActivityId` or an error if the operation fails.
+    async fn activity_new_id(&mut self) -> TbResult<ActivityId>;
+
     /// Sets the gear for an activity if it is null.
     ///
     /// # Arguments
@@ -130,4 +153,38 @@ pub trait ActivityStore {
     ///
     /// Returns a `Result` containing a vector of `Activity` structs or an error if the operation fails.
     async fn activity_get_really_all(&mut self) -> TbResult<Vec<Activity>>;
+
+    /// Retrieves the activities of a user changed since the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `since` - The cursor of the last synchronization.
+    ///
+    /// # Returns
+    ///
+    /// The activities ordered by start.
+    async fn activities_changed(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<Activity>>;
+
+    /// Retrieves a page of the activities of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `query` - The filters, the sort order and the cursor of the previous page.
+    /// * `limit` - The maximal number of activities.
+    ///
+    /// # Returns
+    ///
+    /// The matching activities in the order of the query.
+    async fn activities_query(
+        &mut self,
+        user: UserId,
+        query: &ActivityQuery,
+        limit: i64,
+    ) -> TbResult<Vec<Activity>>;
 }
@@ -1,6 +1,6 @@
 use time::OffsetDateTime;
 
-use crate::{Attachment, PartId, PartTypeId, TbResult};
+use crate::{Attachment, PartId, PartTypeId, SyncCursor, TbResult, UserId};
 
 /// This trait defines methods for storing and retrieving attachments.
 #[async_trait::async_trait]
@@ -32,6 +32,9 @@ pub trait AttachmentStore {
     /// Get all attachments for a list of part IDs.
     async fn attachments_all_by_part(&mut self, id: PartId) -> TbResult<Vec<Attachment>>;
 
+    /// Get all attachments to a gear.
+    async fn attachments_all_by_gear(&mut self, gear: PartId) -> TbResult<Vec<Attachment>>;
+
     /// Get an attachment for a given part and time.
     async fn attachment_get_by_part_and_time(
         &mut self,
@@ -81,4 +84,20 @@ pub trait AttachmentStore {
         hook: PartTypeId,
         time: OffsetDateTime,
     ) -> TbResult<Option<Attachment>>;
+
+    /// Retrieves the attachments of the parts of a user changed since the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `since` - The cursor of the last synchronization.
+    ///
+    /// # Returns
+    ///
+    /// The changed attachments.
+    async fn attachments_changed(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<Attachment>>;
 }
@@ -0,0 +1,84 @@
+use serde_json::Value;
+
+use crate::{AuditEntry, AuditId, PartId, TbResult, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing an append-only store for the audit log.
+pub trait AuditStore {
+    /// Appends an entry to the log.
+    ///
+    /// # Arguments
+    ///
+    /// * `actor` - The user who made the change.
+    /// * `user` - The user the actor was acting as.
+    /// * `entity` - The kind of the changed entity.
+    /// * `entity_id` - The id of the changed entity.
+    /// * `part` - The part the entity belongs to.
+    /// * `before` - The entity before the change.
+    /// * `after` - The entity after the change.
+    #[allow(clippy::too_many_arguments)]
+    async fn audit_append(
+        &mut self,
+        actor: UserId,
+        user: UserId,
+        entity: &str,
+        entity_id: String,
+        part: Option<PartId>,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> TbResult<()>;
+
+    /// Reads the entries for a part.
+    ///
+    /// # Arguments
+    ///
+    /// * `part` - The part.
+    /// * `before` - Only entries with a smaller id are returned.
+    /// * `limit` - The maximal number of entries.
+    ///
+    /// # Returns
+    ///
+    /// The entries ordered by descending id.
+    async fn audit_for_part(
+        &mut self,
+        part: PartId,
+        before: Option<AuditId>,
+        limit: i64,
+    ) -> TbResult<Vec<AuditEntry>>;
+
+    /// Reads the entries of a part which changed its disposal.
+    ///
+    /// # Arguments
+    ///
+    /// * `part` - The part.
+    ///
+    /// # Returns
+    ///
+    /// The entries ordered by ascending id.
+    async fn audit_part_disposals(&mut self, part: PartId) -> TbResult<Vec<AuditEntry>>;
+
+    /// Reads the entries made as a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The user the changes were made as.
+    /// * `before` - Only entries with a smaller id are returned.
+    /// * `limit` - The maximal number of entries.
+    ///
+    /// # Returns
+    ///
+    /// The entries ordered by descending id.
+    async fn audit_for_user(
+        &mut self,
+        user: UserId,
+        before: Option<AuditId>,
+        limit: i64,
+    ) -> TbResult<Vec<AuditEntry>>;
+
+    /// Reads the entries written in the current transaction.
+    ///
+    /// # Returns
+    ///
+    /// The entries ordered by ascending id.
+    async fn audit_for_transaction(&mut self) -> TbResult<Vec<AuditEntry>>;
+}
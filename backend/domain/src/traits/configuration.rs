@@ -0,0 +1,69 @@
+use crate::{ConfigPart, Configuration, ConfigurationId, PartId, TbResult, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing a store for gear configurations.
+pub trait ConfigurationStore {
+    /// Creates a new configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The user ID of the owner.
+    /// * `gear` - The gear the configuration belongs to.
+    /// * `name` - The name of the configuration.
+    /// * `parts` - The parts and their hooks.
+    ///
+    /// # Returns
+    ///
+    /// The newly created configuration.
+    async fn configuration_create(
+        &mut self,
+        owner: UserId,
+        gear: PartId,
+        name: String,
+        parts: Vec<ConfigPart>,
+    ) -> TbResult<Configuration>;
+
+    /// Reads a configuration by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the configuration to read.
+    ///
+    /// # Returns
+    ///
+    /// The configuration with the given ID, if it exists.
+    async fn configuration_get(&mut self, id: ConfigurationId) -> TbResult<Configuration>;
+
+    /// Updates the name and the parts of a configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to update.
+    ///
+    /// # Returns
+    ///
+    /// The updated configuration.
+    async fn configuration_update(&mut self, config: Configuration) -> TbResult<Configuration>;
+
+    /// Deletes a configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the configuration to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted configurations.
+    async fn configuration_delete(&mut self, id: ConfigurationId) -> TbResult<usize>;
+
+    /// Gets all configurations of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The ID of the user.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the configurations owned by the user.
+    async fn configurations_for_user(&mut self, user: UserId) -> TbResult<Vec<Configuration>>;
+}
@@ -0,0 +1,69 @@
+use crate::{GearMatch, GearRule, GearRuleId, PartId, TbResult, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing a store for gear rules.
+pub trait GearRuleStore {
+    /// Creates a new gear rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The user ID of the owner.
+    /// * `position` - The position in the order of evaluation.
+    /// * `gear` - The gear to assign.
+    /// * `conditions` - The conditions of the rule.
+    ///
+    /// # Returns
+    ///
+    /// The newly created gear rule.
+    async fn gear_rule_create(
+        &mut self,
+        owner: UserId,
+        position: i32,
+        gear: PartId,
+        conditions: GearMatch,
+    ) -> TbResult<GearRule>;
+
+    /// Reads a gear rule by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the gear rule to read.
+    ///
+    /// # Returns
+    ///
+    /// The gear rule with the given ID, if it exists.
+    async fn gear_rule_get(&mut self, id: GearRuleId) -> TbResult<GearRule>;
+
+    /// Updates an existing gear rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - The gear rule to update.
+    ///
+    /// # Returns
+    ///
+    /// The updated gear rule.
+    async fn gear_rule_update(&mut self, rule: GearRule) -> TbResult<GearRule>;
+
+    /// Deletes a gear rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the gear rule to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted gear rules.
+    async fn gear_rule_delete(&mut self, id: GearRuleId) -> TbResult<usize>;
+
+    /// Gets all gear rules of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The ID of the user.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the gear rules of the user ordered by position.
+    async fn gear_rules_for_user(&mut self, user: UserId) -> TbResult<Vec<GearRule>>;
+}
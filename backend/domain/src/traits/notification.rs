@@ -0,0 +1,60 @@
+use crate::{DueState, Notice, NotifyPrefs, PartId, ServicePlanId, TbResult, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing a store for notification preferences and sent notices.
+pub trait NotificationStore {
+    /// Retrieves the notification preferences of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The id of the user.
+    ///
+    /// # Returns
+    ///
+    /// The preferences or None if the user never stored any.
+    async fn notify_prefs_get(&mut self, user: UserId) -> TbResult<Option<NotifyPrefs>>;
+
+    /// Creates or replaces the notification preferences of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefs` - The preferences to store.
+    ///
+    /// # Returns
+    ///
+    /// The stored preferences.
+    async fn notify_prefs_set(&mut self, prefs: NotifyPrefs) -> TbResult<NotifyPrefs>;
+
+    /// Retrieves the notification preferences of all users.
+    async fn notify_prefs_all(&mut self) -> TbResult<Vec<NotifyPrefs>>;
+
+    /// Retrieves the last state reported for a plan and part.
+    ///
+    /// # Returns
+    ///
+    /// The state or None if nothing was reported since the last service.
+    async fn notice_get(&mut self, plan: ServicePlanId, part: PartId)
+    -> TbResult<Option<DueState>>;
+
+    /// Records the state reported for a plan and part.
+    async fn notice_set(
+        &mut self,
+        plan: ServicePlanId,
+        part: PartId,
+        state: DueState,
+    ) -> TbResult<()>;
+
+    /// Forgets the state reported for a plan and part.
+    async fn notice_delete(&mut self, plan: ServicePlanId, part: PartId) -> TbResult<()>;
+}
+
+#[async_trait::async_trait]
+/// A trait representing a channel to deliver notices to users.
+pub trait Notifier: Sync {
+    /// Delivers a notice according to the preferences of the user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the notice could not be delivered on any channel.
+    async fn notify(&self, prefs: &NotifyPrefs, notice: &Notice) -> TbResult<()>;
+}
@@ -1,7 +1,7 @@
 #![allow(clippy::too_many_arguments)]
 use time::OffsetDateTime;
 
-use crate::{Part, PartId, PartTypeId, ShopId, TbResult, UsageId, UserId};
+use crate::{Money, Part, PartId, PartTypeId, ShopId, SyncCursor, TbResult, UsageId, UserId};
 
 #[async_trait::async_trait]
 /// A trait representing a store for `Part` objects.
@@ -46,6 +46,7 @@ pub trait PartStore {
         purchase: OffsetDateTime,
         source: Option<String>,
         notes: String,
+        price: Option<Money>,
         usage: UsageId,
         owner: UserId,
         shop: Option<ShopId>,
@@ -129,4 +130,16 @@ pub trait PartStore {
     ///
     /// A vector of PartIds registered to the shop.
     async fn shop_get_parts(&mut self, shop_id: ShopId) -> TbResult<Vec<Part>>;
+
+    /// Retrieves the parts of a user changed since the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `since` - The cursor of the last synchronization.
+    ///
+    /// # Returns
+    ///
+    /// The changed parts.
+    async fn parts_changed(&mut self, user: UserId, since: SyncCursor) -> TbResult<Vec<Part>>;
 }
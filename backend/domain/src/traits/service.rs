@@ -1,4 +1,4 @@
-use crate::{PartId, Service, ServiceId, TbResult};
+use crate::{PartId, Service, ServiceId, SyncCursor, TbResult, UserId};
 
 #[async_trait::async_trait]
 /// A trait representing a store for `Usage` objects.
@@ -20,4 +20,17 @@ pub trait ServiceStore {
     async fn services_delete(&mut self, services: &[Service]) -> TbResult<usize>;
 
     async fn services_by_part(&mut self, part: PartId) -> TbResult<Vec<Service>>;
+
+    /// Retrieves the services of the parts of a user changed since the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `since` - The cursor of the last synchronization.
+    ///
+    /// # Returns
+    ///
+    /// The changed services.
+    async fn services_changed(&mut self, user: UserId, since: SyncCursor)
+    -> TbResult<Vec<Service>>;
 }
@@ -1,4 +1,4 @@
-use crate::{PartId, ServicePlan, ServicePlanId, TbResult, UserId};
+use crate::{PartId, ServicePlan, ServicePlanId, SyncCursor, TbResult, UserId};
 
 #[async_trait::async_trait]
 /// A trait representing a store for `Usage` objects.
@@ -21,4 +21,20 @@ pub trait ServicePlanStore {
 
     async fn by_part(&mut self, part: PartId) -> TbResult<Vec<ServicePlan>>;
     async fn by_user(&mut self, uid: UserId) -> TbResult<Vec<ServicePlan>>;
+
+    /// Retrieves the service plans of a user and of the parts of a user changed since the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `since` - The cursor of the last synchronization.
+    ///
+    /// # Returns
+    ///
+    /// The changed service plans.
+    async fn serviceplans_changed(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<ServicePlan>>;
 }
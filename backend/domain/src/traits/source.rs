@@ -0,0 +1,133 @@
+use time::OffsetDateTime;
+
+use crate::{Activity, Part, PartId, PartTypeId, Session, Store, Summary, TbResult};
+
+/// Gear as reported by an activity source
+#[derive(Clone, Debug)]
+pub struct SourceGear {
+    /// The id of the gear at the source
+    ///
+    /// It is stored in `Part.source` and has to be unique across all sources.
+    pub id: String,
+    /// The display name
+    pub name: String,
+    /// The manufacturer
+    pub vendor: String,
+    /// The model
+    pub model: String,
+    /// The kind of gear
+    pub what: PartTypeId,
+}
+
+#[async_trait::async_trait]
+/// A trait representing an external source of activities and gear, like Strava.
+///
+/// The source is the session of the user at the provider. `authorize` has to succeed before
+/// anything is fetched. Fetching only maps the data of the provider, storing is left to
+/// `sync_activity` and `gear_to_partid`. Gear of the source becomes a part of the user with the
+/// id at the source in `Part.source`, so it is created only once.
+pub trait ActivitySource<S: Store>: Session + Sized {
+    /// Makes sure the session holds valid credentials for the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotAuth` if the user needs to authorize again.
+    async fn authorize(&mut self, store: &mut S) -> TbResult<()>;
+
+    /// Revokes the authorization of the user at the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source is not reachable.
+    async fn deauthorize(&mut self, store: &mut S) -> TbResult<()>;
+
+    /// Fetches an activity from the source.
+    ///
+    /// Gear references have to be mapped with `gear_to_partid`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the activity at the source.
+    /// * `store` - The store to use.
+    ///
+    /// # Returns
+    ///
+    /// The activity ready to be stored.
+    async fn fetch_activity(&mut self, id: &str, store: &mut S) -> TbResult<Activity>;
+
+    /// Fetches a gear from the source.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the gear at the source.
+    /// * `store` - The store to use.
+    ///
+    /// # Returns
+    ///
+    /// The gear data needed to create a part.
+    async fn fetch_gear(&mut self, id: &str, store: &mut S) -> TbResult<SourceGear>;
+
+    /// Maps a gear id of the source to a `PartId`.
+    ///
+    /// If no part has this gear as its source, the gear is fetched and a new part gets created.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the gear at the source.
+    /// * `store` - The store to use.
+    ///
+    /// # Returns
+    ///
+    /// The id of the part for this gear.
+    async fn gear_to_partid(&mut self, id: &str, store: &mut S) -> TbResult<PartId> {
+        if let Some(gear) = store.partid_get_by_source(id).await? {
+            return Ok(gear);
+        }
+
+        log::debug!("New Gear {id}");
+        let gear = self.fetch_gear(id, store).await?;
+
+        // maybe the gear was created by now?
+        if let Some(gear) = store.partid_get_by_source(id).await? {
+            return Ok(gear);
+        }
+
+        let SourceGear {
+            id,
+            name,
+            vendor,
+            model,
+            what,
+        } = gear;
+        let purchase = OffsetDateTime::now_utc();
+        let part = Part::create(
+            name,
+            vendor,
+            model,
+            what,
+            Some(id),
+            purchase,
+            String::new(),
+            None,
+            self,
+            store,
+        )
+        .await?;
+        Ok(part.id)
+    }
+
+    /// Fetches an activity from the source and creates or updates it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the activity at the source.
+    /// * `store` - The store to use.
+    ///
+    /// # Returns
+    ///
+    /// The summary of all changed objects.
+    async fn sync_activity(&mut self, id: &str, store: &mut S) -> TbResult<Summary> {
+        let activity = self.fetch_activity(id, store).await?;
+        activity.upsert(self, store).await
+    }
+}
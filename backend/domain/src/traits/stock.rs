@@ -0,0 +1,82 @@
+#![allow(clippy::too_many_arguments)]
+use crate::{Money, PartTypeId, Stock, StockId, TbResult, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing a store for spare parts.
+pub trait StockStore {
+    /// Creates a new stock.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The user ID of the owner.
+    /// * `what` - The type of the parts.
+    /// * `hook` - The hook the parts are for, none for any hook.
+    /// * `name` - The name for new parts.
+    /// * `vendor` - The vendor name.
+    /// * `model` - The model name.
+    /// * `quantity` - The number of items on hand.
+    /// * `min_quantity` - Warn if fewer items are on hand.
+    /// * `price` - The price of a single item.
+    /// * `notes` - Notes for new parts.
+    ///
+    /// # Returns
+    ///
+    /// The newly created stock.
+    async fn stock_create(
+        &mut self,
+        owner: UserId,
+        what: PartTypeId,
+        hook: Option<PartTypeId>,
+        name: String,
+        vendor: String,
+        model: String,
+        quantity: i32,
+        min_quantity: i32,
+        price: Option<Money>,
+        notes: String,
+    ) -> TbResult<Stock>;
+
+    /// Reads a stock by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the stock to read.
+    ///
+    /// # Returns
+    ///
+    /// The stock with the given ID, if it exists.
+    async fn stock_get(&mut self, id: StockId) -> TbResult<Stock>;
+
+    /// Updates an existing stock.
+    ///
+    /// # Arguments
+    ///
+    /// * `stock` - The stock to update.
+    ///
+    /// # Returns
+    ///
+    /// The updated stock.
+    async fn stock_update(&mut self, stock: Stock) -> TbResult<Stock>;
+
+    /// Deletes a stock.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the stock to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted stocks.
+    async fn stock_delete(&mut self, id: StockId) -> TbResult<usize>;
+
+    /// Gets all stock of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The ID of the user.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the stock owned by the user.
+    async fn stocks_for_user(&mut self, user: UserId) -> TbResult<Vec<Stock>>;
+}
@@ -0,0 +1,25 @@
+use crate::{Deletion, SyncCursor, TbResult, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing a store for the incremental synchronization of summaries.
+pub trait SyncStore {
+    /// Creates a cursor for the current state.
+    ///
+    /// # Returns
+    ///
+    /// A cursor before all changes not yet visible.
+    async fn sync_cursor(&mut self) -> TbResult<SyncCursor>;
+
+    /// Retrieves the entities of a user deleted since the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `since` - The cursor of the last synchronization.
+    ///
+    /// # Returns
+    ///
+    /// The deleted entities.
+    async fn deletions_since(&mut self, user: UserId, since: SyncCursor)
+    -> TbResult<Vec<Deletion>>;
+}
@@ -0,0 +1,107 @@
+use crate::{ActTypeId, ActivityType, PartType, PartTypeId, TbResult};
+
+#[async_trait::async_trait]
+/// A trait representing a store for the catalog of part and activity types.
+pub trait TypeStore {
+    /// Retrieves all part types.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of all `PartType` objects.
+    async fn parttypes_get_all(&mut self) -> TbResult<Vec<PartType>>;
+
+    /// Creates a new part type.
+    ///
+    /// # Arguments
+    ///
+    /// * `parttype` - The part type to create. The id is ignored and assigned by the store.
+    ///
+    /// # Returns
+    ///
+    /// Returns the newly created `PartType`.
+    async fn parttype_create(&mut self, parttype: PartType) -> TbResult<PartType>;
+
+    /// Updates an existing part type.
+    ///
+    /// # Arguments
+    ///
+    /// * `parttype` - The part type with the new values.
+    ///
+    /// # Returns
+    ///
+    /// Returns the updated `PartType`.
+    async fn parttype_update(&mut self, parttype: PartType) -> TbResult<PartType>;
+
+    /// Deletes a part type.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the part type to delete.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of deleted part types.
+    async fn parttype_delete(&mut self, id: PartTypeId) -> TbResult<usize>;
+
+    /// Checks if any part or attachment refers to a part type.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the part type.
+    ///
+    /// # Returns
+    ///
+    /// Returns true if the part type is in use.
+    async fn parttype_in_use(&mut self, id: PartTypeId) -> TbResult<bool>;
+
+    /// Retrieves all activity types.
+    ///
+    /// # Returns
+    ///
+    /// Returns a vector of all `ActivityType` objects.
+    async fn acttypes_get_all(&mut self) -> TbResult<Vec<ActivityType>>;
+
+    /// Creates a new activity type.
+    ///
+    /// # Arguments
+    ///
+    /// * `acttype` - The activity type to create. The id is ignored and assigned by the store.
+    ///
+    /// # Returns
+    ///
+    /// Returns the newly created `ActivityType`.
+    async fn acttype_create(&mut self, acttype: ActivityType) -> TbResult<ActivityType>;
+
+    /// Updates an existing activity type.
+    ///
+    /// # Arguments
+    ///
+    /// * `acttype` - The activity type with the new values.
+    ///
+    /// # Returns
+    ///
+    /// Returns the updated `ActivityType`.
+    async fn acttype_update(&mut self, acttype: ActivityType) -> TbResult<ActivityType>;
+
+    /// Deletes an activity type.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the activity type to delete.
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of deleted activity types.
+    async fn acttype_delete(&mut self, id: ActTypeId) -> TbResult<usize>;
+
+    /// Checks if any activity refers to an activity type.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the activity type.
+    ///
+    /// # Returns
+    ///
+    /// Returns true if the activity type is in use.
+    async fn acttype_in_use(&mut self, id: ActTypeId) -> TbResult<bool>;
+}
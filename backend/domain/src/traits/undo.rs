@@ -0,0 +1,34 @@
+use crate::{TbResult, Undo, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing a store for the undo of the last operation.
+pub trait UndoStore {
+    /// Sets the undo of a user, replacing the previous one.
+    ///
+    /// # Arguments
+    ///
+    /// * `undo` - The undo to store.
+    async fn undo_set(&mut self, undo: Undo) -> TbResult<()>;
+
+    /// Reads the undo of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The user ID of the owner.
+    ///
+    /// # Returns
+    ///
+    /// The undo of the user, if there is one.
+    async fn undo_get(&mut self, owner: UserId) -> TbResult<Option<Undo>>;
+
+    /// Deletes the undo of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The user ID of the owner.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted undos.
+    async fn undo_delete(&mut self, owner: UserId) -> TbResult<usize>;
+}
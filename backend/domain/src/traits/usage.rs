@@ -1,4 +1,4 @@
-use crate::{TbResult, Usage, UsageId};
+use crate::{SyncCursor, TbResult, Usage, UsageId, UserId};
 
 #[async_trait::async_trait]
 /// A trait representing a store for `Usage` objects.
@@ -52,10 +52,29 @@ pub trait UsageStore {
     /// Returns a `Result` containing the number of deleted usages or an error if the operation fails.
     async fn usages_delete(&mut self, usages: &[Usage]) -> TbResult<usize>;
 
+    /// Finds the usages no part, attachment or service refers to
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result` containing the ids of the orphaned usages or an error if the operation fails.
+    async fn usages_orphaned(&mut self) -> TbResult<Vec<UsageId>>;
+
     /// Resets all Usages.
     ///
     /// # Returns
     ///
     /// Returns a `Result` containing the number of reset parts or an error if the operation fails.
     async fn delete_all(&mut self) -> TbResult<usize>;
+
+    /// Retrieves the usages of the parts, attachments and services of a user changed since the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The owner.
+    /// * `since` - The cursor of the last synchronization.
+    ///
+    /// # Returns
+    ///
+    /// The changed usages.
+    async fn usages_changed(&mut self, user: UserId, since: SyncCursor) -> TbResult<Vec<Usage>>;
 }
@@ -0,0 +1,69 @@
+use crate::{ActTypeId, PartTypeId, TbResult, UsageRule, UsageRuleId, UserId};
+
+#[async_trait::async_trait]
+/// A trait representing a store for usage rules.
+pub trait UsageRuleStore {
+    /// Creates a new usage rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The user ID of the owner.
+    /// * `what` - The activity type.
+    /// * `hook` - The hook the parts are attached to.
+    /// * `factor` - The factor for the usage.
+    ///
+    /// # Returns
+    ///
+    /// The newly created usage rule.
+    async fn usage_rule_create(
+        &mut self,
+        owner: UserId,
+        what: ActTypeId,
+        hook: PartTypeId,
+        factor: f64,
+    ) -> TbResult<UsageRule>;
+
+    /// Reads a usage rule by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the usage rule to read.
+    ///
+    /// # Returns
+    ///
+    /// The usage rule with the given ID, if it exists.
+    async fn usage_rule_get(&mut self, id: UsageRuleId) -> TbResult<UsageRule>;
+
+    /// Updates an existing usage rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - The usage rule to update.
+    ///
+    /// # Returns
+    ///
+    /// The updated usage rule.
+    async fn usage_rule_update(&mut self, rule: UsageRule) -> TbResult<UsageRule>;
+
+    /// Deletes a usage rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the usage rule to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted usage rules.
+    async fn usage_rule_delete(&mut self, id: UsageRuleId) -> TbResult<usize>;
+
+    /// Gets all usage rules of a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The ID of the user.
+    ///
+    /// # Returns
+    ///
+    /// A vector of the usage rules owned by the user.
+    async fn usage_rules_for_user(&mut self, user: UserId) -> TbResult<Vec<UsageRule>>;
+}
@@ -61,6 +61,9 @@ pub trait UserStore {
     /// Returns a `Result` containing 1 or an error if the operation fails.
     async fn user_delete(&mut self, user: &UserId) -> TbResult<usize>;
 
+    /// Returns the ids of all users
+    async fn user_ids_get_all(&mut self) -> TbResult<Vec<UserId>>;
+
     /// Updates the onboarding status for a user
     ///
     /// # Arguments
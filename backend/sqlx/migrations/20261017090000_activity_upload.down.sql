@@ -0,0 +1,2 @@
+-- Add down migration script here
+drop sequence if exists activities_upload_id_seq;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- uploaded activities get negative ids, so they never collide with strava ids
+create sequence if not exists activities_upload_id_seq increment by -1 maxvalue -1 start with -1;
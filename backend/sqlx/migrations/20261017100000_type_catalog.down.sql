@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop table if exists activity_types;
+drop table if exists part_types;
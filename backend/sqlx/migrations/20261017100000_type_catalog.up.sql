@@ -0,0 +1,64 @@
+-- Add up migration script here
+create table if not exists part_types (
+    id serial primary key,
+    name text not null,
+    main integer not null,
+    hooks integer[] not null default '{}',
+    "order" integer not null default 9999,
+    "group" text
+);
+
+create table if not exists activity_types (
+    id serial primary key,
+    name text not null,
+    gear_type integer not null references part_types(id)
+);
+
+insert into part_types (id, name, main, hooks, "order", "group") values
+    (1, 'Bike', 1, '{}', 1, null),
+    (2, 'front wheel', 1, '{1}', 5, 'Wheels'),
+    (3, 'tire', 1, '{2,5}', 7, 'Tires'),
+    (4, 'chain', 1, '{1}', 11, 'Drive train'),
+    (5, 'rear wheel', 1, '{1}', 6, 'Wheels'),
+    (6, 'brake pad', 1, '{7,8}', 4, 'Brakes'),
+    (7, 'front brake', 1, '{1}', 2, 'Brakes'),
+    (8, 'rear brake', 1, '{1}', 3, 'Brakes'),
+    (9, 'cassette', 1, '{5}', 10, 'Drive train'),
+    (10, 'seat post', 1, '{1}', 18, 'Seat post'),
+    (11, 'saddle', 1, '{10}', 19, null),
+    (12, 'derailleur', 1, '{1}', 12, 'Drive train'),
+    (13, 'crank', 1, '{1}', 13, 'Drive train'),
+    (14, 'chainring', 1, '{13}', 9, 'Drive train'),
+    (15, 'brake rotor', 1, '{2,5}', 8, 'Brakes'),
+    (16, 'fork', 1, '{1}', 16, 'Fork'),
+    (17, 'rear shock', 1, '{1}', 15, 'Shock'),
+    (18, 'pedal', 1, '{13}', 10, null),
+    (19, 'bottom bracket', 1, '{1}', 14, null),
+    (20, 'headset', 1, '{1}', 17, null),
+    (301, 'Shoe', 301, '{}', 9999, null),
+    (302, 'Snowboard', 302, '{}', 9999, null),
+    (303, 'Ski', 303, '{}', 9999, null),
+    (304, 'Whatever', 304, '{}', 9999, null),
+    (305, 'SUP board', 305, '{}', 9999, null),
+    (306, 'Windsurf Board', 306, '{}', 9999, null),
+    (307, 'Kite Board', 307, '{}', 9999, null),
+    (308, 'Rowing boat', 308, '{}', 9999, null),
+    (309, 'binding', 302, '{302}', 9999, null)
+on conflict (id) do nothing;
+
+insert into activity_types (id, name, gear_type) values
+    (0, 'Whatever', 304),
+    (1, 'Bike Ride', 1),
+    (2, 'Snowboard', 302),
+    (3, 'Running', 301),
+    (4, 'Hiking', 301),
+    (5, 'Virtual Ride', 1),
+    (6, 'Skiing', 303),
+    (7, 'Splitboard Tour', 302),
+    (8, 'Walk', 301),
+    (9, 'EBike Ride', 1),
+    (10, 'Skitour', 303)
+on conflict (id) do nothing;
+
+select setval('part_types_id_seq', (select max(id) from part_types));
+select setval('activity_types_id_seq', (select max(id) from activity_types));
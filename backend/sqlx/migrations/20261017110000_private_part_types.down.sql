@@ -0,0 +1,2 @@
+-- Add down migration script here
+alter table part_types drop column if exists owner;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+alter table part_types add column if not exists owner integer references users(id) on delete cascade;
@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop table if exists notices;
+drop table if exists notify_prefs;
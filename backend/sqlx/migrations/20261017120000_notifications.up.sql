@@ -0,0 +1,15 @@
+-- Add up migration script here
+create table if not exists notify_prefs (
+    user_id integer primary key references users(id) on delete cascade,
+    email text,
+    webhook text,
+    soon boolean not null default true,
+    overdue boolean not null default true
+);
+
+create table if not exists notices (
+    plan uuid not null references service_plans(id) on delete cascade,
+    part integer not null references parts(id) on delete cascade,
+    state integer not null,
+    primary key (plan, part)
+);
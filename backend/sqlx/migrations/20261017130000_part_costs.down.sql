@@ -0,0 +1,5 @@
+-- Add down migration script here
+alter table services drop column if exists cost_currency;
+alter table services drop column if exists cost;
+alter table parts drop column if exists currency;
+alter table parts drop column if exists price;
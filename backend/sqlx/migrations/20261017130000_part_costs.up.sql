@@ -0,0 +1,5 @@
+-- Add up migration script here
+alter table parts add column price integer;
+alter table parts add column currency text;
+alter table services add column cost integer;
+alter table services add column cost_currency text;
@@ -0,0 +1,16 @@
+-- Add up migration script here
+create table if not exists stock (
+    id serial primary key,
+    owner integer not null references users(id) on delete cascade,
+    what integer not null references part_types(id),
+    name text not null,
+    vendor text not null default '',
+    model text not null default '',
+    quantity integer not null default 0 check (quantity >= 0),
+    min_quantity integer not null default 0 check (min_quantity >= 0),
+    price integer,
+    currency text,
+    notes text not null default ''
+);
+
+create index if not exists idx_stock_owner on stock(owner);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop index if exists idx_activities_gears;
+alter table activities drop column if exists gears;
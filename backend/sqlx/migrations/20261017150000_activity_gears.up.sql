@@ -0,0 +1,4 @@
+-- Add up migration script here
+alter table activities add column if not exists gears integer[] not null default '{}';
+
+create index if not exists idx_activities_gears on activities using gin (gears);
@@ -0,0 +1,9 @@
+-- Add up migration script here
+create table if not exists usage_rules (
+    id serial primary key,
+    owner integer not null references users(id) on delete cascade,
+    what integer not null references activity_types(id) on delete cascade,
+    hook integer not null references part_types(id) on delete cascade,
+    factor double precision not null default 1 check (factor >= 0),
+    unique (owner, what, hook)
+);
@@ -0,0 +1,17 @@
+-- Add up migration script here
+create table if not exists gear_rules (
+    id serial primary key,
+    owner integer not null references users(id) on delete cascade,
+    position integer not null default 0,
+    gear integer not null references parts(id) on delete cascade,
+    what integer references activity_types(id) on delete cascade,
+    device_name text,
+    keyword text,
+    weekdays integer[] not null default '{}',
+    time_from time,
+    time_until time,
+    min_distance integer,
+    max_distance integer
+);
+
+create index if not exists idx_gear_rules_owner on gear_rules(owner);
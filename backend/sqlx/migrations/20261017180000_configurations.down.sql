@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop table if exists configuration_parts;
+drop table if exists configurations;
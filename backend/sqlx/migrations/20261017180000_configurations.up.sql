@@ -0,0 +1,16 @@
+-- Add up migration script here
+create table if not exists configurations (
+    id serial primary key,
+    owner integer not null references users(id) on delete cascade,
+    gear integer not null references parts(id) on delete cascade,
+    name text not null
+);
+
+create index if not exists idx_configurations_owner on configurations(owner);
+
+create table if not exists configuration_parts (
+    configuration integer not null references configurations(id) on delete cascade,
+    part integer not null references parts(id) on delete cascade,
+    hook integer not null references part_types(id) on delete cascade,
+    primary key (configuration, part)
+);
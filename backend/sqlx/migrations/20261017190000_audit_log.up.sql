@@ -0,0 +1,16 @@
+-- Add up migration script here
+create table if not exists audit_log (
+    id bigserial primary key,
+    time timestamptz not null default now(),
+    -- no foreign keys, the log outlives users and parts
+    actor integer not null,
+    user_id integer not null,
+    entity text not null,
+    entity_id text not null,
+    part_id integer,
+    before jsonb,
+    after jsonb
+);
+
+create index if not exists idx_audit_log_part on audit_log(part_id, id);
+create index if not exists idx_audit_log_user on audit_log(user_id, id);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop table if exists undos;
+alter table audit_log drop column if exists tx;
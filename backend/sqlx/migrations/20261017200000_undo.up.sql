@@ -0,0 +1,11 @@
+-- Add up migration script here
+alter table audit_log add column if not exists tx bigint not null default txid_current();
+
+create index if not exists idx_audit_log_tx on audit_log(tx);
+
+create table if not exists undos (
+    owner integer primary key references users(id) on delete cascade,
+    operation text not null,
+    time timestamptz not null,
+    changes jsonb not null
+);
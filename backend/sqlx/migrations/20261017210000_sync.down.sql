@@ -0,0 +1,31 @@
+-- Add down migration script here
+drop trigger if exists activities_created on activities;
+drop trigger if exists parts_created on parts;
+drop trigger if exists attachments_created on attachments;
+drop trigger if exists services_created on services;
+drop trigger if exists service_plans_created on service_plans;
+
+drop trigger if exists activities_deleted on activities;
+drop trigger if exists parts_deleted on parts;
+drop trigger if exists attachments_deleted on attachments;
+drop trigger if exists services_deleted on services;
+drop trigger if exists service_plans_deleted on service_plans;
+
+drop function if exists clear_deletion();
+drop function if exists record_deletion();
+drop table if exists deletions;
+
+drop trigger if exists activities_version on activities;
+drop trigger if exists parts_version on parts;
+drop trigger if exists attachments_version on attachments;
+drop trigger if exists usages_version on usages;
+drop trigger if exists services_version on services;
+drop trigger if exists service_plans_version on service_plans;
+drop function if exists set_version();
+
+alter table activities drop column if exists version;
+alter table parts drop column if exists version;
+alter table attachments drop column if exists version;
+alter table usages drop column if exists version;
+alter table services drop column if exists version;
+alter table service_plans drop column if exists version;
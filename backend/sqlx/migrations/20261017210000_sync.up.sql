@@ -0,0 +1,131 @@
+-- Add up migration script here
+
+-- the transaction which changed the row last
+alter table activities add column if not exists version bigint not null default txid_current();
+alter table parts add column if not exists version bigint not null default txid_current();
+alter table attachments add column if not exists version bigint not null default txid_current();
+alter table usages add column if not exists version bigint not null default txid_current();
+alter table services add column if not exists version bigint not null default txid_current();
+alter table service_plans add column if not exists version bigint not null default txid_current();
+
+create index if not exists idx_activities_user_version on activities(user_id, version);
+create index if not exists idx_parts_owner_version on parts(owner, version);
+
+create or replace function set_version()
+returns trigger as $$
+begin
+    new.version = txid_current();
+    return new;
+end;
+$$ language plpgsql;
+
+create or replace trigger activities_version before update on activities
+    for each row execute function set_version();
+create or replace trigger parts_version before update on parts
+    for each row execute function set_version();
+create or replace trigger attachments_version before update on attachments
+    for each row execute function set_version();
+create or replace trigger usages_version before update on usages
+    for each row execute function set_version();
+create or replace trigger services_version before update on services
+    for each row execute function set_version();
+create or replace trigger service_plans_version before update on service_plans
+    for each row execute function set_version();
+
+-- tombstones of deleted entities
+-- attachments are identified by entity_id, the part, and attached
+create table if not exists deletions (
+    version bigint not null default txid_current(),
+    owner integer not null references users(id) on delete cascade,
+    entity text not null,
+    entity_id text not null,
+    attached timestamptz
+);
+
+create index if not exists idx_deletions_owner_version on deletions(owner, version);
+create index if not exists idx_deletions_entity on deletions(entity, entity_id);
+
+create or replace function record_deletion()
+returns trigger as $$
+declare
+    owner integer;
+    entity text;
+    entity_id text;
+    attached timestamptz;
+begin
+    case tg_table_name
+    when 'activities' then
+        owner = old.user_id;
+        entity = 'activity';
+        entity_id = old.id::text;
+    when 'parts' then
+        owner = old.owner;
+        entity = 'part';
+        entity_id = old.id::text;
+    when 'attachments' then
+        select p.owner into owner from parts p where p.id = old.part_id;
+        entity = 'attachment';
+        entity_id = old.part_id::text;
+        attached = old.attached;
+    when 'services' then
+        select p.owner into owner from parts p where p.id = old.part_id;
+        entity = 'service';
+        entity_id = old.id::text;
+    when 'service_plans' then
+        owner = old.uid;
+        if owner is null then
+            select p.owner into owner from parts p where p.id = old.part;
+        end if;
+        entity = 'plan';
+        entity_id = old.id::text;
+    end case;
+    if owner is not null then
+        insert into deletions (owner, entity, entity_id, attached)
+            values (owner, entity, entity_id, attached);
+    end if;
+    return old;
+end;
+$$ language plpgsql;
+
+-- an entity which is created again is not deleted anymore
+create or replace function clear_deletion()
+returns trigger as $$
+begin
+    case tg_table_name
+    when 'activities' then
+        delete from deletions where entity = 'activity' and entity_id = new.id::text;
+    when 'parts' then
+        delete from deletions where entity = 'part' and entity_id = new.id::text;
+    when 'attachments' then
+        delete from deletions
+            where entity = 'attachment' and entity_id = new.part_id::text and attached = new.attached;
+    when 'services' then
+        delete from deletions where entity = 'service' and entity_id = new.id::text;
+    when 'service_plans' then
+        delete from deletions where entity = 'plan' and entity_id = new.id::text;
+    end case;
+    return new;
+end;
+$$ language plpgsql;
+
+create or replace trigger activities_deleted after delete on activities
+    for each row execute function record_deletion();
+create or replace trigger parts_deleted after delete on parts
+    for each row execute function record_deletion();
+create or replace trigger attachments_deleted after delete on attachments
+    for each row execute function record_deletion();
+create or replace trigger services_deleted after delete on services
+    for each row execute function record_deletion();
+create or replace trigger service_plans_deleted after delete on service_plans
+    for each row execute function record_deletion();
+
+create or replace trigger activities_created after insert on activities
+    for each row execute function clear_deletion();
+create or replace trigger parts_created after insert on parts
+    for each row execute function clear_deletion();
+create or replace trigger attachments_created after insert on attachments
+    for each row execute function clear_deletion();
+create or replace trigger services_created after insert on services
+    for each row execute function clear_deletion();
+create or replace trigger service_plans_created after insert on service_plans
+    for each row execute function clear_deletion();
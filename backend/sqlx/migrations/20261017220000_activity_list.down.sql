@@ -0,0 +1,3 @@
+-- Add down migration script here
+drop index if exists idx_activities_user_distance;
+drop index if exists idx_activities_user_start;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+create index if not exists idx_activities_user_start on activities(user_id, start, id);
+create index if not exists idx_activities_user_distance on activities(user_id, (coalesce(distance, 0)), id);
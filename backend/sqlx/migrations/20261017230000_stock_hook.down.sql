@@ -0,0 +1,2 @@
+-- Add down migration script here
+alter table stock drop column if exists hook;
@@ -0,0 +1,2 @@
+-- Add up migration script here
+alter table stock add column if not exists hook integer references part_types(id);
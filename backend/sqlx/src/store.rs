@@ -3,11 +3,20 @@ use tb_domain::{Store, TbResult};
 
 mod activity;
 mod attachment;
+mod audit;
+mod configuration;
+mod gearrule;
+mod notification;
 mod part;
 mod service;
 mod serviceplan;
 mod shop;
+mod stock;
+mod sync;
+mod types;
+mod undo;
 mod usage;
+mod usagerule;
 mod user;
 
 #[async_trait::async_trait]
@@ -1,7 +1,10 @@
 use crate::{SqlxConn, into_domain, vec_into};
 use anyhow::Context;
-use sqlx::FromRow;
-use tb_domain::{ActTypeId, Activity, ActivityId, PartId, TbResult, UserId};
+use sqlx::{FromRow, QueryBuilder};
+use tb_domain::{
+    ActTypeId, Activity, ActivityCursor, ActivityId, ActivityQuery, ActivitySort, PartId,
+    SortOrder, SyncCursor, TbResult, UserId,
+};
 use time::{OffsetDateTime, UtcOffset};
 
 #[derive(Debug, Clone, FromRow, PartialEq)]
@@ -28,6 +31,8 @@ struct DbActivity {
     energy: Option<i32>,
     /// Which gear did she use?
     gear: Option<i32>,
+    /// Further gears
+    gears: Vec<i32>,
     /// utc offset since timstamptz does not store the timezone
     utc_offset: i32,
     /// The primary key
@@ -35,6 +40,8 @@ struct DbActivity {
     /// device name
     device_name: Option<String>,
     external_id: Option<String>,
+    /// the transaction of the last change, maintained by the database
+    version: i64,
 }
 
 impl From<Activity> for DbActivity {
@@ -52,6 +59,7 @@ impl From<Activity> for DbActivity {
             descend,
             energy,
             gear,
+            gears,
             device_name,
             external_id,
         } = v;
@@ -69,9 +77,11 @@ impl From<Activity> for DbActivity {
             descend,
             energy,
             gear: gear.map(Into::into),
+            gears: vec_into(gears),
             utc_offset,
             device_name,
             external_id,
+            version: 0,
         }
     }
 }
@@ -93,9 +103,11 @@ impl TryFrom<DbActivity> for Activity {
             descend,
             energy,
             gear,
+            gears,
             utc_offset,
             device_name,
             external_id,
+            version: _,
         } = v;
         let utc_offset = ((utc_offset + 900) / 1800) * 1800; //round it to 1800s
         let offset = UtcOffset::from_whole_seconds(utc_offset).context("Utc Offset invalid")?;
@@ -114,6 +126,7 @@ impl TryFrom<DbActivity> for Activity {
             descend,
             energy,
             gear: gear.map(Into::into),
+            gears: vec_into(gears),
             device_name,
             external_id,
         })
@@ -133,8 +146,8 @@ impl<'c> tb_domain::ActivityStore for SqlxConn<'c> {
         let values = DbActivity::from(act);
         sqlx::query_as!(
             DbActivity,
-            "INSERT INTO activities (id, user_id, what, name, start, duration, time, distance, climb, descend, energy, gear, utc_offset, device_name, external_id)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            "INSERT INTO activities (id, user_id, what, name, start, duration, time, distance, climb, descend, energy, gear, gears, utc_offset, device_name, external_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
              RETURNING *",
             values.id,
             values.user_id,
@@ -148,6 +161,7 @@ impl<'c> tb_domain::ActivityStore for SqlxConn<'c> {
             values.descend,
             values.energy,
             values.gear,
+            &values.gears,
             values.utc_offset,
             values.device_name,
             values.external_id
@@ -179,7 +193,7 @@ impl<'c> tb_domain::ActivityStore for SqlxConn<'c> {
             DbActivity,
             "UPDATE activities
              SET user_id = $2, what = $3, name = $4, start = $5, duration = $6, time = $7,
-                 distance = $8, climb = $9, descend = $10, energy = $11, gear = $12
+                 distance = $8, climb = $9, descend = $10, energy = $11, gear = $12, gears = $13
              WHERE id = $1
              RETURNING *",
             act.id,
@@ -194,6 +208,7 @@ impl<'c> tb_domain::ActivityStore for SqlxConn<'c> {
             act.descend,
             act.energy,
             act.gear,
+            &act.gears,
         )
         .fetch_one(&mut **self.inner())
         .await
@@ -231,7 +246,7 @@ impl<'c> tb_domain::ActivityStore for SqlxConn<'c> {
         vec_tryinto(
             sqlx::query_as!(
                 DbActivity,
-                "SELECT * FROM activities WHERE gear = $1 AND start >= $2 AND start < $3",
+                "SELECT * FROM activities WHERE (gear = $1 OR $1 = ANY(gears)) AND start >= $2 AND start < $3",
                 i32::from(part),
                 begin,
                 end
@@ -261,6 +276,34 @@ impl<'c> tb_domain::ActivityStore for SqlxConn<'c> {
         .try_into()
     }
 
+    async fn activity_get_by_user_and_start(
+        &mut self,
+        uid: UserId,
+        start: OffsetDateTime,
+    ) -> TbResult<Option<Activity>> {
+        sqlx::query_as!(
+            DbActivity,
+            "SELECT * FROM activities
+             WHERE user_id = $1 AND date_trunc('minute', start) = date_trunc('minute', $2::timestamptz)
+             FOR UPDATE",
+            i32::from(uid),
+            start
+        )
+        .fetch_optional(&mut **self.inner())
+        .await
+        .map_err(into_domain)?
+        .map(TryInto::try_into)
+        .transpose()
+    }
+
+    async fn activity_new_id(&mut self) -> TbResult<ActivityId> {
+        sqlx::query_scalar!("SELECT nextval('activities_upload_id_seq') AS \"id!\"")
+            .fetch_one(&mut **self.inner())
+            .await
+            .map_err(into_domain)
+            .map(ActivityId::new)
+    }
+
     async fn activity_set_gear_if_null(
         &mut self,
         user: UserId,
@@ -302,4 +345,108 @@ impl<'c> tb_domain::ActivityStore for SqlxConn<'c> {
 
         Ok(result.rows_affected() as usize)
     }
+
+    async fn activities_changed(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<Activity>> {
+        vec_tryinto(
+            sqlx::query_as!(
+                DbActivity,
+                "SELECT * FROM activities WHERE user_id = $1 AND version >= $2 ORDER BY start",
+                i32::from(user),
+                i64::from(since)
+            )
+            .fetch_all(&mut **self.inner())
+            .await,
+        )
+    }
+
+    async fn activities_query(
+        &mut self,
+        user: UserId,
+        query: &ActivityQuery,
+        limit: i64,
+    ) -> TbResult<Vec<Activity>> {
+        let mut sql = QueryBuilder::new("SELECT * FROM activities WHERE user_id = ");
+        sql.push_bind(i32::from(user));
+        if let Some(from) = query.from {
+            sql.push(" AND start >= ").push_bind(from);
+        }
+        if let Some(to) = query.to {
+            sql.push(" AND start < ").push_bind(to);
+        }
+        if let Some(what) = query.what {
+            sql.push(" AND what = ").push_bind(i32::from(what));
+        }
+        if let Some(gear) = query.gear {
+            let gear = i32::from(gear);
+            sql.push(" AND (gear = ")
+                .push_bind(gear)
+                .push(" OR ")
+                .push_bind(gear)
+                .push(" = ANY(gears))");
+        }
+        if let Some(part) = query.part {
+            let part = i32::from(part);
+            sql.push(" AND (gear = ")
+                .push_bind(part)
+                .push(" OR ")
+                .push_bind(part)
+                .push(
+                    " = ANY(gears) OR EXISTS (
+                   SELECT 1 FROM attachments a
+                   WHERE a.part_id = ",
+                )
+                .push_bind(part)
+                .push(
+                    " AND (a.gear = activities.gear OR a.gear = ANY(activities.gears))
+                     AND a.attached <= activities.start AND a.detached > activities.start))",
+                );
+        }
+        if let Some(min) = query.min_distance {
+            sql.push(" AND coalesce(distance, 0) >= ").push_bind(min);
+        }
+        if let Some(max) = query.max_distance {
+            sql.push(" AND coalesce(distance, 0) <= ").push_bind(max);
+        }
+        if let Some(device_name) = &query.device_name {
+            sql.push(" AND lower(device_name) = lower(")
+                .push_bind(device_name.clone())
+                .push(")");
+        }
+
+        // the keys match the indexes on activities
+        let key = match query.sort {
+            ActivitySort::Start => "start",
+            ActivitySort::Distance => "coalesce(distance, 0)",
+        };
+        let (cmp, dir) = match query.order {
+            SortOrder::Asc => (">", ""),
+            SortOrder::Desc => ("<", " DESC"),
+        };
+        if let Some(after) = query.after {
+            sql.push(format_args!(" AND ({key}, id) {cmp} ("));
+            let id = match after {
+                ActivityCursor::Start(start, id) => {
+                    sql.push_bind(start);
+                    id
+                }
+                ActivityCursor::Distance(distance, id) => {
+                    sql.push_bind(distance);
+                    id
+                }
+            };
+            sql.push(", ").push_bind(i64::from(id)).push(")");
+        }
+        sql.push(format_args!(" ORDER BY {key}{dir}, id{dir} LIMIT "))
+            .push_bind(limit);
+
+        vec_tryinto(
+            sql.build_query_as::<DbActivity>()
+                .fetch_all(&mut **self.inner())
+                .await,
+        )
+    }
 }
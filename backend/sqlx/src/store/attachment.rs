@@ -3,7 +3,7 @@ use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{SqlxConn, into_domain, vec_into};
-use tb_domain::{Attachment, PartId, PartTypeId, TbResult};
+use tb_domain::{Attachment, PartId, PartTypeId, SyncCursor, TbResult, UserId};
 
 #[derive(Clone, Copy, Debug, PartialEq, FromRow)]
 pub struct DbAttachment {
@@ -13,6 +13,8 @@ pub struct DbAttachment {
     hook: i32,
     detached: OffsetDateTime,
     usage: Uuid,
+    /// the transaction of the last change, maintained by the database
+    version: i64,
 }
 
 impl From<Attachment> for DbAttachment {
@@ -32,6 +34,7 @@ impl From<Attachment> for DbAttachment {
             hook: hook.into(),
             detached,
             usage: usage.into(),
+            version: 0,
         }
     }
 }
@@ -44,6 +47,7 @@ impl From<DbAttachment> for Attachment {
             hook,
             detached,
             usage,
+            version: _,
         } = value;
         Self {
             part_id: part_id.into(),
@@ -127,6 +131,19 @@ impl<'c> tb_domain::AttachmentStore for SqlxConn<'c> {
         .map(vec_into)
     }
 
+    async fn attachments_all_by_gear(&mut self, gear: PartId) -> TbResult<Vec<Attachment>> {
+        sqlx::query_as!(
+            DbAttachment,
+            "SELECT * FROM attachments
+             WHERE gear = $1",
+            i32::from(gear)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
     async fn attachment_get_by_part_and_time(
         &mut self,
         pid: PartId,
@@ -300,4 +317,22 @@ impl<'c> tb_domain::AttachmentStore for SqlxConn<'c> {
 
         Ok(result.rows_affected() as usize)
     }
+
+    async fn attachments_changed(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<Attachment>> {
+        sqlx::query_as!(
+            DbAttachment,
+            "SELECT a.* FROM attachments a JOIN parts p ON a.part_id = p.id
+             WHERE p.owner = $1 AND a.version >= $2",
+            i32::from(user),
+            i64::from(since)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
 }
@@ -0,0 +1,158 @@
+use serde_json::Value;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use crate::{SqlxConn, into_domain, vec_into};
+use tb_domain::{AuditEntry, AuditId, PartId, TbResult, UserId};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbAuditEntry {
+    id: i64,
+    time: OffsetDateTime,
+    actor: i32,
+    user_id: i32,
+    entity: String,
+    entity_id: String,
+    part_id: Option<i32>,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+impl From<DbAuditEntry> for AuditEntry {
+    fn from(value: DbAuditEntry) -> Self {
+        let DbAuditEntry {
+            id,
+            time,
+            actor,
+            user_id,
+            entity,
+            entity_id,
+            part_id,
+            before,
+            after,
+        } = value;
+        // the database only holds valid json
+        let json = |s: Option<String>| s.and_then(|s| serde_json::from_str(&s).ok());
+        Self {
+            id: id.into(),
+            time,
+            actor: actor.into(),
+            user: user_id.into(),
+            entity,
+            entity_id,
+            part: part_id.map(Into::into),
+            before: json(before),
+            after: json(after),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::AuditStore for SqlxConn<'c> {
+    async fn audit_append(
+        &mut self,
+        actor: UserId,
+        user: UserId,
+        entity: &str,
+        entity_id: String,
+        part: Option<PartId>,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> TbResult<()> {
+        sqlx::query!(
+            "INSERT INTO audit_log (actor, user_id, entity, entity_id, part_id, before, after)
+             VALUES ($1, $2, $3, $4, $5, $6::text::jsonb, $7::text::jsonb)",
+            i32::from(actor),
+            i32::from(user),
+            entity,
+            entity_id,
+            part.map(i32::from),
+            before.map(|v| v.to_string()),
+            after.map(|v| v.to_string()),
+        )
+        .execute(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        Ok(())
+    }
+
+    async fn audit_for_part(
+        &mut self,
+        part: PartId,
+        before: Option<AuditId>,
+        limit: i64,
+    ) -> TbResult<Vec<AuditEntry>> {
+        sqlx::query_as!(
+            DbAuditEntry,
+            r#"SELECT id, time, actor, user_id, entity, entity_id, part_id,
+                before::text AS "before", after::text AS "after"
+             FROM audit_log
+             WHERE part_id = $1 AND ($2::bigint IS NULL OR id < $2)
+             ORDER BY id DESC
+             LIMIT $3"#,
+            i32::from(part),
+            before.map(i64::from),
+            limit
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
+    async fn audit_part_disposals(&mut self, part: PartId) -> TbResult<Vec<AuditEntry>> {
+        sqlx::query_as!(
+            DbAuditEntry,
+            r#"SELECT id, time, actor, user_id, entity, entity_id, part_id,
+                before::text AS "before", after::text AS "after"
+             FROM audit_log
+             WHERE part_id = $1 AND entity = 'part' AND entity_id = $1::text
+                AND (before -> 'disposed_at') IS DISTINCT FROM (after -> 'disposed_at')
+             ORDER BY id"#,
+            i32::from(part)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
+    async fn audit_for_user(
+        &mut self,
+        user: UserId,
+        before: Option<AuditId>,
+        limit: i64,
+    ) -> TbResult<Vec<AuditEntry>> {
+        sqlx::query_as!(
+            DbAuditEntry,
+            r#"SELECT id, time, actor, user_id, entity, entity_id, part_id,
+                before::text AS "before", after::text AS "after"
+             FROM audit_log
+             WHERE user_id = $1 AND ($2::bigint IS NULL OR id < $2)
+             ORDER BY id DESC
+             LIMIT $3"#,
+            i32::from(user),
+            before.map(i64::from),
+            limit
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
+    async fn audit_for_transaction(&mut self) -> TbResult<Vec<AuditEntry>> {
+        sqlx::query_as!(
+            DbAuditEntry,
+            r#"SELECT id, time, actor, user_id, entity, entity_id, part_id,
+                before::text AS "before", after::text AS "after"
+             FROM audit_log
+             WHERE tx = txid_current()
+             ORDER BY id"#
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+}
@@ -0,0 +1,157 @@
+use sqlx::FromRow;
+
+use crate::{SqlxConn, into_domain};
+use tb_domain::{ConfigPart, Configuration, ConfigurationId, PartId, TbResult, UserId};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbConfiguration {
+    id: i32,
+    owner: i32,
+    gear: i32,
+    name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbConfigPart {
+    configuration: i32,
+    part: i32,
+    hook: i32,
+}
+
+impl DbConfiguration {
+    fn with_parts(self, parts: &[DbConfigPart]) -> Configuration {
+        let DbConfiguration {
+            id,
+            owner,
+            gear,
+            name,
+        } = self;
+        Configuration {
+            id: id.into(),
+            owner: owner.into(),
+            gear: gear.into(),
+            name,
+            parts: parts
+                .iter()
+                .filter(|p| p.configuration == id)
+                .map(|p| ConfigPart {
+                    part: p.part.into(),
+                    hook: p.hook.into(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'c> SqlxConn<'c> {
+    async fn configuration_parts(&mut self, ids: &[i32]) -> TbResult<Vec<DbConfigPart>> {
+        sqlx::query_as!(
+            DbConfigPart,
+            "SELECT * FROM configuration_parts WHERE configuration = ANY($1) ORDER BY part",
+            ids
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+    }
+
+    async fn configuration_set_parts(&mut self, id: i32, parts: &[ConfigPart]) -> TbResult<()> {
+        let (part, hook): (Vec<i32>, Vec<i32>) = parts
+            .iter()
+            .map(|p| (i32::from(p.part), i32::from(p.hook)))
+            .unzip();
+        sqlx::query!(
+            "DELETE FROM configuration_parts WHERE configuration = $1",
+            id
+        )
+        .execute(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        sqlx::query!(
+            "INSERT INTO configuration_parts (configuration, part, hook)
+             SELECT $1, * FROM UNNEST($2::int[], $3::int[])",
+            id,
+            &part,
+            &hook
+        )
+        .execute(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::ConfigurationStore for SqlxConn<'c> {
+    async fn configuration_create(
+        &mut self,
+        owner: UserId,
+        gear: PartId,
+        name: String,
+        parts: Vec<ConfigPart>,
+    ) -> TbResult<Configuration> {
+        let config = sqlx::query_as!(
+            DbConfiguration,
+            "INSERT INTO configurations (owner, gear, name) VALUES ($1, $2, $3) RETURNING *",
+            i32::from(owner),
+            i32::from(gear),
+            name
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        self.configuration_set_parts(config.id, &parts).await?;
+        let parts = self.configuration_parts(&[config.id]).await?;
+        Ok(config.with_parts(&parts))
+    }
+
+    async fn configuration_get(&mut self, id: ConfigurationId) -> TbResult<Configuration> {
+        let config = sqlx::query_as!(
+            DbConfiguration,
+            "SELECT * FROM configurations WHERE id = $1",
+            i32::from(id)
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        let parts = self.configuration_parts(&[config.id]).await?;
+        Ok(config.with_parts(&parts))
+    }
+
+    async fn configuration_update(&mut self, config: Configuration) -> TbResult<Configuration> {
+        let db = sqlx::query_as!(
+            DbConfiguration,
+            "UPDATE configurations SET name = $2 WHERE id = $1 RETURNING *",
+            i32::from(config.id),
+            config.name
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        self.configuration_set_parts(db.id, &config.parts).await?;
+        let parts = self.configuration_parts(&[db.id]).await?;
+        Ok(db.with_parts(&parts))
+    }
+
+    async fn configuration_delete(&mut self, id: ConfigurationId) -> TbResult<usize> {
+        let result = sqlx::query!("DELETE FROM configurations WHERE id = $1", i32::from(id))
+            .execute(&mut **self.inner())
+            .await
+            .map_err(into_domain)?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn configurations_for_user(&mut self, user: UserId) -> TbResult<Vec<Configuration>> {
+        let configs = sqlx::query_as!(
+            DbConfiguration,
+            "SELECT * FROM configurations WHERE owner = $1 ORDER BY gear, name",
+            i32::from(user)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        let ids: Vec<_> = configs.iter().map(|c| c.id).collect();
+        let parts = self.configuration_parts(&ids).await?;
+        Ok(configs.into_iter().map(|c| c.with_parts(&parts)).collect())
+    }
+}
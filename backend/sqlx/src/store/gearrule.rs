@@ -0,0 +1,175 @@
+use sqlx::FromRow;
+use time::Time;
+
+use crate::{SqlxConn, into_domain, vec_into};
+use tb_domain::{GearMatch, GearRule, GearRuleId, PartId, TbResult, UserId};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbGearRule {
+    id: i32,
+    owner: i32,
+    position: i32,
+    gear: i32,
+    what: Option<i32>,
+    device_name: Option<String>,
+    keyword: Option<String>,
+    weekdays: Vec<i32>,
+    time_from: Option<Time>,
+    time_until: Option<Time>,
+    min_distance: Option<i32>,
+    max_distance: Option<i32>,
+}
+
+impl From<DbGearRule> for GearRule {
+    fn from(value: DbGearRule) -> Self {
+        let DbGearRule {
+            id,
+            owner,
+            position,
+            gear,
+            what,
+            device_name,
+            keyword,
+            weekdays,
+            time_from,
+            time_until,
+            min_distance,
+            max_distance,
+        } = value;
+        Self {
+            id: id.into(),
+            owner: owner.into(),
+            position,
+            gear: gear.into(),
+            conditions: GearMatch {
+                what: what.map(Into::into),
+                device_name,
+                keyword,
+                weekdays,
+                from: time_from,
+                until: time_until,
+                min_distance,
+                max_distance,
+            },
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::GearRuleStore for SqlxConn<'c> {
+    async fn gear_rule_create(
+        &mut self,
+        owner: UserId,
+        position: i32,
+        gear: PartId,
+        conditions: GearMatch,
+    ) -> TbResult<GearRule> {
+        let GearMatch {
+            what,
+            device_name,
+            keyword,
+            weekdays,
+            from,
+            until,
+            min_distance,
+            max_distance,
+        } = conditions;
+        sqlx::query_as!(
+            DbGearRule,
+            "INSERT INTO gear_rules (owner, position, gear, what, device_name, keyword, weekdays,
+                time_from, time_until, min_distance, max_distance)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             RETURNING *",
+            i32::from(owner),
+            position,
+            i32::from(gear),
+            what.map(i32::from),
+            device_name,
+            keyword,
+            &weekdays,
+            from,
+            until,
+            min_distance,
+            max_distance
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn gear_rule_get(&mut self, id: GearRuleId) -> TbResult<GearRule> {
+        sqlx::query_as!(
+            DbGearRule,
+            "SELECT * FROM gear_rules WHERE id = $1",
+            i32::from(id)
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn gear_rule_update(&mut self, rule: GearRule) -> TbResult<GearRule> {
+        let GearRule {
+            id,
+            position,
+            gear,
+            conditions:
+                GearMatch {
+                    what,
+                    device_name,
+                    keyword,
+                    weekdays,
+                    from,
+                    until,
+                    min_distance,
+                    max_distance,
+                },
+            ..
+        } = rule;
+        sqlx::query_as!(
+            DbGearRule,
+            "UPDATE gear_rules
+             SET position = $2, gear = $3, what = $4, device_name = $5, keyword = $6, weekdays = $7,
+                 time_from = $8, time_until = $9, min_distance = $10, max_distance = $11
+             WHERE id = $1
+             RETURNING *",
+            i32::from(id),
+            position,
+            i32::from(gear),
+            what.map(i32::from),
+            device_name,
+            keyword,
+            &weekdays,
+            from,
+            until,
+            min_distance,
+            max_distance
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn gear_rule_delete(&mut self, id: GearRuleId) -> TbResult<usize> {
+        let result = sqlx::query!("DELETE FROM gear_rules WHERE id = $1", i32::from(id))
+            .execute(&mut **self.inner())
+            .await
+            .map_err(into_domain)?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn gear_rules_for_user(&mut self, user: UserId) -> TbResult<Vec<GearRule>> {
+        sqlx::query_as!(
+            DbGearRule,
+            "SELECT * FROM gear_rules WHERE owner = $1 ORDER BY position, id",
+            i32::from(user)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+}
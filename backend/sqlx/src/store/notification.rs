@@ -0,0 +1,149 @@
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use tb_domain::{DueState, NotifyPrefs, PartId, ServicePlanId, TbResult, UserId};
+
+use crate::{SqlxConn, into_domain, vec_into};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbNotifyPrefs {
+    user_id: i32,
+    email: Option<String>,
+    webhook: Option<String>,
+    soon: bool,
+    overdue: bool,
+}
+
+impl From<NotifyPrefs> for DbNotifyPrefs {
+    fn from(value: NotifyPrefs) -> Self {
+        let NotifyPrefs {
+            user,
+            email,
+            webhook,
+            soon,
+            overdue,
+        } = value;
+        Self {
+            user_id: user.into(),
+            email,
+            webhook,
+            soon,
+            overdue,
+        }
+    }
+}
+
+impl From<DbNotifyPrefs> for NotifyPrefs {
+    fn from(value: DbNotifyPrefs) -> Self {
+        let DbNotifyPrefs {
+            user_id,
+            email,
+            webhook,
+            soon,
+            overdue,
+        } = value;
+        Self {
+            user: user_id.into(),
+            email,
+            webhook,
+            soon,
+            overdue,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::NotificationStore for SqlxConn<'c> {
+    async fn notify_prefs_get(&mut self, user: UserId) -> TbResult<Option<NotifyPrefs>> {
+        sqlx::query_as!(
+            DbNotifyPrefs,
+            "SELECT user_id, email, webhook, soon, overdue FROM notify_prefs WHERE user_id = $1",
+            i32::from(user)
+        )
+        .fetch_optional(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(|p| p.map(Into::into))
+    }
+
+    async fn notify_prefs_set(&mut self, prefs: NotifyPrefs) -> TbResult<NotifyPrefs> {
+        let prefs = DbNotifyPrefs::from(prefs);
+        sqlx::query_as!(
+            DbNotifyPrefs,
+            "INSERT INTO notify_prefs (user_id, email, webhook, soon, overdue)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (user_id) DO UPDATE
+             SET email = $2, webhook = $3, soon = $4, overdue = $5
+             RETURNING user_id, email, webhook, soon, overdue",
+            prefs.user_id,
+            prefs.email,
+            prefs.webhook,
+            prefs.soon,
+            prefs.overdue
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn notify_prefs_all(&mut self) -> TbResult<Vec<NotifyPrefs>> {
+        sqlx::query_as!(
+            DbNotifyPrefs,
+            "SELECT user_id, email, webhook, soon, overdue FROM notify_prefs ORDER BY user_id"
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
+    async fn notice_get(
+        &mut self,
+        plan: ServicePlanId,
+        part: PartId,
+    ) -> TbResult<Option<DueState>> {
+        let state = sqlx::query_scalar!(
+            "SELECT state FROM notices WHERE plan = $1 AND part = $2",
+            Uuid::from(plan),
+            i32::from(part)
+        )
+        .fetch_optional(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        state.map(DueState::try_from).transpose()
+    }
+
+    async fn notice_set(
+        &mut self,
+        plan: ServicePlanId,
+        part: PartId,
+        state: DueState,
+    ) -> TbResult<()> {
+        sqlx::query!(
+            "INSERT INTO notices (plan, part, state)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (plan, part) DO UPDATE
+             SET state = $3",
+            Uuid::from(plan),
+            i32::from(part),
+            i32::from(state)
+        )
+        .execute(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(|_| ())
+    }
+
+    async fn notice_delete(&mut self, plan: ServicePlanId, part: PartId) -> TbResult<()> {
+        sqlx::query!(
+            "DELETE FROM notices WHERE plan = $1 AND part = $2",
+            Uuid::from(plan),
+            i32::from(part)
+        )
+        .execute(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(|_| ())
+    }
+}
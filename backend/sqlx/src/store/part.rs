@@ -3,7 +3,7 @@ use time::OffsetDateTime;
 use uuid::Uuid;
 
 use crate::{SqlxConn, into_domain, option_into, vec_into};
-use tb_domain::{Part, PartId, PartTypeId, ShopId, TbResult, UsageId, UserId};
+use tb_domain::{Money, Part, PartId, PartTypeId, ShopId, SyncCursor, TbResult, UsageId, UserId};
 
 /// The database's representation of a part.
 #[derive(Clone, Debug, PartialEq, FromRow)]
@@ -21,6 +21,10 @@ struct DbPart {
     source: Option<String>,
     notes: String,
     shop: Option<i32>,
+    price: Option<i32>,
+    currency: Option<String>,
+    /// the transaction of the last change, maintained by the database
+    version: i64,
 }
 
 impl From<DbPart> for Part {
@@ -39,6 +43,9 @@ impl From<DbPart> for Part {
             source,
             notes,
             shop,
+            price,
+            currency,
+            version: _,
         } = db;
         Self {
             id: id.into(),
@@ -54,6 +61,9 @@ impl From<DbPart> for Part {
             source,
             notes,
             shop: shop.map(Into::into),
+            price: price
+                .zip(currency)
+                .map(|(amount, currency)| Money { amount, currency }),
         }
     }
 }
@@ -74,7 +84,9 @@ impl From<Part> for DbPart {
             source,
             notes,
             shop,
+            price,
         } = value;
+        let (price, currency) = price.map(|p| (p.amount, p.currency)).unzip();
         Self {
             id: id.into(),
             owner: owner.into(),
@@ -89,6 +101,9 @@ impl From<Part> for DbPart {
             source,
             notes,
             shop: shop.map(Into::into),
+            price,
+            currency,
+            version: 0,
         }
     }
 }
@@ -124,15 +139,17 @@ impl<'c> tb_domain::PartStore for SqlxConn<'c> {
         in_purchase: OffsetDateTime,
         in_source: Option<String>,
         in_notes: String,
+        in_price: Option<Money>,
         in_usage: UsageId,
         in_owner: UserId,
         in_shop: Option<ShopId>,
     ) -> TbResult<Part> {
         let in_shop: Option<i32> = in_shop.map(Into::into);
+        let (in_price, in_currency) = in_price.map(|p| (p.amount, p.currency)).unzip();
         sqlx::query_as!(
             DbPart,
-            "INSERT INTO parts (owner, what, name, vendor, model, purchase, last_used, usage, source, notes, shop)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "INSERT INTO parts (owner, what, name, vendor, model, purchase, last_used, usage, source, notes, shop, price, currency)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
              RETURNING *",
             i32::from(in_owner),
             i32::from(in_what),
@@ -144,7 +161,9 @@ impl<'c> tb_domain::PartStore for SqlxConn<'c> {
             Uuid::from(in_usage),
             in_source,
             in_notes,
-            in_shop
+            in_shop,
+            in_price,
+            in_currency
         )
         .fetch_one(&mut **self.inner())
         .await
@@ -158,7 +177,8 @@ impl<'c> tb_domain::PartStore for SqlxConn<'c> {
             DbPart,
             "UPDATE parts
              SET owner = $2, what = $3, name = $4, vendor = $5, model = $6,
-                 purchase = $7, last_used = $8, disposed_at = $9, usage = $10, source = $11, notes = $12, shop = $13
+                 purchase = $7, last_used = $8, disposed_at = $9, usage = $10, source = $11, notes = $12, shop = $13,
+                 price = $14, currency = $15
              WHERE id = $1
              RETURNING *",
             part.id,
@@ -173,7 +193,9 @@ impl<'c> tb_domain::PartStore for SqlxConn<'c> {
             part.usage,
             part.source,
             part.notes,
-            part.shop
+            part.shop,
+            part.price,
+            part.currency
         )
         .fetch_one(&mut **self.inner())
         .await
@@ -259,4 +281,17 @@ impl<'c> tb_domain::PartStore for SqlxConn<'c> {
         .map_err(into_domain)
         .map(vec_into)
     }
+
+    async fn parts_changed(&mut self, user: UserId, since: SyncCursor) -> TbResult<Vec<Part>> {
+        sqlx::query_as!(
+            DbPart,
+            "SELECT * FROM parts WHERE owner = $1 AND version >= $2 ORDER BY last_used",
+            i32::from(user),
+            i64::from(since)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
 }
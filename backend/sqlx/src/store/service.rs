@@ -1,7 +1,7 @@
 use crate::{SqlxConn, into_domain, vec_into};
 use ::time::OffsetDateTime;
 use sqlx::FromRow;
-use tb_domain::{PartId, Service, ServiceId, TbResult};
+use tb_domain::{Money, PartId, Service, ServiceId, SyncCursor, TbResult, UserId};
 use uuid::Uuid;
 
 #[derive(Clone, Debug, FromRow, PartialEq, Eq)]
@@ -22,6 +22,9 @@ pub struct DbService {
     successor: Option<Uuid>,
     // an optional ServicePlan it is fullfilling
     plans: Vec<Uuid>,
+    // the cost of the service
+    cost: Option<i32>,
+    cost_currency: Option<String>,
 }
 
 impl From<Service> for DbService {
@@ -36,7 +39,9 @@ impl From<Service> for DbService {
             usage,
             successor,
             plans,
+            cost,
         } = value;
+        let (cost, cost_currency) = cost.map(|c| (c.amount, c.currency)).unzip();
         DbService {
             id: id.into(),
             part_id: part_id.into(),
@@ -47,6 +52,8 @@ impl From<Service> for DbService {
             usage: usage.into(),
             successor: successor.map(Into::into),
             plans: vec_into(plans),
+            cost,
+            cost_currency,
         }
     }
 }
@@ -63,6 +70,8 @@ impl From<DbService> for Service {
             usage,
             successor,
             plans,
+            cost,
+            cost_currency,
         } = value;
         Service {
             id: id.into(),
@@ -74,6 +83,9 @@ impl From<DbService> for Service {
             usage: usage.into(),
             successor: successor.map(Into::into),
             plans: vec_into(plans),
+            cost: cost
+                .zip(cost_currency)
+                .map(|(amount, currency)| Money { amount, currency }),
         }
     }
 }
@@ -84,9 +96,9 @@ impl<'c> tb_domain::ServiceStore for SqlxConn<'c> {
         let service: DbService = service.into();
         sqlx::query_as!(
             DbService,
-            r#"INSERT INTO services (id, part_id, time, redone, name, notes, usage, successor, plans)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-             RETURNING id, part_id, time, redone, name, notes, usage, successor, plans as "plans!""#,
+            r#"INSERT INTO services (id, part_id, time, redone, name, notes, usage, successor, plans, cost, cost_currency)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             RETURNING id, part_id, time, redone, name, notes, usage, successor, plans as "plans!", cost, cost_currency"#,
             service.id,
             service.part_id,
             service.time,
@@ -95,7 +107,9 @@ impl<'c> tb_domain::ServiceStore for SqlxConn<'c> {
             service.notes,
             service.usage,
             service.successor,
-            &service.plans as _
+            &service.plans as _,
+            service.cost,
+            service.cost_currency
         )
         .fetch_one(&mut **self.inner())
         .await
@@ -106,7 +120,7 @@ impl<'c> tb_domain::ServiceStore for SqlxConn<'c> {
     async fn get(&mut self, service: ServiceId) -> TbResult<Service> {
         sqlx::query_as!(
             DbService,
-            r#"SELECT id, part_id, time, redone, name, notes, usage, successor, plans as "plans!" FROM services WHERE id = $1"#,
+            r#"SELECT id, part_id, time, redone, name, notes, usage, successor, plans as "plans!", cost, cost_currency FROM services WHERE id = $1"#,
             Uuid::from(service)
         )
         .fetch_one(&mut **self.inner())
@@ -121,9 +135,10 @@ impl<'c> tb_domain::ServiceStore for SqlxConn<'c> {
             DbService,
             r#"UPDATE services
              SET part_id = $2, time = $3, redone = $4, name = $5, notes = $6,
-                 usage = $7, successor = $8, plans = $9
+                 usage = $7, successor = $8, plans = $9,
+                 cost = $10, cost_currency = $11
              WHERE id = $1
-             RETURNING id, part_id, time, redone, name, notes, usage, successor, plans as "plans!""#,
+             RETURNING id, part_id, time, redone, name, notes, usage, successor, plans as "plans!", cost, cost_currency"#,
             service.id,
             service.part_id,
             service.time,
@@ -132,7 +147,9 @@ impl<'c> tb_domain::ServiceStore for SqlxConn<'c> {
             service.notes,
             service.usage,
             service.successor,
-            &service.plans as _
+            &service.plans as _,
+            service.cost,
+            service.cost_currency
         )
         .fetch_one(&mut **self.inner())
         .await
@@ -152,7 +169,7 @@ impl<'c> tb_domain::ServiceStore for SqlxConn<'c> {
     async fn services_by_part(&mut self, part: PartId) -> TbResult<Vec<Service>> {
         sqlx::query_as!(
             DbService,
-            r#"SELECT id, part_id, time, redone, name, notes, usage, successor, plans as "plans!" FROM services WHERE part_id = $1"#,
+            r#"SELECT id, part_id, time, redone, name, notes, usage, successor, plans as "plans!", cost, cost_currency FROM services WHERE part_id = $1"#,
             i32::from(part)
         )
         .fetch_all(&mut **self.inner())
@@ -171,4 +188,23 @@ impl<'c> tb_domain::ServiceStore for SqlxConn<'c> {
 
         Ok(result.rows_affected() as usize)
     }
+
+    async fn services_changed(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<Service>> {
+        sqlx::query_as!(
+            DbService,
+            r#"SELECT s.id, s.part_id, s.time, s.redone, s.name, s.notes, s.usage, s.successor, s.plans as "plans!", s.cost, s.cost_currency
+               FROM services s JOIN parts p ON s.part_id = p.id
+               WHERE p.owner = $1 AND s.version >= $2"#,
+            i32::from(user),
+            i64::from(since)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
 }
@@ -1,6 +1,6 @@
 use sqlx::FromRow;
 
-use tb_domain::{PartId, ServicePlan, ServicePlanId, TbResult, UserId};
+use tb_domain::{PartId, ServicePlan, ServicePlanId, SyncCursor, TbResult, UserId};
 use uuid::Uuid;
 
 use crate::{SqlxConn, into_domain, vec_into};
@@ -20,6 +20,8 @@ struct DbServicePlan {
     pub rides: Option<i32>,
     pub uid: Option<i32>,
     pub energy: Option<i32>,
+    /// the transaction of the last change, maintained by the database
+    version: i64,
 }
 
 impl From<ServicePlan> for DbServicePlan {
@@ -53,6 +55,7 @@ impl From<ServicePlan> for DbServicePlan {
             rides,
             uid: uid.map(Into::into),
             energy,
+            version: 0,
         }
     }
 }
@@ -73,6 +76,7 @@ impl From<DbServicePlan> for ServicePlan {
             rides,
             uid,
             energy,
+            version: _,
         } = value;
         Self {
             id: id.into(),
@@ -205,4 +209,23 @@ impl<'c> tb_domain::ServicePlanStore for SqlxConn<'c> {
 
         Ok(result.rows_affected() as usize)
     }
+
+    async fn serviceplans_changed(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<ServicePlan>> {
+        sqlx::query_as!(
+            DbServicePlan,
+            "SELECT * FROM service_plans
+             WHERE (uid = $1 OR part IN (SELECT id FROM parts WHERE owner = $1))
+               AND version >= $2",
+            i32::from(user),
+            i64::from(since)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
 }
@@ -0,0 +1,181 @@
+use sqlx::FromRow;
+
+use crate::{SqlxConn, into_domain, vec_into};
+use tb_domain::{Money, PartTypeId, Stock, StockId, TbResult, UserId};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbStock {
+    id: i32,
+    owner: i32,
+    what: i32,
+    name: String,
+    vendor: String,
+    model: String,
+    quantity: i32,
+    min_quantity: i32,
+    price: Option<i32>,
+    currency: Option<String>,
+    notes: String,
+    hook: Option<i32>,
+}
+
+impl From<Stock> for DbStock {
+    fn from(value: Stock) -> Self {
+        let Stock {
+            id,
+            owner,
+            what,
+            name,
+            vendor,
+            model,
+            quantity,
+            min_quantity,
+            price,
+            notes,
+            hook,
+        } = value;
+        let (price, currency) = price.map(|p| (p.amount, p.currency)).unzip();
+        Self {
+            id: id.into(),
+            owner: owner.into(),
+            what: what.into(),
+            name,
+            vendor,
+            model,
+            quantity,
+            min_quantity,
+            price,
+            currency,
+            notes,
+            hook: hook.map(Into::into),
+        }
+    }
+}
+
+impl From<DbStock> for Stock {
+    fn from(value: DbStock) -> Self {
+        let DbStock {
+            id,
+            owner,
+            what,
+            name,
+            vendor,
+            model,
+            quantity,
+            min_quantity,
+            price,
+            currency,
+            notes,
+            hook,
+        } = value;
+        Self {
+            id: id.into(),
+            owner: owner.into(),
+            what: what.into(),
+            name,
+            vendor,
+            model,
+            quantity,
+            min_quantity,
+            price: price
+                .zip(currency)
+                .map(|(amount, currency)| Money { amount, currency }),
+            notes,
+            hook: hook.map(Into::into),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::StockStore for SqlxConn<'c> {
+    async fn stock_create(
+        &mut self,
+        owner: UserId,
+        what: PartTypeId,
+        hook: Option<PartTypeId>,
+        name: String,
+        vendor: String,
+        model: String,
+        quantity: i32,
+        min_quantity: i32,
+        price: Option<Money>,
+        notes: String,
+    ) -> TbResult<Stock> {
+        let (price, currency) = price.map(|p| (p.amount, p.currency)).unzip();
+        sqlx::query_as!(
+            DbStock,
+            "INSERT INTO stock (owner, what, name, vendor, model, quantity, min_quantity, price, currency, notes, hook)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+             RETURNING *",
+            i32::from(owner),
+            i32::from(what),
+            name,
+            vendor,
+            model,
+            quantity,
+            min_quantity,
+            price,
+            currency,
+            notes,
+            hook.map(i32::from)
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn stock_get(&mut self, id: StockId) -> TbResult<Stock> {
+        sqlx::query_as!(DbStock, "SELECT * FROM stock WHERE id = $1", i32::from(id))
+            .fetch_one(&mut **self.inner())
+            .await
+            .map_err(into_domain)
+            .map(Into::into)
+    }
+
+    async fn stock_update(&mut self, stock: Stock) -> TbResult<Stock> {
+        let stock = DbStock::from(stock);
+        sqlx::query_as!(
+            DbStock,
+            "UPDATE stock
+             SET name = $2, vendor = $3, model = $4, quantity = $5, min_quantity = $6,
+                 price = $7, currency = $8, notes = $9, hook = $10
+             WHERE id = $1
+             RETURNING *",
+            stock.id,
+            stock.name,
+            stock.vendor,
+            stock.model,
+            stock.quantity,
+            stock.min_quantity,
+            stock.price,
+            stock.currency,
+            stock.notes,
+            stock.hook
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn stock_delete(&mut self, id: StockId) -> TbResult<usize> {
+        let result = sqlx::query!("DELETE FROM stock WHERE id = $1", i32::from(id))
+            .execute(&mut **self.inner())
+            .await
+            .map_err(into_domain)?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn stocks_for_user(&mut self, user: UserId) -> TbResult<Vec<Stock>> {
+        sqlx::query_as!(
+            DbStock,
+            "SELECT * FROM stock WHERE owner = $1 ORDER BY what, name",
+            i32::from(user)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+}
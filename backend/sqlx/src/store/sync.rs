@@ -0,0 +1,76 @@
+use anyhow::{Context, anyhow};
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{SqlxConn, into_domain};
+use tb_domain::{Deletion, Error, SyncCursor, TbResult, UserId};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbDeletion {
+    entity: String,
+    entity_id: String,
+    attached: Option<OffsetDateTime>,
+}
+
+impl TryFrom<DbDeletion> for Deletion {
+    type Error = Error;
+
+    fn try_from(value: DbDeletion) -> TbResult<Self> {
+        let DbDeletion {
+            entity,
+            entity_id,
+            attached,
+        } = value;
+        let int = || {
+            entity_id
+                .parse::<i64>()
+                .context("invalid id of deleted entity")
+        };
+        let uuid = || Uuid::parse_str(&entity_id).context("invalid id of deleted entity");
+        Ok(match entity.as_str() {
+            "activity" => Deletion::Activity { id: int()?.into() },
+            "part" => Deletion::Part {
+                id: (int()? as i32).into(),
+            },
+            "attachment" => Deletion::Attachment {
+                part_id: (int()? as i32).into(),
+                attached: attached.context("deleted attachment without attach time")?,
+            },
+            "service" => Deletion::Service { id: uuid()?.into() },
+            "plan" => Deletion::Plan { id: uuid()?.into() },
+            _ => return Err(anyhow!("unknown deleted entity {entity}").into()),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::SyncStore for SqlxConn<'c> {
+    async fn sync_cursor(&mut self) -> TbResult<SyncCursor> {
+        sqlx::query_scalar!(r#"SELECT txid_snapshot_xmin(txid_current_snapshot()) AS "cursor!""#)
+            .fetch_one(&mut **self.inner())
+            .await
+            .map_err(into_domain)
+            .map(Into::into)
+    }
+
+    async fn deletions_since(
+        &mut self,
+        user: UserId,
+        since: SyncCursor,
+    ) -> TbResult<Vec<Deletion>> {
+        sqlx::query_as!(
+            DbDeletion,
+            "SELECT entity, entity_id, attached FROM deletions
+             WHERE owner = $1 AND version >= $2",
+            i32::from(user),
+            i64::from(since)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect()
+    }
+}
@@ -0,0 +1,238 @@
+use sqlx::FromRow;
+
+use tb_domain::{ActTypeId, ActivityType, PartType, PartTypeId, TbResult};
+
+use crate::{SqlxConn, into_domain, option_into, vec_into};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbPartType {
+    id: i32,
+    name: String,
+    main: i32,
+    hooks: Vec<i32>,
+    order: i32,
+    group: Option<String>,
+    owner: Option<i32>,
+}
+
+impl From<PartType> for DbPartType {
+    fn from(value: PartType) -> Self {
+        let PartType {
+            id,
+            name,
+            main,
+            hooks,
+            order,
+            group,
+            owner,
+        } = value;
+        Self {
+            id: id.into(),
+            name,
+            main: main.into(),
+            hooks: vec_into(hooks),
+            order,
+            group,
+            owner: option_into(owner),
+        }
+    }
+}
+
+impl From<DbPartType> for PartType {
+    fn from(value: DbPartType) -> Self {
+        let DbPartType {
+            id,
+            name,
+            main,
+            hooks,
+            order,
+            group,
+            owner,
+        } = value;
+        Self {
+            id: id.into(),
+            name,
+            main: main.into(),
+            hooks: vec_into(hooks),
+            order,
+            group,
+            owner: option_into(owner),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbActivityType {
+    id: i32,
+    name: String,
+    gear_type: i32,
+}
+
+impl From<ActivityType> for DbActivityType {
+    fn from(value: ActivityType) -> Self {
+        let ActivityType {
+            id,
+            name,
+            gear_type,
+        } = value;
+        Self {
+            id: id.into(),
+            name,
+            gear_type: gear_type.into(),
+        }
+    }
+}
+
+impl From<DbActivityType> for ActivityType {
+    fn from(value: DbActivityType) -> Self {
+        let DbActivityType {
+            id,
+            name,
+            gear_type,
+        } = value;
+        Self {
+            id: id.into(),
+            name,
+            gear_type: gear_type.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::TypeStore for SqlxConn<'c> {
+    async fn parttypes_get_all(&mut self) -> TbResult<Vec<PartType>> {
+        sqlx::query_as!(
+            DbPartType,
+            r#"SELECT id, name, main, hooks, "order", "group", owner FROM part_types ORDER BY id"#
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
+    async fn parttype_create(&mut self, parttype: PartType) -> TbResult<PartType> {
+        let parttype = DbPartType::from(parttype);
+        sqlx::query_as!(
+            DbPartType,
+            r#"INSERT INTO part_types (name, main, hooks, "order", "group", owner)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING id, name, main, hooks, "order", "group", owner"#,
+            parttype.name,
+            parttype.main,
+            &parttype.hooks,
+            parttype.order,
+            parttype.group,
+            parttype.owner
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn parttype_update(&mut self, parttype: PartType) -> TbResult<PartType> {
+        let parttype = DbPartType::from(parttype);
+        sqlx::query_as!(
+            DbPartType,
+            r#"UPDATE part_types
+               SET name = $2, main = $3, hooks = $4, "order" = $5, "group" = $6
+               WHERE id = $1
+               RETURNING id, name, main, hooks, "order", "group", owner"#,
+            parttype.id,
+            parttype.name,
+            parttype.main,
+            &parttype.hooks,
+            parttype.order,
+            parttype.group
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn parttype_delete(&mut self, id: PartTypeId) -> TbResult<usize> {
+        sqlx::query!("DELETE FROM part_types WHERE id = $1", i32::from(id))
+            .execute(&mut **self.inner())
+            .await
+            .map_err(into_domain)
+            .map(|r| r.rows_affected() as usize)
+    }
+
+    async fn parttype_in_use(&mut self, id: PartTypeId) -> TbResult<bool> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM parts WHERE what = $1)
+                   OR EXISTS (SELECT 1 FROM attachments WHERE hook = $1)
+                   OR EXISTS (SELECT 1 FROM service_plans WHERE what = $1 OR hook = $1)
+               AS "used!""#,
+            i32::from(id)
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+    }
+
+    async fn acttypes_get_all(&mut self) -> TbResult<Vec<ActivityType>> {
+        sqlx::query_as!(
+            DbActivityType,
+            "SELECT id, name, gear_type FROM activity_types ORDER BY id"
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
+    async fn acttype_create(&mut self, acttype: ActivityType) -> TbResult<ActivityType> {
+        let acttype = DbActivityType::from(acttype);
+        sqlx::query_as!(
+            DbActivityType,
+            "INSERT INTO activity_types (name, gear_type)
+             VALUES ($1, $2)
+             RETURNING id, name, gear_type",
+            acttype.name,
+            acttype.gear_type
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn acttype_update(&mut self, acttype: ActivityType) -> TbResult<ActivityType> {
+        let acttype = DbActivityType::from(acttype);
+        sqlx::query_as!(
+            DbActivityType,
+            "UPDATE activity_types
+             SET name = $2, gear_type = $3
+             WHERE id = $1
+             RETURNING id, name, gear_type",
+            acttype.id,
+            acttype.name,
+            acttype.gear_type
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn acttype_delete(&mut self, id: ActTypeId) -> TbResult<usize> {
+        sqlx::query!("DELETE FROM activity_types WHERE id = $1", i32::from(id))
+            .execute(&mut **self.inner())
+            .await
+            .map_err(into_domain)
+            .map(|r| r.rows_affected() as usize)
+    }
+
+    async fn acttype_in_use(&mut self, id: ActTypeId) -> TbResult<bool> {
+        sqlx::query_scalar!(
+            r#"SELECT EXISTS (SELECT 1 FROM activities WHERE what = $1) AS "used!""#,
+            i32::from(id)
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+    }
+}
@@ -0,0 +1,76 @@
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+use crate::{SqlxConn, into_domain};
+use tb_domain::{Error, TbResult, Undo, UserId};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbUndo {
+    owner: i32,
+    operation: String,
+    time: OffsetDateTime,
+    changes: String,
+}
+
+impl TryFrom<DbUndo> for Undo {
+    type Error = Error;
+
+    fn try_from(value: DbUndo) -> TbResult<Self> {
+        let DbUndo {
+            owner,
+            operation,
+            time,
+            changes,
+        } = value;
+        Ok(Self {
+            owner: owner.into(),
+            operation,
+            time,
+            changes: serde_json::from_str(&changes).map_err(|e| Error::AnyFailure(e.into()))?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::UndoStore for SqlxConn<'c> {
+    async fn undo_set(&mut self, undo: Undo) -> TbResult<()> {
+        let changes =
+            serde_json::to_string(&undo.changes).map_err(|e| Error::AnyFailure(e.into()))?;
+        sqlx::query!(
+            "INSERT INTO undos (owner, operation, time, changes)
+             VALUES ($1, $2, $3, $4::text::jsonb)
+             ON CONFLICT (owner) DO UPDATE
+             SET operation = $2, time = $3, changes = $4::text::jsonb",
+            i32::from(undo.owner),
+            undo.operation,
+            undo.time,
+            changes
+        )
+        .execute(&mut **self.inner())
+        .await
+        .map_err(into_domain)?;
+        Ok(())
+    }
+
+    async fn undo_get(&mut self, owner: UserId) -> TbResult<Option<Undo>> {
+        sqlx::query_as!(
+            DbUndo,
+            r#"SELECT owner, operation, time, changes::text AS "changes!"
+             FROM undos WHERE owner = $1"#,
+            i32::from(owner)
+        )
+        .fetch_optional(&mut **self.inner())
+        .await
+        .map_err(into_domain)?
+        .map(TryInto::try_into)
+        .transpose()
+    }
+
+    async fn undo_delete(&mut self, owner: UserId) -> TbResult<usize> {
+        let result = sqlx::query!("DELETE FROM undos WHERE owner = $1", i32::from(owner))
+            .execute(&mut **self.inner())
+            .await
+            .map_err(into_domain)?;
+        Ok(result.rows_affected() as usize)
+    }
+}
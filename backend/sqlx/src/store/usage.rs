@@ -3,8 +3,8 @@ use sqlx::{Acquire, FromRow};
 use std::borrow::Borrow;
 use uuid::Uuid;
 
-use crate::{SqlxConn, into_domain, option_into};
-use tb_domain::{TbResult, Usage, UsageId, UsageStore};
+use crate::{SqlxConn, into_domain, option_into, vec_into};
+use tb_domain::{SyncCursor, TbResult, Usage, UsageId, UsageStore, UserId};
 
 #[derive(Clone, Debug, PartialEq, Default, FromRow)]
 pub struct DbUsage {
@@ -150,4 +150,35 @@ impl<'c> UsageStore for SqlxConn<'c> {
 
         Ok(result.rows_affected() as usize)
     }
+
+    async fn usages_orphaned(&mut self) -> TbResult<Vec<UsageId>> {
+        sqlx::query_scalar!(
+            "SELECT id FROM usages u
+             WHERE NOT EXISTS (SELECT 1 FROM parts WHERE usage = u.id)
+               AND NOT EXISTS (SELECT 1 FROM attachments WHERE usage = u.id)
+               AND NOT EXISTS (SELECT 1 FROM services WHERE usage = u.id)"
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+
+    async fn usages_changed(&mut self, user: UserId, since: SyncCursor) -> TbResult<Vec<Usage>> {
+        sqlx::query_as!(
+            DbUsage,
+            r#"SELECT id, time, distance, climb, descend, energy as "energy!", count FROM usages
+               WHERE version >= $2 AND id IN (
+                   SELECT usage FROM parts WHERE owner = $1
+                   UNION SELECT a.usage FROM attachments a JOIN parts p ON a.part_id = p.id WHERE p.owner = $1
+                   UNION SELECT s.usage FROM services s JOIN parts p ON s.part_id = p.id WHERE p.owner = $1
+               )"#,
+            i32::from(user),
+            i64::from(since)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
 }
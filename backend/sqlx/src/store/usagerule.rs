@@ -0,0 +1,103 @@
+use sqlx::FromRow;
+
+use crate::{SqlxConn, into_domain, vec_into};
+use tb_domain::{ActTypeId, PartTypeId, TbResult, UsageRule, UsageRuleId, UserId};
+
+#[derive(Clone, Debug, PartialEq, FromRow)]
+struct DbUsageRule {
+    id: i32,
+    owner: i32,
+    what: i32,
+    hook: i32,
+    factor: f64,
+}
+
+impl From<DbUsageRule> for UsageRule {
+    fn from(value: DbUsageRule) -> Self {
+        let DbUsageRule {
+            id,
+            owner,
+            what,
+            hook,
+            factor,
+        } = value;
+        Self {
+            id: id.into(),
+            owner: owner.into(),
+            what: what.into(),
+            hook: hook.into(),
+            factor,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'c> tb_domain::UsageRuleStore for SqlxConn<'c> {
+    async fn usage_rule_create(
+        &mut self,
+        owner: UserId,
+        what: ActTypeId,
+        hook: PartTypeId,
+        factor: f64,
+    ) -> TbResult<UsageRule> {
+        sqlx::query_as!(
+            DbUsageRule,
+            "INSERT INTO usage_rules (owner, what, hook, factor)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *",
+            i32::from(owner),
+            i32::from(what),
+            i32::from(hook),
+            factor
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn usage_rule_get(&mut self, id: UsageRuleId) -> TbResult<UsageRule> {
+        sqlx::query_as!(
+            DbUsageRule,
+            "SELECT * FROM usage_rules WHERE id = $1",
+            i32::from(id)
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn usage_rule_update(&mut self, rule: UsageRule) -> TbResult<UsageRule> {
+        sqlx::query_as!(
+            DbUsageRule,
+            "UPDATE usage_rules SET factor = $2 WHERE id = $1 RETURNING *",
+            i32::from(rule.id),
+            rule.factor
+        )
+        .fetch_one(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(Into::into)
+    }
+
+    async fn usage_rule_delete(&mut self, id: UsageRuleId) -> TbResult<usize> {
+        let result = sqlx::query!("DELETE FROM usage_rules WHERE id = $1", i32::from(id))
+            .execute(&mut **self.inner())
+            .await
+            .map_err(into_domain)?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn usage_rules_for_user(&mut self, user: UserId) -> TbResult<Vec<UsageRule>> {
+        sqlx::query_as!(
+            DbUsageRule,
+            "SELECT * FROM usage_rules WHERE owner = $1 ORDER BY what, hook",
+            i32::from(user)
+        )
+        .fetch_all(&mut **self.inner())
+        .await
+        .map_err(into_domain)
+        .map(vec_into)
+    }
+}
@@ -1,6 +1,6 @@
 use sqlx::FromRow;
 
-use crate::{SqlxConn, into_domain};
+use crate::{SqlxConn, into_domain, vec_into};
 use tb_domain::{OnboardingStatus, TbResult, User, UserId};
 
 #[derive(Clone, Debug, FromRow)]
@@ -127,6 +127,14 @@ impl<'c> tb_domain::UserStore for SqlxConn<'c> {
         Ok(result.rows_affected() as usize)
     }
 
+    async fn user_ids_get_all(&mut self) -> TbResult<Vec<UserId>> {
+        sqlx::query_scalar!("SELECT id FROM users ORDER BY id")
+            .fetch_all(&mut **self.inner())
+            .await
+            .map_err(into_domain)
+            .map(vec_into)
+    }
+
     async fn update_onboarding_status(
         &mut self,
         uid: &UserId,
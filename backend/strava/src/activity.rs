@@ -50,16 +50,16 @@ impl StravaActivity {
     /// # Arguments
     ///
     /// * `self` - A StravaActivity struct that represents an activity from Strava API.
-    /// * `user` - A reference to a StravaUser struct that represents the user who performed the activity.
+    /// * `source` - The activity source of the user who performed the activity.
     /// * `store` - A mutable reference to an AppConn struct that represents a connection to the Tendabike API.
     ///
     /// # Returns
     ///
     /// A Result containing a Activity struct if the conversion was successful, or an error if it failed.
-    async fn into_activity(
+    pub(crate) async fn into_activity<S: StravaStore>(
         self,
-        user: &mut impl StravaSession,
-        store: &mut impl StravaStore,
+        source: &mut impl ActivitySource<S>,
+        store: &mut S,
     ) -> TbResult<Activity> {
         let StravaActivity {
             id,
@@ -81,14 +81,19 @@ impl StravaActivity {
         let what = Self::get_type(&type_)?;
         let gear = match gear_id {
             // cannot use map due to async closure
-            Some(x) => Some(gear::into_partid(x, user, store).await?),
+            Some(x) => Some(source.gear_to_partid(&x, store).await?),
             None => None,
         };
-        Ok(Activity {
+        // Strava only knows the primary gear, keep the further gears
+        let old = store.activity_read_by_id(id.into()).await?;
+        let use_rules = old.is_none() && gear.is_none();
+        let gears = old.map(|a| a.gears).unwrap_or_default();
+        let mut activity = Activity {
             id: id.into(),
             what,
             gear,
-            user_id: user.tb_id(),
+            gears,
+            user_id: source.user_id(),
             name,
             start: start_date.to_offset(offset),
             duration: elapsed_time,
@@ -99,7 +104,12 @@ impl StravaActivity {
             energy: kilojoules.map(|e| e.round() as i32),
             device_name,
             external_id,
-        })
+        };
+        // the gear rules only fill in the gear of new activities
+        if use_rules {
+            GearRule::assign(&mut activity, store).await?;
+        }
+        Ok(activity)
     }
 
     /// Maps Strava workout type strings to Tendabike types.
@@ -172,9 +182,10 @@ impl StravaActivity {
         user: &mut impl StravaSession,
         store: &mut impl StravaStore,
     ) -> TbResult<Summary> {
-        let activity = self.into_activity(user, store).await?;
+        let mut source = StravaSource(user);
+        let activity = self.into_activity(&mut source, store).await?;
 
-        activity.upsert(user, store).await
+        activity.upsert(&source, store).await
     }
 }
 
@@ -192,10 +203,9 @@ pub async fn upsert_activity(
     user: &mut impl StravaSession,
     store: &mut impl StravaStore,
 ) -> TbResult<Summary> {
-    let act: StravaActivity = user
-        .request_json(&format!("/activities/{id}"), store)
-        .await?;
-    act.send_to_tb(user, store).await
+    StravaSource(user)
+        .sync_activity(&id.to_string(), store)
+        .await
 }
 
 pub(crate) async fn delete_activity(
@@ -1,9 +1,7 @@
 //! This module contains the implementation of StravaGear, a struct that represents a gear object from Strava API.
-//! It also contains functions to convert StravaGear to Tendabike's Part object and to map Strava gear_id to Tendabike gear_id.
+//! It also contains the conversion of StravaGear to the provider neutral SourceGear.
 //!
 
-use time::OffsetDateTime;
-
 use crate::*;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -41,41 +39,14 @@ impl StravaGear {
     }
 }
 
-/// map strava gear_id to tb gear_id
-///
-/// If it does not exist create it at tb
-/// None will return None
-pub(crate) async fn into_partid(
-    strava_id: String,
-    user: &mut impl StravaSession,
-    store: &mut impl StravaStore,
-) -> TbResult<PartId> {
-    if let Some(gear) = store.partid_get_by_source(&strava_id).await? {
-        return Ok(gear);
-    }
-
-    debug!("New Gear");
-    let gear = user
-        .request_json::<StravaGear>(&format!("/gear/{}", &strava_id), store)
-        .await
-        .context("Couldn't map gear")?;
-
-    // maybe the gear was created by now?
-    if let Some(gear) = store.partid_get_by_source(&strava_id).await? {
-        return Ok(gear);
+impl From<StravaGear> for SourceGear {
+    fn from(gear: StravaGear) -> Self {
+        SourceGear {
+            what: gear.what(),
+            id: gear.id,
+            name: gear.name,
+            vendor: gear.brand_name.unwrap_or_default(),
+            model: gear.model_name.unwrap_or_default(),
+        }
     }
-
-    let what = gear.what();
-    let source = Some(gear.id);
-    let vendor = gear.brand_name.unwrap_or("".into());
-    let model = gear.model_name.unwrap_or("".into());
-    let name = gear.name;
-    let purchase = OffsetDateTime::now_utc();
-    let notes = String::new();
-    let tbid = Part::create(
-        name, vendor, model, what, source, purchase, notes, user, store,
-    )
-    .await?
-    .id;
-    Ok(tbid)
 }
@@ -7,6 +7,7 @@
 //! - `activity`: contains functionality to fetch user activities from Strava API.
 //! - `event`: contains functionality to handle Strava webhook events.
 //! - `gear`: contains functionality to fetch user gear data from Strava API.
+//! - `source`: implements the provider neutral `ActivitySource` for Strava.
 //! - `user`: contains functionality to fetch user data from Strava API and manage user authentication.
 //!
 //! The module also contains some utility functions and imports used across the submodules.
@@ -27,6 +28,9 @@ pub mod activity;
 pub mod event;
 pub mod gear;
 
+mod source;
+pub use source::*;
+
 mod user;
 pub use user::*;
 
@@ -0,0 +1,61 @@
+//! This module implements the provider neutral `ActivitySource` for Strava.
+//!
+//! `StravaSource` wraps a `StravaSession` and maps the Strava API objects to the domain model.
+//! The generic sync logic like mapping gear ids to parts lives in `tb_domain`.
+
+use crate::activity::StravaActivity;
+use crate::gear::StravaGear;
+use crate::*;
+
+/// Strava as a source for activities and gear
+pub struct StravaSource<'a, U>(pub &'a mut U);
+
+impl<U: StravaSession> Session for StravaSource<'_, U> {
+    fn user_id(&self) -> UserId {
+        self.0.user_id()
+    }
+
+    fn shop(&self) -> Option<ShopId> {
+        self.0.shop()
+    }
+
+    fn set_shop(&mut self, shop: Option<ShopId>) -> TbResult<()> {
+        self.0.set_shop(shop)
+    }
+
+    fn is_admin(&self) -> bool {
+        self.0.is_admin()
+    }
+
+    fn actor(&self) -> UserId {
+        self.0.actor()
+    }
+}
+
+#[async_trait::async_trait]
+impl<U: StravaSession, S: StravaStore> ActivitySource<S> for StravaSource<'_, U> {
+    async fn authorize(&mut self, store: &mut S) -> TbResult<()> {
+        self.0.authorize(store).await
+    }
+
+    async fn deauthorize(&mut self, store: &mut S) -> TbResult<()> {
+        self.0.deauthorize(store).await
+    }
+
+    async fn fetch_activity(&mut self, id: &str, store: &mut S) -> TbResult<Activity> {
+        let act: StravaActivity = self
+            .0
+            .request_json(&format!("/activities/{id}"), store)
+            .await?;
+        act.into_activity(self, store).await
+    }
+
+    async fn fetch_gear(&mut self, id: &str, store: &mut S) -> TbResult<SourceGear> {
+        let gear: StravaGear = self
+            .0
+            .request_json(&format!("/gear/{id}"), store)
+            .await
+            .context("Couldn't map gear")?;
+        Ok(gear.into())
+    }
+}
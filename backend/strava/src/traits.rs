@@ -225,6 +225,9 @@ pub trait StravaSession: Session {
         self.user_id()
     }
 
+    /// Makes sure the access token is valid, refreshing it if needed.
+    async fn authorize(&mut self, store: &mut impl StravaStore) -> TbResult<()>;
+
     async fn request_json<T: DeserializeOwned>(
         &mut self,
         uri: &str,
@@ -164,7 +164,7 @@ impl StravaUser {
 
         let mut parts = Vec::new();
         for gear in ath.bikes.into_iter().chain(ath.shoes) {
-            parts.push(gear::into_partid(gear.id, user, store).await?);
+            parts.push(StravaSource(user).gear_to_partid(&gear.id, store).await?);
         }
 
         Ok(parts)
@@ -212,7 +212,7 @@ pub async fn user_deauthorize(
     user: &mut impl StravaSession,
     store: &mut impl StravaStore,
 ) -> TbResult<()> {
-    if let Err(err) = user.deauthorize(store).await {
+    if let Err(err) = StravaSource(user).deauthorize(store).await {
         warn!("could not deauthorize user {}: {:#}", user.tb_id(), err)
     }
 